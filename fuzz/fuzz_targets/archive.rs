@@ -0,0 +1,37 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate top_tree_compression;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::process;
+
+use top_tree_compression::top_dag::TopDag;
+use top_tree_compression::compressed::CompressedTopDag;
+use top_tree_compression::validation::DecodeLimits;
+
+//the input is the raw content of an archive file as written by save_to_file
+//it goes through the same path as an untrusted file: extracting, reading the sections and validating them
+fuzz_target!(|data: &[u8]| {
+    let directory = env::temp_dir().join(format!("top_tree_fuzz_{}", process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("archive");
+    let path = path.to_str().unwrap();
+    File::create(format!("{}.tar", path)).unwrap().write_all(data).unwrap();
+
+    let limits = DecodeLimits {
+        max_clusters: 1 << 10,
+        max_labels: 1 << 10,
+        max_label_bytes: 1 << 16,
+        max_decompressed_nodes: 1 << 16,
+    };
+
+    if let Ok(top_dag) = TopDag::<Vec<u8>>::new_fom_file_with_limits(path, &limits) {
+        //everything the loader accepts has to decompress without panicking
+        top_dag.get_IO_tree();
+    }
+    if let Ok(compressed_top_dag) = CompressedTopDag::new_fom_file_with_limits(path, &limits) {
+        compressed_top_dag.get_IO_tree();
+    }
+});
@@ -0,0 +1,21 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate top_tree_compression;
+
+use top_tree_compression::compressed::CompressedTopDag;
+use top_tree_compression::validation::DecodeLimits;
+
+//the input is the raw content of a container file as written by save_to_container
+fuzz_target!(|data: &[u8]| {
+    let limits = DecodeLimits {
+        max_clusters: 1 << 10,
+        max_labels: 1 << 10,
+        max_label_bytes: 1 << 16,
+        max_decompressed_nodes: 1 << 16,
+    };
+
+    if let Ok(compressed_top_dag) = CompressedTopDag::from_reader(&mut &data[..], &limits) {
+        //everything the loader accepts has to decompress without panicking
+        compressed_top_dag.get_IO_tree();
+    }
+});
@@ -0,0 +1,41 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate top_tree_compression;
+
+use top_tree_compression::top_dag::TopDag;
+use top_tree_compression::validation::DecodeLimits;
+
+//the input is split into the four streams of an archive:
+//[number of clusters: u8][number of pointers: u8][structure bits][pointers: u8 each][merge types: u8 each][labels separated by '\n']
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 2 { return }
+    let number_of_clusters = data[0] as usize;
+    let number_of_pointers = data[1] as usize;
+    let mut rest = &data[2..];
+
+    let structure_bytes = (number_of_clusters * 2 + 7) / 8;
+    if rest.len() < structure_bytes + number_of_pointers + number_of_clusters { return }
+
+    let structure = (0..number_of_clusters * 2).map(|index| rest[index / 8] & (1 << (index % 8)) != 0).collect();
+    rest = &rest[structure_bytes..];
+
+    let pointer = rest[..number_of_pointers].iter().map(|elem| *elem as usize).collect();
+    rest = &rest[number_of_pointers..];
+
+    let merge_type = rest[..number_of_clusters].iter().map(|elem| *elem as i32).collect();
+    rest = &rest[number_of_clusters..];
+
+    let label = rest.split(|byte| *byte == b'\n').map(|elem| elem.to_vec()).collect();
+
+    let limits = DecodeLimits {
+        max_clusters: 1 << 10,
+        max_labels: 1 << 10,
+        max_label_bytes: 1 << 16,
+        max_decompressed_nodes: 1 << 16,
+    };
+
+    if let Ok(top_dag) = TopDag::<Vec<u8>>::from_streams(structure, pointer, merge_type, label, &limits) {
+        //everything the loader accepts has to decompress without panicking
+        top_dag.get_IO_tree();
+    }
+});
@@ -0,0 +1,285 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::mem::size_of;
+
+use quick_xml::Reader;
+use quick_xml::events::Event;
+
+use io_tree::{IO_Tree, ParseError, GenResult};
+use label::Label;
+use memory::{MemoryEstimate, MemoryError};
+
+
+
+///marks a missing first child or next sibling
+const NO_NODE: usize = usize::max_value();
+
+#[derive(Clone, Debug)]
+struct ArenaNode {
+    ///the id of the label in the label vector
+    label: usize,
+    first_child: usize,
+    last_child: usize,
+    next_sibling: usize,
+    number_of_children: usize,
+}
+
+///A tree stored in flat arrays
+///
+///Nodes are referenced by their index, the root is node 0.
+///Every node knows its first child and its next sibling, so no node owns an allocation.
+///Labels are interned, every distinct label is stored once and nodes store its id.
+pub struct ArenaTree<L: Label = Vec<u8>> {
+    nodes: Vec<ArenaNode>,
+
+    labels: HashMap<L, usize>,
+    label_vector: Vec<L>,
+}
+
+impl ArenaTree {
+    ///reads the tree without building an IO_Tree first
+    pub fn new_from_xml(path: &str) -> GenResult<ArenaTree> {
+        let file = File::open(path)?;
+        ArenaTree::from_xml_reader(BufReader::new(file))
+    }
+
+    ///like new_from_xml but reads from any buffered source, for example stdin
+    pub fn from_xml_reader<R: BufRead>(source: R) -> GenResult<ArenaTree> {
+        ArenaTree::from_xml_reader_with_limit(source, None)
+    }
+
+    ///stops with a MemoryError as soon as the parsed nodes and the builder for them would exceed memory_limit bytes
+    pub fn from_xml_reader_with_limit<R: BufRead>(source: R, memory_limit: Option<usize>) -> GenResult<ArenaTree> {
+        let mut reader = Reader::from_reader(source);
+
+        let mut buf = Vec::new();
+
+        let mut tree = ArenaTree::new();
+        //the open nodes, the last one is the parent of the next node
+        let mut node_stack: Vec<usize> = Vec::new();
+
+        'filereader: loop {
+            match reader.read_event(&mut buf) {
+                Ok(Event::Start(ref elem)) => {
+                    let parent = node_stack.last().cloned();
+                    //only the root has no parent
+                    if parent.is_none() && !tree.is_empty() {return Err(Box::new(ParseError::CannotParse));}
+                    if let Some(limit) = memory_limit {
+                        tree.check_memory(limit)?;
+                    }
+
+                    let node = tree.push_ref(parent, elem.name());
+                    node_stack.push(node);
+                },
+
+                Ok(Event::End(ref elem)) => {
+                    if let Some(node) = node_stack.pop() {
+                        if &tree.label(node)[..] != elem.name() {return Err(Box::new(ParseError::CannotParse));}
+                    } else {
+                        return Err(Box::new(ParseError::CannotParse));
+                    }
+                },
+
+                Ok(Event::Eof) => break 'filereader,
+
+                Err(_) => {
+                    return Err(Box::new(ParseError::CannotParse))
+                },
+
+                _ => (), //the same events as in IO_Tree::new_from_xml are ignored
+            }
+
+            buf.clear();
+        }
+
+        if tree.is_empty() || !node_stack.is_empty() {
+            return Err(Box::new(ParseError::CannotParse));
+        }
+        Ok(tree)
+    }
+
+    ///the arena with one more node and the builder for all of its nodes have to fit into the limit
+    fn check_memory(&self, limit: usize) -> std::result::Result<(), MemoryError> {
+        let number_of_nodes = self.nodes.len() + 1;
+        let needed = MemoryEstimate::while_parsing(number_of_nodes).total()
+            .saturating_add(number_of_nodes.saturating_mul(size_of::<ArenaNode>()));
+        if needed > limit { return Err(MemoryError::BudgetExceeded { needed, limit }) }
+        Ok(())
+    }
+
+    ///like push but the label is only copied the first time it is seen
+    fn push_ref(&mut self, parent: Option<usize>, label: &[u8]) -> usize {
+        let label_id = match self.labels.get(label) {
+            Some(label_id) => *label_id,
+            None => self.insert_label(label.to_vec()),
+        };
+        self.push_node(parent, label_id)
+    }
+}
+
+impl<L: Label> ArenaTree<L> {
+    pub fn new() -> ArenaTree<L> {
+        ArenaTree {
+            nodes: Vec::new(),
+
+            labels: HashMap::new(),
+            label_vector: Vec::new(),
+        }
+    }
+
+    ///converts the tree, the nodes get their index in preorder
+    #[allow(non_snake_case)]
+    pub fn from_IO_tree(tree: &IO_Tree<L>) -> ArenaTree<L> {
+        let mut arena_tree = ArenaTree::new();
+
+        //(node, parent in the arena)
+        let mut stack = vec![(tree, None)];
+        while let Some((node, parent)) = stack.pop() {
+            let index = arena_tree.push(parent, node.label.clone());
+            //reversed so the first child is taken from the stack first
+            stack.extend(node.children.iter().rev().map(|child| (child, Some(index))));
+        }
+        arena_tree
+    }
+
+    ///adds a node as the last child of parent, the first node without a parent is the root
+    ///returns the index of the node
+    pub fn push(&mut self, parent: Option<usize>, label: L) -> usize {
+        let label_id = match self.labels.get(&label) {
+            Some(label_id) => *label_id,
+            None => self.insert_label(label),
+        };
+        self.push_node(parent, label_id)
+    }
+
+    fn insert_label(&mut self, label: L) -> usize {
+        let label_id = self.label_vector.len();
+        self.label_vector.push(label.clone());
+        self.labels.insert(label, label_id);
+        label_id
+    }
+
+    fn push_node(&mut self, parent: Option<usize>, label_id: usize) -> usize {
+        let node = self.nodes.len();
+        match parent {
+            Some(parent) => {
+                let last_child = self.nodes[parent].last_child;
+                if last_child == NO_NODE {
+                    self.nodes[parent].first_child = node;
+                } else {
+                    self.nodes[last_child].next_sibling = node;
+                }
+                self.nodes[parent].last_child = node;
+                self.nodes[parent].number_of_children += 1;
+            },
+
+            None => if !self.nodes.is_empty() {panic!("Error: The tree already has a root")},
+        }
+
+        self.nodes.push(ArenaNode {
+            label: label_id,
+            first_child: NO_NODE,
+            last_child: NO_NODE,
+            next_sibling: NO_NODE,
+            number_of_children: 0,
+        });
+        node
+    }
+
+    ///builds the IO_Tree of the same shape
+    #[allow(non_snake_case)]
+    pub fn to_IO_tree(&self) -> IO_Tree<L> {
+        assert!(!self.is_empty(), "Error: The tree has no node");
+
+        //(node, next child that is not converted jet, converted children)
+        let mut stack = vec![(0, self.nodes[0].first_child, VecDeque::with_capacity(self.nodes[0].number_of_children))];
+        loop {
+            let (node, next_child) = {
+                let (node, next_child, _) = stack.last().unwrap();
+                (*node, *next_child)
+            };
+
+            if next_child != NO_NODE {
+                stack.last_mut().unwrap().1 = self.nodes[next_child].next_sibling;
+                stack.push((next_child, self.nodes[next_child].first_child, VecDeque::with_capacity(self.nodes[next_child].number_of_children)));
+            } else {
+                //all children are converted so the node is finished
+                let (_, _, children) = stack.pop().unwrap();
+                let tree = IO_Tree { label: self.label(node).clone(), children };
+                match stack.last_mut() {
+                    Some((_, _, siblings)) => siblings.push_back(tree),
+                    None => return tree,
+                }
+            }
+        }
+    }
+
+    pub fn root(&self) -> Option<usize> {
+        if self.nodes.is_empty() { None } else { Some(0) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn number_of_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn label(&self, node: usize) -> &L {
+        &self.label_vector[self.nodes[node].label]
+    }
+
+    ///the id of the label of the node in labels
+    pub fn label_id(&self, node: usize) -> usize {
+        self.nodes[node].label
+    }
+
+    ///all distinct labels, the index is the id of the label
+    pub fn labels(&self) -> &[L] {
+        &self.label_vector
+    }
+
+    pub fn first_child(&self, node: usize) -> Option<usize> {
+        to_option(self.nodes[node].first_child)
+    }
+
+    pub fn next_sibling(&self, node: usize) -> Option<usize> {
+        to_option(self.nodes[node].next_sibling)
+    }
+
+    pub fn number_of_children(&self, node: usize) -> usize {
+        self.nodes[node].number_of_children
+    }
+
+    pub fn children(&self, node: usize) -> Children<L> {
+        Children { tree: self, next: self.nodes[node].first_child }
+    }
+
+    ///returns the number of inner nodes and the number of leafs
+    pub fn count(&self) -> (usize, usize) {
+        let number_of_leafs = self.nodes.iter().filter(|node| node.number_of_children == 0).count();
+        (self.nodes.len() - number_of_leafs, number_of_leafs)
+    }
+}
+
+///Iterates over the children of a node in order
+pub struct Children<'a, L: Label + 'a> {
+    tree: &'a ArenaTree<L>,
+    next: usize,
+}
+
+impl<'a, L: Label> Iterator for Children<'a, L> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let current = to_option(self.next)?;
+        self.next = self.tree.nodes[current].next_sibling;
+        Some(current)
+    }
+}
+
+fn to_option(node: usize) -> Option<usize> {
+    if node == NO_NODE { None } else { Some(node) }
+}
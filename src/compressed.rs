@@ -0,0 +1,433 @@
+use std::borrow::Cow;
+
+use structs::MergeType;
+use io_tree::{IO_Tree, GenResult};
+use validation::{DecodeLimits, DecodeError, check_stream_lengths, check_single_node};
+use format::{Streams, SuccinctStreams, open_archive, load_succinct_streams};
+use succinct::{BitVector, IntVector, LabelStore};
+use label_encoding::{FrontCodedDictionary, LabelDictionary};
+use expand::{Expand, Expansion, expand_tree};
+use shared_tree::{SharedTree, share_tree};
+use flags_and_statistic::{SizeReport, SizeKind};
+
+
+
+///number of clusters summarized by one entry of the excess tables
+const EXCESS_BLOCK: usize = 128;
+
+///A read only TopDAG that answers queries on the encoded streams
+///
+///Ids are the ones of the archive: ids below number_of_labels are labels,
+///the id number_of_labels + i is the i-th cluster in preorder of the traversal, the root is the first one.
+///A tree of a single node has no cluster, the only pointer is its label and the root.
+///A child that is a true cluster is found with rank and a scan over the structure bits,
+///every other child is read from the pointer stream, so nothing is expanded in memory.
+///
+///Memory for c clusters and l labels, in bits: the structure takes 2c and an eighth of that for the rank samples,
+///each of the c + 1 pointers ceil(log2(l + c)), a merge type 2 and the excess tables about 16 per 128 clusters.
+///Plain labels take their bytes plus one offset per label, a front coded dictionary stays as it is in the archive.
+///new_fom_file copies the sdsl vectors in chunks, so while loading only the compressed sdsl vectors,
+///the parts above and a buffer of a few thousand values are in memory. Wavelet tree labels are decoded completely.
+///The sdsl vectors of the last loaded archive stay in memory until the next archive is loaded or saved.
+pub struct CompressedTopDag<S: AsRef<[u64]> = Vec<u64>> {
+    ///two bits per cluster, set if the child is a true cluster that follows in preorder
+    structure: BitVector<S>,
+    ///for every block of clusters the negated minimal excess inside of the block
+    block_min_excess: IntVector<S>,
+    ///for every block of clusters the excess at the end of the block plus EXCESS_BLOCK
+    block_excess: IntVector<S>,
+    ///one entry for every zero in structure
+    pointer: IntVector<S>,
+    merge_type: IntVector<S>,
+    labels: CompressedLabels<S>,
+    ///counted once by the validation
+    number_of_nodes: usize,
+}
+
+///The labels of a CompressedTopDag
+///a front coded archive keeps its dictionary, a container always stores the labels one after another
+pub enum CompressedLabels<S: AsRef<[u64]> = Vec<u64>> {
+    Store(LabelStore<S>),
+
+    FrontCoded(FrontCodedDictionary),
+}
+
+impl<S: AsRef<[u64]>> CompressedLabels<S> {
+    pub fn len(&self) -> usize {
+        match self {
+            CompressedLabels::Store(store) => store.len(),
+            CompressedLabels::FrontCoded(dictionary) => dictionary.len(),
+        }
+    }
+
+    pub fn get(&self, id: usize) -> Option<Cow<[u8]>> {
+        match self {
+            CompressedLabels::Store(store) => store.get(id).map(Cow::Borrowed),
+            CompressedLabels::FrontCoded(dictionary) => dictionary.get(id).map(Cow::Owned),
+        }
+    }
+
+    pub fn size_in_bits(&self) -> usize {
+        match self {
+            CompressedLabels::Store(store) => store.size_in_bits(),
+            CompressedLabels::FrontCoded(dictionary) => dictionary.size_in_bits(),
+        }
+    }
+
+    ///a front coded dictionary was checked completely when it was deserialized
+    fn check(&self) -> bool {
+        match self {
+            CompressedLabels::Store(store) => store.check(),
+            CompressedLabels::FrontCoded(_) => true,
+        }
+    }
+}
+
+impl CompressedTopDag {
+    pub fn new_fom_file(path: &str) -> GenResult<CompressedTopDag> {
+        CompressedTopDag::new_fom_file_with_limits(path, &DecodeLimits::default())
+    }
+
+    pub fn new_fom_file_with_limits(path: &str, limits: &DecodeLimits) -> GenResult<CompressedTopDag> {
+        let (_meta, streams) = open_archive(path, |directory| load_succinct_streams(directory, limits))?;
+        Ok(CompressedTopDag::from_succinct_streams(streams, limits)?)
+    }
+
+    ///the streams are fully validated so untrusted input returns an error instead of panicking
+    pub fn from_streams(streams: &Streams, limits: &DecodeLimits) -> Result<CompressedTopDag, DecodeError> {
+        check_stream_lengths(&streams.structure, &streams.pointer, &streams.merge_type, streams.label.len(), limits)?;
+
+        let mut merge_type = Vec::with_capacity(streams.merge_type.len());
+        for elem in &streams.merge_type {
+            if MergeType::from_i32(*elem).is_none() { return Err(DecodeError::UnknownMergeType(*elem)) }
+            merge_type.push(*elem as usize);
+        }
+
+        CompressedTopDag::from_succinct_streams(SuccinctStreams {
+            structure: BitVector::new(&streams.structure),
+            pointer: IntVector::new(&streams.pointer),
+            merge_type: IntVector::with_width(&merge_type, 2),
+            label: streams.label.clone(),
+        }, limits)
+    }
+
+    pub(crate) fn from_succinct_streams(streams: SuccinctStreams, limits: &DecodeLimits) -> Result<CompressedTopDag, DecodeError> {
+        let SuccinctStreams { structure, pointer, merge_type, label } = streams;
+        let number_of_clusters = merge_type.len();
+        if label.len() == 0 { return Err(DecodeError::Empty) }
+        //validate needs the lengths of from_parts
+        if structure.len() != number_of_clusters * 2 || pointer.len() != number_of_clusters + 1 { return Err(DecodeError::LengthMismatch) }
+
+        let (block_min_excess, block_excess) = build_excess_tables(&structure, number_of_clusters);
+        let mut compressed_top_dag = CompressedTopDag {
+            structure,
+            block_min_excess,
+            block_excess,
+            pointer,
+            merge_type,
+            labels: match label {
+                LabelDictionary::Plain(labels) => CompressedLabels::Store(LabelStore::new(&labels)),
+                LabelDictionary::FrontCoded(dictionary) => CompressedLabels::FrontCoded(dictionary),
+            },
+            number_of_nodes: 0,
+        };
+
+        compressed_top_dag.number_of_nodes = compressed_top_dag.validate(limits)?;
+        Ok(compressed_top_dag)
+    }
+}
+
+impl<S: AsRef<[u64]>> CompressedTopDag<S> {
+    ///the parts are validated completely, so untrusted input returns an error instead of panicking
+    pub fn from_parts(structure: BitVector<S>, block_min_excess: IntVector<S>, block_excess: IntVector<S>, pointer: IntVector<S>, merge_type: IntVector<S>, labels: LabelStore<S>, limits: &DecodeLimits) -> Result<CompressedTopDag<S>, DecodeError> {
+        let number_of_clusters = merge_type.len();
+        let number_of_blocks = (number_of_clusters + EXCESS_BLOCK - 1) / EXCESS_BLOCK;
+        if     structure.len() != number_of_clusters * 2
+            || block_min_excess.len() != number_of_blocks
+            || block_excess.len() != number_of_blocks
+            || pointer.len() != number_of_clusters + 1 {
+            return Err(DecodeError::LengthMismatch);
+        }
+
+        let mut compressed_top_dag = CompressedTopDag { structure, block_min_excess, block_excess, pointer, merge_type, labels: CompressedLabels::Store(labels), number_of_nodes: 0 };
+        compressed_top_dag.number_of_nodes = compressed_top_dag.validate(limits)?;
+        Ok(compressed_top_dag)
+    }
+
+    pub fn parts(&self) -> (&BitVector<S>, &IntVector<S>, &IntVector<S>, &IntVector<S>, &IntVector<S>, &CompressedLabels<S>) {
+        (&self.structure, &self.block_min_excess, &self.block_excess, &self.pointer, &self.merge_type, &self.labels)
+    }
+
+    pub fn number_of_labels(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn number_of_clusters(&self) -> usize {
+        self.merge_type.len()
+    }
+
+    ///number of nodes of the decompressed tree
+    pub fn number_of_nodes(&self) -> usize {
+        self.number_of_nodes
+    }
+
+    ///the memory used by every part, the excess tables count to the structure
+    ///unlike the report of save_to_file it measures the in-memory representation, not the archive files
+    pub fn size_report(&self) -> SizeReport {
+        SizeReport {
+            kind: SizeKind::InMemory,
+            structure_bits: self.structure.size_in_bits() + self.block_min_excess.size_in_bits() + self.block_excess.size_in_bits(),
+            pointer_bits: self.pointer.size_in_bits(),
+            merge_type_bits: self.merge_type.size_in_bits(),
+            label_bits: self.labels.size_in_bits(),
+            number_of_nodes: self.number_of_nodes(),
+            number_of_clusters: self.number_of_clusters(),
+        }
+    }
+
+    pub fn root(&self) -> usize {
+        if self.number_of_clusters() == 0 {
+            self.pointer.get(0)
+        } else {
+            self.number_of_labels()
+        }
+    }
+
+    pub fn is_label(&self, id: usize) -> bool {
+        id < self.number_of_labels()
+    }
+
+    ///None if the id is no label
+    pub fn label(&self, id: usize) -> Option<Cow<[u8]>> {
+        self.labels.get(id)
+    }
+
+    ///None if the id is no cluster
+    pub fn merge_type(&self, id: usize) -> Option<MergeType> {
+        let index = self.cluster_index(id)?;
+        MergeType::from_i32(self.merge_type.get(index) as i32)
+    }
+
+    ///the ids of the first and the second child, None if the id is no cluster
+    ///an error if the structure has no end for the subtree of the first child
+    pub fn children(&self, id: usize) -> Result<Option<(usize, usize)>, DecodeError> {
+        let index = match self.cluster_index(id) {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+        let number_of_labels = self.number_of_labels();
+
+        let first_child = if self.structure.get(index * 2) {
+            number_of_labels + index + 1
+        } else {
+            self.pointer.get(self.structure.rank0(index * 2))
+        };
+
+        let second_child = if self.structure.get(index * 2 + 1) {
+            if self.structure.get(index * 2) {
+                //the second child follows the subtree of the first child
+                number_of_labels + self.subtree_end(index + 1)? + 1
+            } else {
+                number_of_labels + index + 1
+            }
+        } else {
+            self.pointer.get(self.structure.rank0(index * 2 + 1))
+        };
+
+        Ok(Some((first_child, second_child)))
+    }
+
+    #[allow(non_snake_case)]
+    pub fn get_IO_tree(&self) -> Result<IO_Tree, DecodeError> {
+        expand_tree(self, self.root())
+    }
+
+    ///the decompressed tree with every distinct subtree stored once
+    pub fn get_shared_tree(&self) -> Result<SharedTree<Vec<u8>>, DecodeError> {
+        share_tree(self, self.root())
+    }
+
+    ///checks that the TopDAG decompresses to the tree, the shared subtrees are not expanded
+    ///the error is the first node in preorder that differs as a Mismatch, or a DecodeError
+    pub fn verify_against(&self, tree: &IO_Tree) -> GenResult<()> {
+        Ok(self.get_shared_tree()?.verify_against(tree)?)
+    }
+
+    ///like verify_against but streams the xml, so the input tree is never in memory
+    pub fn verify_against_xml(&self, path: &str) -> GenResult<()> {
+        self.get_shared_tree()?.verify_against_xml(path)
+    }
+
+    fn cluster_index(&self, id: usize) -> Option<usize> {
+        let index = id.checked_sub(self.number_of_labels())?;
+        if index < self.number_of_clusters() { Some(index) } else { None }
+    }
+
+    ///number of true cluster children minus one
+    fn excess_step(&self, index: usize) -> isize {
+        self.structure.get(index * 2) as isize + self.structure.get(index * 2 + 1) as isize - 1
+    }
+
+    ///the last cluster of the traversal subtree that starts at index
+    ///that is the first position where the excess from index on drops to -1
+    fn subtree_end(&self, start: usize) -> Result<usize, DecodeError> {
+        let number_of_clusters = self.number_of_clusters();
+        let mut excess = 0;
+
+        //scan the rest of the first block
+        let mut index = start;
+        let block_end = ((start / EXCESS_BLOCK + 1) * EXCESS_BLOCK).min(number_of_clusters);
+        while index < block_end {
+            excess += self.excess_step(index);
+            if excess == -1 { return Ok(index) }
+            index += 1;
+        }
+
+        //skip blocks that do not reach -1
+        let mut block = index / EXCESS_BLOCK;
+        while block * EXCESS_BLOCK < number_of_clusters {
+            if excess - self.block_min_excess.get(block) as isize <= -1 {
+                //the block table says the excess reaches -1 inside of the block
+                for index in block * EXCESS_BLOCK..((block + 1) * EXCESS_BLOCK).min(number_of_clusters) {
+                    excess += self.excess_step(index);
+                    if excess == -1 { return Ok(index) }
+                }
+                return Err(DecodeError::LengthMismatch);
+            }
+            excess += self.block_excess.get(block) as isize - EXCESS_BLOCK as isize;
+            block += 1;
+        }
+
+        Err(DecodeError::LengthMismatch)
+    }
+
+    ///checks the index structures and simulates the traversal once to check pointers, the order of the clusters, the root and the size
+    ///returns the number of nodes of the decompressed tree
+    fn validate(&self, limits: &DecodeLimits) -> Result<usize, DecodeError> {
+        let number_of_clusters = self.number_of_clusters();
+        let number_of_labels = self.number_of_labels();
+
+        if number_of_clusters > limits.max_clusters { return Err(DecodeError::SectionTooLarge("merge_type")) }
+        if number_of_labels > limits.max_labels { return Err(DecodeError::SectionTooLarge("label")) }
+        if !self.structure.check() || !self.labels.check() { return Err(DecodeError::CannotLoad("container")) }
+        if number_of_labels == 0 { return Err(DecodeError::Empty) }
+        //from_parts made sure that there is exactly one pointer
+        if number_of_clusters == 0 { return check_single_node(self.structure.len(), &[self.pointer.get(0)], number_of_labels).map(|_| 1) }
+
+        //every true cluster except the root is referenced by exactly one set bit, every other child by a pointer
+        let number_of_ones = self.structure.rank1(self.structure.len());
+        if number_of_ones + 1 != number_of_clusters || self.structure.len() - number_of_ones != self.pointer.len() {
+            return Err(DecodeError::LengthMismatch);
+        }
+        for index in 0..number_of_clusters {
+            let merge_type = self.merge_type.get(index) as i32;
+            if MergeType::from_i32(merge_type).is_none() { return Err(DecodeError::UnknownMergeType(merge_type)) }
+        }
+        let (block_min_excess, block_excess) = build_excess_tables(&self.structure, number_of_clusters);
+        for block in 0..block_excess.len() {
+            if     block_min_excess.get(block) != self.block_min_excess.get(block)
+                || block_excess.get(block) != self.block_excess.get(block) {
+                return Err(DecodeError::CannotLoad("container"));
+            }
+        }
+
+        let (size, top_nodes) = self.simulate_traversal(limits)?;
+        if top_nodes != 1 { return Err(DecodeError::InvalidRoot) }
+        //a cluster of size s expands to (s + 1) / 2 nodes
+        Ok(((size + 1) / 2) as usize)
+    }
+
+    ///runs the traversal once without expanding anything
+    ///returns the size and the number of top level nodes of the root
+    fn simulate_traversal(&self, limits: &DecodeLimits) -> Result<(u64, u8), DecodeError> {
+        let number_of_clusters = self.number_of_clusters();
+        let number_of_labels = self.number_of_labels();
+
+        //the root is a label
+        if number_of_clusters == 0 { return Ok((1, 1)) }
+
+        //size of every finished cluster, 0 while it is not finished
+        let mut size: Vec<u64> = vec![0; number_of_clusters];
+        //number of nodes on the top level of every cluster, at most 2 is stored
+        let mut top_nodes: Vec<u8> = vec![0; number_of_clusters];
+
+        let mut global_index = 0;
+        //(index, number of handled children)
+        let mut workstack = vec![(0, 0)];
+        let mut results: Vec<(u64, u8)> = Vec::new();
+
+        while let Some((index, state)) = workstack.pop() {
+            if state < 2 {
+                workstack.push((index, state + 1));
+                let position = index * 2 + state;
+                if self.structure.get(position) {
+                    global_index += 1;
+                    if global_index >= number_of_clusters { return Err(DecodeError::LengthMismatch) }
+                    workstack.push((global_index, 0));
+                } else {
+                    let pointer = self.pointer.get(self.structure.rank0(position));
+                    if pointer < number_of_labels {
+                        results.push((1, 1));
+                    } else {
+                        let referenced = pointer - number_of_labels;
+                        if referenced >= number_of_clusters || size[referenced] == 0 {
+                            return Err(DecodeError::InvalidPointer(pointer));
+                        }
+                        results.push((size[referenced], top_nodes[referenced]));
+                    }
+                }
+            } else {
+                let (second_size, second_top_nodes) = results.pop().unwrap();
+                let (first_size, first_top_nodes) = results.pop().unwrap();
+
+                size[index] = first_size.saturating_add(second_size).saturating_add(1);
+                top_nodes[index] = if self.merge_type.get(index) == 0 {
+                    first_top_nodes
+                } else {
+                    (first_top_nodes + second_top_nodes).min(2)
+                };
+
+                //a cluster of size s expands to (s + 1) / 2 nodes
+                if size[index] / 2 >= limits.max_decompressed_nodes as u64 { return Err(DecodeError::TooLarge) }
+                results.push((size[index], top_nodes[index]));
+            }
+        }
+
+        if global_index + 1 != number_of_clusters { return Err(DecodeError::LengthMismatch) }
+        Ok((size[0], top_nodes[0]))
+    }
+}
+
+impl<S: AsRef<[u64]>> Expand for CompressedTopDag<S> {
+    type Label = Vec<u8>;
+
+    fn expansion(&self, id: usize) -> Result<Expansion<Vec<u8>>, DecodeError> {
+        if let Some(label) = self.label(id) {
+            Ok(Expansion::Label(label.into_owned()))
+        } else {
+            let merge_type = self.merge_type(id).ok_or(DecodeError::InvalidPointer(id))?;
+            let (first_child, second_child) = self.children(id)?.ok_or(DecodeError::InvalidPointer(id))?;
+            Ok(Expansion::Cluster(merge_type, first_child, second_child))
+        }
+    }
+}
+
+///builds the tables subtree_end uses to skip whole blocks
+fn build_excess_tables<S: AsRef<[u64]>>(structure: &BitVector<S>, number_of_clusters: usize) -> (IntVector, IntVector) {
+    let number_of_blocks = (number_of_clusters + EXCESS_BLOCK - 1) / EXCESS_BLOCK;
+    let mut block_min_excess = Vec::with_capacity(number_of_blocks);
+    let mut block_excess = Vec::with_capacity(number_of_blocks);
+
+    for block in 0..number_of_blocks {
+        let mut excess: isize = 0;
+        let mut min_excess: isize = 0;
+        for index in block * EXCESS_BLOCK..((block + 1) * EXCESS_BLOCK).min(number_of_clusters) {
+            excess += structure.get(index * 2) as isize + structure.get(index * 2 + 1) as isize - 1;
+            min_excess = min_excess.min(excess);
+        }
+        block_min_excess.push((-min_excess) as usize);
+        block_excess.push((excess + EXCESS_BLOCK as isize) as usize);
+    }
+
+    (IntVector::new(&block_min_excess), IntVector::new(&block_excess))
+}
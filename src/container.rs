@@ -0,0 +1,228 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::ptr;
+use std::slice;
+use std::sync::Arc;
+use std::os::unix::io::AsRawFd;
+
+use sdsl_interface::libc;
+use io_tree::GenResult;
+use validation::{DecodeError, DecodeLimits};
+use compressed::{CompressedTopDag, CompressedLabels};
+use succinct::{BitVector, IntVector, LabelStore, words_as_bytes};
+
+
+
+///"TTDAG" followed by three zero bytes, read as little endian word
+const MAGIC: u64 = 0x0000_0047_4144_5454;
+///bump this for every change of the layout
+pub const CONTAINER_VERSION: u64 = 1;
+///all words are little endian, a mapping on a big endian machine reads the mark swapped and rejects the container
+const BYTE_ORDER_MARK: u64 = 0x0102_0304_0506_0708;
+
+const HEADER_WORDS: usize = 31;
+
+///A read only memory mapping of a whole file
+///the pages are only read from disk when they are accessed
+pub struct Mmap {
+    pointer: *mut libc::c_void,
+    length: usize,
+}
+
+//the mapping is read only so it can be shared between threads
+unsafe impl Send for Mmap {}
+unsafe impl Sync for Mmap {}
+
+impl Mmap {
+    pub fn open(path: &str) -> GenResult<Mmap> {
+        let file = File::open(path)?;
+        let length = file.metadata()?.len() as usize;
+        if length == 0 || length % 8 != 0 {
+            return Err(Box::new(DecodeError::CannotLoad("container")));
+        }
+
+        let pointer = unsafe {
+            libc::mmap(ptr::null_mut(), length, libc::PROT_READ, libc::MAP_PRIVATE, file.as_raw_fd(), 0)
+        };
+        if pointer == libc::MAP_FAILED {
+            return Err(Box::new(DecodeError::CannotLoad("container")));
+        }
+
+        //the mapping stays valid after the file is closed
+        Ok(Mmap { pointer, length })
+    }
+}
+
+impl AsRef<[u64]> for Mmap {
+    fn as_ref(&self) -> &[u64] {
+        //mappings are page aligned so the cast to words is aligned
+        unsafe {
+            slice::from_raw_parts(self.pointer as *const u64, self.length / 8)
+        }
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.pointer, self.length);
+        }
+    }
+}
+
+///A range of words inside of a shared buffer, this is the storage of a loaded container
+#[derive(Clone)]
+pub struct Section<B: AsRef<[u64]>> {
+    buffer: Arc<B>,
+    start: usize,
+    end: usize,
+}
+
+impl<B: AsRef<[u64]>> AsRef<[u64]> for Section<B> {
+    fn as_ref(&self) -> &[u64] {
+        &(*self.buffer).as_ref()[self.start..self.end]
+    }
+}
+
+impl<S: AsRef<[u64]>> CompressedTopDag<S> {
+    ///writes the container, all sections are stored as words so they can be used in place after loading
+    pub fn write_container<W: Write>(&self, writer: &mut W) -> GenResult<()> {
+        let (structure, block_min_excess, block_excess, pointer, merge_type, labels) = self.parts();
+        //the container stores the labels one after another, a front coded dictionary is decoded for it
+        let decoded;
+        let (label_offsets, label_bytes) = match labels {
+            CompressedLabels::Store(store) => {
+                let (offsets, bytes) = store.parts();
+                (offsets.parts(), bytes)
+            },
+            CompressedLabels::FrontCoded(dictionary) => {
+                decoded = LabelStore::new(&dictionary.to_vec());
+                let (offsets, bytes) = decoded.parts();
+                (offsets.parts(), bytes)
+            },
+        };
+
+        let mut header: Vec<u64> = vec![MAGIC, CONTAINER_VERSION, BYTE_ORDER_MARK, 0];
+        let mut sections: Vec<&[u64]> = Vec::new();
+        let mut offset = HEADER_WORDS;
+
+        let (length, words, superblock_ranks) = structure.parts();
+        header.push(length as u64);
+        push_section(&mut header, &mut sections, &mut offset, words);
+        push_section(&mut header, &mut sections, &mut offset, superblock_ranks);
+
+        for &(length, width, words) in &[block_min_excess.parts(), block_excess.parts(), pointer.parts(), merge_type.parts(), label_offsets] {
+            header.push(length as u64);
+            header.push(width as u64);
+            push_section(&mut header, &mut sections, &mut offset, words);
+        }
+
+        push_section(&mut header, &mut sections, &mut offset, label_bytes);
+        header[3] = offset as u64;
+        assert!(header.len() == HEADER_WORDS);
+
+        write_words(writer, &header)?;
+        for section in sections {
+            write_words(writer, section)?;
+        }
+        Ok(())
+    }
+
+    pub fn save_to_container(&self, path: &str) -> GenResult<()> {
+        let mut file = File::create(path)?;
+        self.write_container(&mut file)
+    }
+}
+
+impl CompressedTopDag<Section<Mmap>> {
+    pub fn open_mmap(path: &str) -> GenResult<CompressedTopDag<Section<Mmap>>> {
+        CompressedTopDag::open_mmap_with_limits(path, &DecodeLimits::default())
+    }
+
+    ///maps the container into memory and validates all sections, this reads the whole file once
+    ///the sections are used in place, so only a little endian machine can map a container
+    pub fn open_mmap_with_limits(path: &str, limits: &DecodeLimits) -> GenResult<CompressedTopDag<Section<Mmap>>> {
+        Ok(parse_container(Arc::new(Mmap::open(path)?), limits)?)
+    }
+}
+
+impl CompressedTopDag<Section<Vec<u64>>> {
+    ///reads a container from a stream and validates it
+    pub fn from_reader<R: Read>(reader: &mut R, limits: &DecodeLimits) -> GenResult<CompressedTopDag<Section<Vec<u64>>>> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        if bytes.len() % 8 != 0 {
+            return Err(Box::new(DecodeError::CannotLoad("container")));
+        }
+
+        let mut words = Vec::with_capacity(bytes.len() / 8);
+        for chunk in bytes.chunks(8) {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(chunk);
+            words.push(u64::from_le_bytes(word));
+        }
+
+        Ok(parse_container(Arc::new(words), limits)?)
+    }
+}
+
+///writes the words in little endian, on a little endian machine that is their memory
+fn write_words<W: Write>(writer: &mut W, words: &[u64]) -> GenResult<()> {
+    if cfg!(target_endian = "little") {
+        writer.write_all(words_as_bytes(words))?;
+    } else {
+        for word in words {
+            writer.write_all(&word.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+///stores offset and length of the section in the header
+fn push_section<'a>(header: &mut Vec<u64>, sections: &mut Vec<&'a [u64]>, offset: &mut usize, words: &'a [u64]) {
+    header.push(*offset as u64);
+    header.push(words.len() as u64);
+    *offset += words.len();
+    sections.push(words);
+}
+
+///checks the header and builds the structures on the sections of the buffer without copying
+fn parse_container<B: AsRef<[u64]>>(buffer: Arc<B>, limits: &DecodeLimits) -> Result<CompressedTopDag<Section<B>>, DecodeError> {
+    let corrupt = DecodeError::CannotLoad("container");
+    let total_words = (*buffer).as_ref().len();
+    if total_words < HEADER_WORDS {
+        return Err(corrupt);
+    }
+    let header: Vec<usize> = (*buffer).as_ref()[..HEADER_WORDS].iter().map(|word| *word as usize).collect();
+
+    if header[0] as u64 != MAGIC || header[2] as u64 != BYTE_ORDER_MARK || header[3] != total_words {
+        return Err(corrupt);
+    }
+    if header[1] as u64 > CONTAINER_VERSION {
+        return Err(DecodeError::UnsupportedVersion(header[1] as u32));
+    }
+
+    let section = |position: usize| -> Result<Section<B>, DecodeError> {
+        let start = header[position];
+        let end = start.checked_add(header[position + 1]).ok_or(DecodeError::CannotLoad("container"))?;
+        if start < HEADER_WORDS || end > total_words {
+            return Err(DecodeError::CannotLoad("container"));
+        }
+        Ok(Section { buffer: buffer.clone(), start, end })
+    };
+    let int_vector = |position: usize| -> Result<IntVector<Section<B>>, DecodeError> {
+        IntVector::from_parts(header[position], header[position + 1], section(position + 2)?).ok_or(DecodeError::CannotLoad("container"))
+    };
+
+    let structure = BitVector::from_parts(header[4], section(5)?, section(7)?).ok_or(DecodeError::CannotLoad("container"))?;
+    let block_min_excess = int_vector(9)?;
+    let block_excess = int_vector(13)?;
+    let pointer = int_vector(17)?;
+    let merge_type = int_vector(21)?;
+    let labels = LabelStore::from_parts(int_vector(25)?, section(29)?).ok_or(DecodeError::CannotLoad("container"))?;
+
+    match CompressedTopDag::from_parts(structure, block_min_excess, block_excess, pointer, merge_type, labels, limits) {
+        Err(DecodeError::LengthMismatch) => Err(corrupt),
+        result => result,
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::VecDeque;
+
+use structs::MergeType;
+use io_tree::IO_Tree;
+use validation::DecodeError;
+
+
+
+///What an id of a TopDAG stands for
+pub enum Expansion<L> {
+    Label(L),
+    Cluster(MergeType, usize, usize),
+}
+
+///A TopDAG that can be decompressed
+pub trait Expand {
+    type Label;
+
+    ///an error if the data of the id is corrupt
+    fn expansion(&self, id: usize) -> Result<Expansion<Self::Label>, DecodeError>;
+}
+
+enum Step<L> {
+    ///expand the id and hang the children below its bottom boundary node
+    Expand(usize, VecDeque<IO_Tree<L>>),
+    ///the second child of an AB cluster is expanded, hang it below the first child
+    BelowFirst(usize),
+    ///both children of a CE or DE cluster are expanded, put them next to each other
+    Concatenate,
+}
+
+///expands the cluster to the tree it represents
+///a cluster expands to a sequence of siblings, the root has to expand to exactly one node
+///uses an explicit stack so the depth of the tree is not limited by the call stack
+pub fn expand_tree<E: Expand>(source: &E, root: usize) -> Result<IO_Tree<E::Label>, DecodeError> {
+    let mut steps = vec![Step::Expand(root, VecDeque::new())];
+    let mut results: Vec<VecDeque<IO_Tree<E::Label>>> = Vec::new();
+
+    while let Some(step) = steps.pop() {
+        match step {
+            Step::Expand(id, children) => {
+                match source.expansion(id)? {
+                    Expansion::Label(label) => {
+                        let mut result = VecDeque::with_capacity(1);
+                        result.push_back(IO_Tree { label, children });
+                        results.push(result);
+                    },
+
+                    Expansion::Cluster(MergeType::AB, first_child, second_child) => {
+                        steps.push(Step::BelowFirst(first_child));
+                        steps.push(Step::Expand(second_child, children));
+                    },
+
+                    Expansion::Cluster(MergeType::CE, first_child, second_child) => {
+                        steps.push(Step::Concatenate);
+                        steps.push(Step::Expand(second_child, VecDeque::new()));
+                        steps.push(Step::Expand(first_child, children));
+                    },
+
+                    Expansion::Cluster(MergeType::DE, first_child, second_child) => {
+                        steps.push(Step::Concatenate);
+                        steps.push(Step::Expand(second_child, children));
+                        steps.push(Step::Expand(first_child, VecDeque::new()));
+                    },
+                }
+            },
+
+            Step::BelowFirst(first_child) => {
+                let below = results.pop().unwrap();
+                steps.push(Step::Expand(first_child, below));
+            },
+
+            Step::Concatenate => {
+                let mut second = results.pop().unwrap();
+                let mut first = results.pop().unwrap();
+                first.append(&mut second);
+                results.push(first);
+            },
+        }
+    }
+
+    let mut root = results.pop().unwrap();
+    if !results.is_empty() || root.len() != 1 { return Err(DecodeError::InvalidRoot) }
+    Ok(root.pop_back().unwrap())
+}
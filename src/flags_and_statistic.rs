@@ -1,11 +1,29 @@
 use std::time::Duration;
-use std::fmt::{Formatter, Result, Display};
+use std::fmt::{Formatter, Result, Display, Write};
+use std::collections::BTreeMap;
 
 
 
 pub struct Flags {
     pub merge_rule: MergeRule,
     pub slowing_down: f64,
+    ///how save_to_file stores the labels
+    pub label_encoding: LabelEncoding,
+    ///how save_to_file stores the pointers
+    pub pointer_encoding: PointerEncoding,
+    ///number of threads for the merge rounds, 1 merges sequentially
+    ///the simplified rules build the same TopDAG with any number of threads.
+    ///The advanced rules only merge if the cluster already exists, and in parallel they check that against
+    ///the clusters from before the phase, not against the ones built earlier in the same phase.
+    ///So they can choose other merges than the sequential build, the TopDAG decompresses to the same tree
+    ///but its clusters and its number of rounds can differ.
+    pub threads: usize,
+    ///upper bound for the number of nodes of the input tree
+    ///the arrays are reserved with it, the memory_limit is always checked with the real size of the tree
+    pub capacity_hint: Option<usize>,
+    ///upper bound in bytes for the memory of the builder, see MemoryEstimate
+    ///a tree that needs more is rejected before the arrays are allocated
+    pub memory_limit: Option<usize>,
 }
 
 impl Default for Flags {
@@ -14,10 +32,16 @@ impl Default for Flags {
             merge_rule: MergeRule::SimplifiedStandardRules,
             //4 is the minimum so it has no effect
             slowing_down: 4.0,
+            label_encoding: LabelEncoding::WaveletTree,
+            pointer_encoding: PointerEncoding::Vlc,
+            threads: 1,
+            capacity_hint: None,
+            memory_limit: None,
         }
     }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum MergeRule {
     SimplifiedStandardRules,
 
@@ -26,12 +50,35 @@ pub enum MergeRule {
     SlowAdvancedRules,
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LabelEncoding {
+    ///Huffman shaped wavelet tree over the joined labels (sdsl wt_huff)
+    WaveletTree,
+
+    ///Front coded dictionary, small for large sets of labels with common prefixes like URLs
+    FrontCoding,
+
+    ///Every label with its length
+    Plain,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PointerEncoding {
+    ///Variable length codes of the raw ids (sdsl vlc_vector)
+    Vlc,
+
+    ///Canonical Huffman code over the pointer frequencies, small if a few clusters are referenced most of the time
+    Huffman,
+}
+
+#[derive(Clone)]
 pub struct Statistic {
     pub time_for_xml_parsing: Duration,
     pub time_for_io_tree_parsing: Duration,
 
     pub timestamps_vector: Vec<(Duration,Duration)>,
 
+    ///not set by the TopDag, a caller can take it from get_IO_tree_timed
     pub time_for_decompression: Duration,
 
     pub time_for_traverse: Duration,
@@ -39,12 +86,28 @@ pub struct Statistic {
 
     pub number_of_merge_rounds: usize,
 
+    ///the input counts are taken after the insertion, before the first merge
+    ///inner nodes of the input, without the dummy node
     pub number_of_nodes_in_io_tree: usize,
     pub number_of_leafs_in_io_tree: usize,
+    ///every node except the root has one edge to its parent
     pub number_of_edges_in_io_tree: usize,
+    ///distinct labels of the input
+    pub number_of_labels_in_io_tree: usize,
+    ///nodes on the longest path from the root to a leaf
+    pub depth_of_io_tree: usize,
 
+    ///distinct labels, they are the leafs of the TopDAG
     pub number_of_leafs_in_top_dag: usize,
+    ///distinct clusters
     pub number_of_nodes_in_top_dag: usize,
+
+    ///one entry for every merge round
+    pub rounds: Vec<RoundStatistic>,
+    pub shape: TopDagShape,
+
+    ///not set by the TopDag, a caller can take it from save_to_file
+    pub size_report: Option<SizeReport>,
 }
 
 impl Statistic {
@@ -65,13 +128,213 @@ impl Statistic {
             number_of_nodes_in_io_tree: 0,
             number_of_leafs_in_io_tree: 0,
             number_of_edges_in_io_tree: 0,
+            number_of_labels_in_io_tree: 0,
+            depth_of_io_tree: 0,
 
             number_of_leafs_in_top_dag: 0,
             number_of_nodes_in_top_dag: 0,
+
+            rounds: Vec::new(),
+            shape: TopDagShape::default(),
+
+            size_report: None,
         }
     }
 }
 
+///changes whenever a field of the JSON or CSV export is renamed or removed
+pub const STATISTIC_SCHEMA_VERSION: usize = 2;
+
+///the columns of Statistic::to_csv_row
+const CSV_COLUMNS: &[&str] = &[
+    "schema_version",
+    "xml_parsing_ns",
+    "io_tree_parsing_ns",
+    "horizontal_merges_ns",
+    "vertical_merges_ns",
+    "traverse_ns",
+    "detraverse_ns",
+    "decompression_ns",
+    "number_of_merge_rounds",
+    "number_of_nodes_in_io_tree",
+    "number_of_leafs_in_io_tree",
+    "number_of_edges_in_io_tree",
+    "number_of_leafs_in_top_dag",
+    "number_of_nodes_in_top_dag",
+    "height",
+    "max_cluster_size",
+    "number_of_nodes",
+    "number_of_labels_in_io_tree",
+    "depth_of_io_tree",
+    "node_compression_ratio",
+    "edge_compression_ratio",
+    "size_kind",
+    "structure_bits",
+    "pointer_bits",
+    "merge_type_bits",
+    "label_bits",
+    "merge_timestamps_ns",
+    "rounds",
+    "sharing_histogram",
+];
+
+impl Statistic {
+    ///nodes and leafs of the input per label and cluster of the TopDAG
+    pub fn node_compression_ratio(&self) -> f64 {
+        let input = self.number_of_nodes_in_io_tree + self.number_of_leafs_in_io_tree;
+        let top_dag = self.number_of_nodes_in_top_dag + self.number_of_leafs_in_top_dag;
+        input as f64 / top_dag.max(1) as f64
+    }
+
+    ///edges of the input per edge of the TopDAG, every cluster has two edges to its children
+    pub fn edge_compression_ratio(&self) -> f64 {
+        self.number_of_edges_in_io_tree as f64 / (2 * self.number_of_nodes_in_top_dag).max(1) as f64
+    }
+
+    ///the durations summed over all rounds
+    fn merge_durations(&self) -> (Duration, Duration) {
+        let mut horizontal_merge_timestamp = Duration::new(0,0);
+        let mut vertical_merge_timestamp = Duration::new(0,0);
+        for (timestamp_1, timestamp_2) in &self.timestamps_vector {
+            horizontal_merge_timestamp += *timestamp_1;
+            vertical_merge_timestamp += *timestamp_2;
+        }
+        (horizontal_merge_timestamp, vertical_merge_timestamp)
+    }
+
+    ///the header line that belongs to to_csv_row
+    pub fn csv_header() -> String {
+        CSV_COLUMNS.join(",")
+    }
+
+    ///one line with the columns of csv_header, durations are in nanoseconds
+    ///the size columns are empty without a size report
+    ///the lists are records separated by ';' with fields separated by ':', in the order of the JSON export
+    pub fn to_csv_row(&self) -> String {
+        let (horizontal_merges, vertical_merges) = self.merge_durations();
+        let values = [
+            STATISTIC_SCHEMA_VERSION.to_string(),
+            nanoseconds(self.time_for_xml_parsing).to_string(),
+            nanoseconds(self.time_for_io_tree_parsing).to_string(),
+            nanoseconds(horizontal_merges).to_string(),
+            nanoseconds(vertical_merges).to_string(),
+            nanoseconds(self.time_for_traverse).to_string(),
+            nanoseconds(self.time_for_detraverse).to_string(),
+            nanoseconds(self.time_for_decompression).to_string(),
+            self.number_of_merge_rounds.to_string(),
+            self.number_of_nodes_in_io_tree.to_string(),
+            self.number_of_leafs_in_io_tree.to_string(),
+            self.number_of_edges_in_io_tree.to_string(),
+            self.number_of_leafs_in_top_dag.to_string(),
+            self.number_of_nodes_in_top_dag.to_string(),
+            self.shape.height.to_string(),
+            self.shape.max_cluster_size.to_string(),
+            self.shape.number_of_nodes.to_string(),
+            self.number_of_labels_in_io_tree.to_string(),
+            self.depth_of_io_tree.to_string(),
+            self.node_compression_ratio().to_string(),
+            self.edge_compression_ratio().to_string(),
+            self.size_report.as_ref().map_or(String::new(), |size_report| size_report.kind.export_name().to_owned()),
+            self.size_report.as_ref().map_or(String::new(), |size_report| size_report.structure_bits.to_string()),
+            self.size_report.as_ref().map_or(String::new(), |size_report| size_report.pointer_bits.to_string()),
+            self.size_report.as_ref().map_or(String::new(), |size_report| size_report.merge_type_bits.to_string()),
+            self.size_report.as_ref().map_or(String::new(), |size_report| size_report.label_bits.to_string()),
+            self.timestamps_vector.iter()
+                .map(|(timestamp_1, timestamp_2)| format!("{}:{}", nanoseconds(*timestamp_1), nanoseconds(*timestamp_2)))
+                .collect::<Vec<_>>().join(";"),
+            self.rounds.iter()
+                .map(|round| format!("{}:{}:{}:{}:{}:{}:{}:{}:{}:{}:{}",
+                    round.round,
+                    round.live_nodes_before, round.live_nodes_after,
+                    round.live_edges_before, round.live_edges_after,
+                    round.ab_merges, round.ce_merges, round.de_merges,
+                    round.slowed_down_merges,
+                    round.new_clusters, round.reused_clusters))
+                .collect::<Vec<_>>().join(";"),
+            self.shape.sharing_histogram.iter()
+                .map(|(parents, clusters)| format!("{}:{}", parents, clusters))
+                .collect::<Vec<_>>().join(";"),
+        ];
+        assert!(values.len() == CSV_COLUMNS.len());
+        values.join(",")
+    }
+
+    ///all fields as one JSON object, durations are in nanoseconds
+    pub fn to_json(&self) -> String {
+        let mut output = String::new();
+        self.write_json(&mut output).expect("Error: Writing to a String failed");
+        output
+    }
+
+    fn write_json(&self, output: &mut String) -> Result {
+        let (horizontal_merges, vertical_merges) = self.merge_durations();
+        write!(output, "{{")?;
+        write!(output, "\"schema_version\":{},", STATISTIC_SCHEMA_VERSION)?;
+        write!(output, "\"xml_parsing_ns\":{},", nanoseconds(self.time_for_xml_parsing))?;
+        write!(output, "\"io_tree_parsing_ns\":{},", nanoseconds(self.time_for_io_tree_parsing))?;
+        write!(output, "\"horizontal_merges_ns\":{},", nanoseconds(horizontal_merges))?;
+        write!(output, "\"vertical_merges_ns\":{},", nanoseconds(vertical_merges))?;
+        write!(output, "\"traverse_ns\":{},", nanoseconds(self.time_for_traverse))?;
+        write!(output, "\"detraverse_ns\":{},", nanoseconds(self.time_for_detraverse))?;
+        write!(output, "\"decompression_ns\":{},", nanoseconds(self.time_for_decompression))?;
+        write!(output, "\"number_of_merge_rounds\":{},", self.number_of_merge_rounds)?;
+        write!(output, "\"number_of_nodes_in_io_tree\":{},", self.number_of_nodes_in_io_tree)?;
+        write!(output, "\"number_of_leafs_in_io_tree\":{},", self.number_of_leafs_in_io_tree)?;
+        write!(output, "\"number_of_edges_in_io_tree\":{},", self.number_of_edges_in_io_tree)?;
+        write!(output, "\"number_of_labels_in_io_tree\":{},", self.number_of_labels_in_io_tree)?;
+        write!(output, "\"depth_of_io_tree\":{},", self.depth_of_io_tree)?;
+        write!(output, "\"number_of_leafs_in_top_dag\":{},", self.number_of_leafs_in_top_dag)?;
+        write!(output, "\"number_of_nodes_in_top_dag\":{},", self.number_of_nodes_in_top_dag)?;
+        write!(output, "\"node_compression_ratio\":{},", self.node_compression_ratio())?;
+        write!(output, "\"edge_compression_ratio\":{},", self.edge_compression_ratio())?;
+
+        //[horizontal, vertical] for every round
+        write!(output, "\"merge_timestamps_ns\":[")?;
+        for (index, (timestamp_1, timestamp_2)) in self.timestamps_vector.iter().enumerate() {
+            if index > 0 { write!(output, ",")?; }
+            write!(output, "[{},{}]", nanoseconds(*timestamp_1), nanoseconds(*timestamp_2))?;
+        }
+        write!(output, "],")?;
+
+        write!(output, "\"rounds\":[")?;
+        for (index, round) in self.rounds.iter().enumerate() {
+            if index > 0 { write!(output, ",")?; }
+            write!(output, "{{\"round\":{},\"live_nodes_before\":{},\"live_nodes_after\":{},\"live_edges_before\":{},\"live_edges_after\":{},\"ab_merges\":{},\"ce_merges\":{},\"de_merges\":{},\"slowed_down_merges\":{},\"new_clusters\":{},\"reused_clusters\":{}}}",
+                round.round,
+                round.live_nodes_before, round.live_nodes_after,
+                round.live_edges_before, round.live_edges_after,
+                round.ab_merges, round.ce_merges, round.de_merges,
+                round.slowed_down_merges,
+                round.new_clusters, round.reused_clusters)?;
+        }
+        write!(output, "],")?;
+
+        //[number of parents, number of clusters] in ascending order
+        write!(output, "\"shape\":{{\"height\":{},\"max_cluster_size\":{},\"number_of_nodes\":{},\"sharing_histogram\":[",
+            self.shape.height, self.shape.max_cluster_size, self.shape.number_of_nodes)?;
+        for (index, (parents, clusters)) in self.shape.sharing_histogram.iter().enumerate() {
+            if index > 0 { write!(output, ",")?; }
+            write!(output, "[{},{}]", parents, clusters)?;
+        }
+        write!(output, "]}},")?;
+
+        write!(output, "\"size_report\":")?;
+        match self.size_report {
+            Some(ref size_report) => write!(output, "{{\"kind\":\"{}\",\"structure_bits\":{},\"pointer_bits\":{},\"merge_type_bits\":{},\"label_bits\":{},\"number_of_nodes\":{},\"number_of_clusters\":{}}}",
+                size_report.kind.export_name(),
+                size_report.structure_bits, size_report.pointer_bits, size_report.merge_type_bits, size_report.label_bits,
+                size_report.number_of_nodes, size_report.number_of_clusters)?,
+            None => write!(output, "null")?,
+        }
+
+        write!(output, "}}")
+    }
+}
+
+fn nanoseconds(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000_000 + u64::from(duration.subsec_nanos())
+}
+
 impl Display for Statistic {
     fn fmt(&self, f: &mut Formatter) -> Result {
         if Duration::default() != self.time_for_xml_parsing {
@@ -98,8 +361,181 @@ impl Display for Statistic {
         writeln!(f, "Number of nodes in the IO tree: {}", self.number_of_nodes_in_io_tree)?;
         writeln!(f, "Number of leafs in the IO tree: {}", self.number_of_leafs_in_io_tree)?;
         writeln!(f, "Number of edges in the IO tree: {}", self.number_of_edges_in_io_tree)?;
+        writeln!(f, "Number of labels in the IO tree: {}", self.number_of_labels_in_io_tree)?;
+        writeln!(f, "Depth of the IO tree: {}", self.depth_of_io_tree)?;
 
         writeln!(f, "Number of leafs in the TopDAG: {}", self.number_of_leafs_in_top_dag)?;
-        writeln!(f, "Number of nodes in the TopDAG: {}", self.number_of_nodes_in_top_dag)
+        writeln!(f, "Number of nodes in the TopDAG: {}", self.number_of_nodes_in_top_dag)?;
+        writeln!(f, "Node compression ratio: {:.3}, Edge compression ratio: {:.3}", self.node_compression_ratio(), self.edge_compression_ratio())?;
+
+        if !self.rounds.is_empty() {
+            writeln!(f, "")?;
+            for round in &self.rounds {
+                writeln!(f, "{}", round)?;
+            }
+        }
+
+        writeln!(f, "")?;
+        write!(f, "{}", self.shape)?;
+
+        if Duration::default() != self.time_for_decompression {
+            writeln!(f, "")?;
+            writeln!(f, "Decompression toke: {:?}", self.time_for_decompression)?;
+        }
+
+        if let Some(ref size_report) = self.size_report {
+            writeln!(f, "")?;
+            write!(f, "{}", size_report)?;
+        }
+        Ok(())
+    }
+}
+
+///What happened in one horizontal and vertical merge
+///live nodes are the nodes and leafs that are not merged into a cluster jet
+#[derive(Clone, Debug, Default)]
+pub struct RoundStatistic {
+    ///starts with 1
+    pub round: usize,
+
+    pub live_nodes_before: usize,
+    pub live_nodes_after: usize,
+    pub live_edges_before: usize,
+    pub live_edges_after: usize,
+
+    pub ab_merges: usize,
+    pub ce_merges: usize,
+    pub de_merges: usize,
+    ///merges that were possible but skipped because of slowing_down
+    pub slowed_down_merges: usize,
+
+    ///merges that created a cluster that did not exist before
+    pub new_clusters: usize,
+    ///merges that found the cluster in the dictionary
+    pub reused_clusters: usize,
+}
+
+impl RoundStatistic {
+    pub fn merges(&self) -> usize {
+        self.ab_merges + self.ce_merges + self.de_merges
+    }
+}
+
+impl Display for RoundStatistic {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f, "Round {}: {} -> {} nodes, {} -> {} edges, merges AB: {} CE: {} DE: {}, slowed down: {}, new clusters: {}, reused clusters: {}",
+            self.round,
+            self.live_nodes_before, self.live_nodes_after,
+            self.live_edges_before, self.live_edges_after,
+            self.ab_merges, self.ce_merges, self.de_merges,
+            self.slowed_down_merges,
+            self.new_clusters, self.reused_clusters)
+    }
+}
+
+///The shape of a finished TopDAG
+#[derive(Clone, Debug, Default)]
+pub struct TopDagShape {
+    ///the longest path from the root to a label, a label has height 0
+    pub height: usize,
+    ///the number of nodes the largest cluster below the root expands to
+    ///the root always covers the whole tree so it is left out
+    pub max_cluster_size: usize,
+    ///number of parents to the number of clusters with that many parents
+    ///a cluster that is used twice by the same parent counts twice
+    pub sharing_histogram: BTreeMap<usize, usize>,
+    ///nodes of the decompressed tree, the height is O(log number_of_nodes)
+    pub number_of_nodes: usize,
+}
+
+impl Display for TopDagShape {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let log_nodes = (self.number_of_nodes.max(1) as f64).log2();
+        writeln!(f, "Height of the TopDAG: {} (log2 of the nodes: {:.1})", self.height, log_nodes)?;
+        writeln!(f, "Largest cluster below the root: {} nodes", self.max_cluster_size)?;
+        writeln!(f, "Sharing histogram (parents: clusters):")?;
+        for (parents, clusters) in &self.sharing_histogram {
+            writeln!(f, "{}: {}", parents, clusters)?;
+        }
+        Ok(())
+    }
+}
+
+///The encoded size of every stream of an archive
+#[derive(Clone, Debug, Default)]
+pub struct SizeReport {
+    ///what the bits of the streams measure
+    pub kind: SizeKind,
+
+    pub structure_bits: usize,
+    pub pointer_bits: usize,
+    pub merge_type_bits: usize,
+    pub label_bits: usize,
+
+    ///nodes of the decompressed tree
+    pub number_of_nodes: usize,
+    pub number_of_clusters: usize,
+}
+
+impl SizeReport {
+    pub fn total_bits(&self) -> usize {
+        self.structure_bits + self.pointer_bits + self.merge_type_bits + self.label_bits
+    }
+
+    pub fn bits_per_node(&self) -> f64 {
+        self.total_bits() as f64 / self.number_of_nodes.max(1) as f64
+    }
+
+    pub fn bits_per_cluster(&self) -> f64 {
+        self.total_bits() as f64 / self.number_of_clusters.max(1) as f64
+    }
+}
+
+impl Display for SizeReport {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let total = self.total_bits().max(1) as f64;
+        writeln!(f, "{}", self.kind)?;
+        writeln!(f, "Structure stream: {} bits ({:.1}%)", self.structure_bits, 100.0 * self.structure_bits as f64 / total)?;
+        writeln!(f, "Pointer stream: {} bits ({:.1}%)", self.pointer_bits, 100.0 * self.pointer_bits as f64 / total)?;
+        writeln!(f, "Merge type stream: {} bits ({:.1}%)", self.merge_type_bits, 100.0 * self.merge_type_bits as f64 / total)?;
+        writeln!(f, "Label stream: {} bits ({:.1}%)", self.label_bits, 100.0 * self.label_bits as f64 / total)?;
+        writeln!(f, "Overall: {} bits", self.total_bits())?;
+        writeln!(f, "Bits per input node: {:.3}", self.bits_per_node())?;
+        writeln!(f, "Bits per TopDAG cluster: {:.3}", self.bits_per_cluster())
+    }
+}
+
+///What the bits of a SizeReport measure
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SizeKind {
+    ///the size of the section files of an archive, as written by save_to_file
+    EncodedFile,
+
+    ///the memory of a CompressedTopDag, including the rank and excess tables
+    InMemory,
+}
+
+impl Default for SizeKind {
+    fn default() -> Self {
+        SizeKind::EncodedFile
+    }
+}
+
+impl SizeKind {
+    ///the name in the JSON and CSV export of a Statistic
+    fn export_name(&self) -> &'static str {
+        match self {
+            SizeKind::EncodedFile => "encoded_file",
+            SizeKind::InMemory => "in_memory",
+        }
+    }
+}
+
+impl Display for SizeKind {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            SizeKind::EncodedFile => write!(f, "Size of the archive files:"),
+            SizeKind::InMemory => write!(f, "Size in memory:"),
+        }
     }
 }
\ No newline at end of file
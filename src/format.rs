@@ -0,0 +1,561 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::{Read, Write, ErrorKind};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+
+use io_tree::GenResult;
+use validation::{DecodeError, DecodeLimits, check_declared_length};
+use flags_and_statistic::{LabelEncoding, PointerEncoding, SizeReport, SizeKind};
+use compressed::CompressedTopDag;
+use label_encoding::{FrontCodedDictionary, LabelDictionary, encode_plain, decode_plain};
+use pointer_encoding::{encode_huffman, decode_huffman, decode_huffman_with, huffman_pointer_count};
+use succinct::{BitVector, IntVector};
+use structs::MergeType;
+use sdsl_interface::{
+    lock_global_vectors,
+
+    load_structure_from_file,
+    load_pointer_from_file,
+    load_merge_type_from_file,
+    load_label_from_file,
+
+    save_structure_to_file,
+    save_pointer_to_file,
+    save_merge_type_to_file,
+    save_label_to_file,
+
+    set_structure_vector,
+    get_structure_vector,
+    get_structure_vector_length,
+    get_structure_words,
+
+    set_pointer_vector,
+    get_pointer_vector,
+    get_pointer_vector_length,
+    get_pointer_range,
+
+    set_merge_type_vector,
+    get_merge_type_vector,
+    get_merge_type_vector_length,
+    get_merge_type_range,
+
+    set_label_vector,
+    get_label_bytes,
+    labels_from_bytes,
+};
+
+
+
+///Version 1: no meta section, labels are joined with '\n' without escaping
+pub const VERSION_1: u32 = 1;
+///Version 2: meta section, labels are escaped
+pub const VERSION_2: u32 = 2;
+///Version 3: selectable label encoding
+pub const VERSION_3: u32 = 3;
+///Version 4: selectable pointer encoding
+pub const VERSION_4: u32 = 4;
+
+///the version save_to_file writes
+///every change to the traversal, the merge type mapping or a section encoding needs a new version and a golden in tests/golden
+pub const CURRENT_VERSION: u32 = VERSION_4;
+
+const META_FILE: &str = "/meta";
+
+///The four streams traverse produces
+pub struct Streams {
+    pub structure: Vec<bool>,
+    pub pointer: Vec<usize>,
+    pub merge_type: Vec<i32>,
+    pub label: LabelDictionary,
+}
+
+///The values of the sections sdsl stores, as they are in its vectors
+///the sections this crate encodes itself are read from their files, their entries are None
+pub struct SdslSections {
+    pub structure: Vec<bool>,
+    pub merge_type: Vec<i32>,
+    ///the vlc_vector of the pointers
+    pub pointer: Option<Vec<usize>>,
+    ///the wavelet tree of the labels, the joined labels and the terminator sdsl appends
+    pub label: Option<Vec<u8>>,
+}
+
+///The streams of an archive in the succinct vectors of a CompressedTopDag
+///the merge types and the pointers are checked while they are copied, validate checks the rest
+pub(crate) struct SuccinctStreams {
+    pub structure: BitVector,
+    pub pointer: IntVector,
+    pub merge_type: IntVector,
+    pub label: LabelDictionary,
+}
+
+///number of values that are copied from the sdsl vectors at once
+const CHUNK_SIZE: usize = 1 << 12;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Meta {
+    pub version: u32,
+    pub label_encoding: LabelEncoding,
+    pub pointer_encoding: PointerEncoding,
+}
+
+impl Meta {
+    pub fn current(label_encoding: LabelEncoding, pointer_encoding: PointerEncoding) -> Meta {
+        Meta {
+            version: CURRENT_VERSION,
+            label_encoding,
+            pointer_encoding,
+        }
+    }
+
+    ///path is the archive directory
+    pub fn save(&self, path: &str) -> GenResult<()> {
+        let mut file = File::create(format!("{}{}", path, META_FILE))?;
+        write!(file, "version={}\n", self.version)?;
+        write!(file, "label_encoding={}\n", label_encoding_name(self.label_encoding))?;
+        write!(file, "pointer_encoding={}\n", pointer_encoding_name(self.pointer_encoding))?;
+        Ok(())
+    }
+
+    ///path is the archive directory
+    ///archives without a meta section are version 1
+    pub fn load(path: &str) -> GenResult<Meta> {
+        let mut content = String::new();
+        match File::open(format!("{}{}", path, META_FILE)) {
+            Ok(mut file) => {
+                file.read_to_string(&mut content).map_err(|_| DecodeError::InvalidMeta)?;
+            },
+            Err(ref error) if error.kind() == ErrorKind::NotFound => {
+                return Ok(Meta { version: VERSION_1, label_encoding: LabelEncoding::WaveletTree, pointer_encoding: PointerEncoding::Vlc });
+            },
+            Err(error) => return Err(Box::new(error)),
+        }
+
+        let mut version = None;
+        //archives before version 3 always use the wavelet tree
+        let mut label_encoding = LabelEncoding::WaveletTree;
+        //archives before version 4 always use the vlc_vector
+        let mut pointer_encoding = PointerEncoding::Vlc;
+        for line in content.lines() {
+            let mut key_value = line.splitn(2, '=');
+            match (key_value.next(), key_value.next()) {
+                (Some("version"), Some(value)) => {
+                    version = Some(value.trim().parse::<u32>().map_err(|_| DecodeError::InvalidMeta)?);
+                },
+                (Some("label_encoding"), Some(value)) => {
+                    label_encoding = label_encoding_from_name(value.trim()).ok_or(DecodeError::InvalidMeta)?;
+                },
+                (Some("pointer_encoding"), Some(value)) => {
+                    pointer_encoding = pointer_encoding_from_name(value.trim()).ok_or(DecodeError::InvalidMeta)?;
+                },
+                //keys of newer minor additions are ignored
+                (Some(_), Some(_)) => (),
+                _ => if !line.trim().is_empty() { return Err(Box::new(DecodeError::InvalidMeta)) },
+            }
+        }
+
+        let version = version.ok_or(DecodeError::InvalidMeta)?;
+        if version == 0 {
+            return Err(Box::new(DecodeError::InvalidMeta));
+        }
+        if version > CURRENT_VERSION {
+            return Err(Box::new(DecodeError::UnsupportedVersion(version)));
+        }
+
+        Ok(Meta { version, label_encoding, pointer_encoding })
+    }
+}
+
+///counts the extract directories of this process so every call gets its own
+static EXTRACT_COUNTER: AtomicUsize = ATOMIC_USIZE_INIT;
+
+///extracts the archive into a new temporary directory, calls read with the archive directory in it and removes it again
+///the archive file is never changed, so untrusted archives can not write next to it
+///and several threads can read the same archive at the same time
+pub fn open_archive<T, F: FnOnce(&str) -> GenResult<T>>(path: &str, read: F) -> GenResult<T> {
+    let path = path.trim_right_matches(".tar");
+    let name = path.rsplit('/').next().unwrap();
+
+    let extract_directory = env::temp_dir().join(format!("top_tree_archive_{}_{}", process::id(), EXTRACT_COUNTER.fetch_add(1, Ordering::SeqCst)));
+    let extract_directory = extract_directory.to_str().ok_or(DecodeError::CannotLoad("archive"))?.to_owned();
+    fs::create_dir(&extract_directory)?;
+
+    let archive_directory = format!("{}/{}", extract_directory, name);
+    let result = extract_archive(&format!("{}.tar", path), &extract_directory, &archive_directory)
+        .and_then(|_| read(&archive_directory));
+
+    let _ = fs::remove_dir_all(&extract_directory);
+    result
+}
+
+///the sections of the archive have to be regular files, a link could point anywhere
+fn extract_archive(archive: &str, extract_directory: &str, archive_directory: &str) -> GenResult<()> {
+    if !run_command!("tar", "-xf", archive, "-C", extract_directory).status.success() {
+        return Err(Box::new(DecodeError::CannotLoad("archive")));
+    }
+
+    if !fs::symlink_metadata(archive_directory).map_err(|_| DecodeError::CannotLoad("archive"))?.is_dir() {
+        return Err(Box::new(DecodeError::CannotLoad("archive")));
+    }
+    for entry in fs::read_dir(archive_directory)? {
+        if !fs::symlink_metadata(entry?.path())?.is_file() {
+            return Err(Box::new(DecodeError::CannotLoad("archive")));
+        }
+    }
+    Ok(())
+}
+
+///path is the archive directory
+pub fn load_streams(path: &str, limits: &DecodeLimits) -> GenResult<(Meta, Streams)> {
+    let meta = Meta::load(path)?;
+    let sections = {
+        let _global_vectors = lock_global_vectors();
+        load_sdsl_sections(path, &meta, limits)?
+    };
+    let streams = decode_sections(path, &meta, sections, limits)?;
+    Ok((meta, streams))
+}
+
+///reads the sections sdsl stores, the caller holds the lock of the global vectors
+fn load_sdsl_sections(path: &str, meta: &Meta, limits: &DecodeLimits) -> GenResult<SdslSections> {
+    let structure_path = format!("{}/structure.sdsl", path);
+    let merge_type_path = format!("{}/merge_type.sdsl", path);
+
+    check_declared_length(&structure_path, "structure", limits.max_clusters.saturating_mul(2))?;
+    check_declared_length(&merge_type_path, "merge_type", limits.max_clusters)?;
+
+    if !load_structure_from_file(&structure_path) {return Err(Box::new(DecodeError::CannotLoad("structure")))}
+    if !load_merge_type_from_file(&merge_type_path) {return Err(Box::new(DecodeError::CannotLoad("merge_type")))}
+
+    let pointer = match meta.pointer_encoding {
+        PointerEncoding::Vlc => {
+            let pointer_path = format!("{}/{}", path, pointer_file_name(meta.pointer_encoding));
+            check_declared_length(&pointer_path, "pointer", limits.max_clusters.saturating_mul(2))?;
+            if !load_pointer_from_file(&pointer_path) {return Err(Box::new(DecodeError::CannotLoad("pointer")))}
+            Some(get_pointer_vector())
+        },
+        PointerEncoding::Huffman => None,
+    };
+
+    Ok(SdslSections {
+        structure: get_structure_vector(),
+        merge_type: get_merge_type_vector(),
+        pointer,
+        label: load_sdsl_label(path, meta, limits)?,
+    })
+}
+
+///combines the sections sdsl stored with the sections this crate encodes in the archive directory
+///every format version is decoded here, so the sdsl vectors of an archive can be checked without sdsl
+pub fn decode_sections(path: &str, meta: &Meta, sections: SdslSections, limits: &DecodeLimits) -> GenResult<Streams> {
+    let SdslSections { structure, merge_type, pointer, label } = sections;
+
+    let pointer = match (meta.pointer_encoding, pointer) {
+        (PointerEncoding::Vlc, Some(pointer)) => pointer,
+        (PointerEncoding::Vlc, None) => return Err(Box::new(DecodeError::CannotLoad("pointer"))),
+        (PointerEncoding::Huffman, _) => {
+            //a code takes at most 8 bytes and an entry of the code table at most 11
+            let max_pointers = limits.max_clusters.saturating_mul(2);
+            let bytes = read_file(&format!("{}/{}", path, pointer_file_name(meta.pointer_encoding)), "pointer", max_pointers.saturating_mul(20))?;
+            decode_huffman(&bytes, max_pointers)?
+        },
+    };
+    let label = decode_labels(path, meta, label, limits)?;
+
+    Ok(Streams { structure, pointer, merge_type, label })
+}
+
+///reads the archive directory without expanding a stream to one word per bit or value
+///the sdsl vectors are copied in chunks of CHUNK_SIZE values into the succinct vectors,
+///a pointer takes ceil(log2(labels + clusters)) bits and a merge type 2 bits
+pub(crate) fn load_succinct_streams(path: &str, limits: &DecodeLimits) -> GenResult<(Meta, SuccinctStreams)> {
+    let meta = Meta::load(path)?;
+    let _global_vectors = lock_global_vectors();
+
+    let structure_path = format!("{}/structure.sdsl", path);
+    let merge_type_path = format!("{}/merge_type.sdsl", path);
+
+    check_declared_length(&structure_path, "structure", limits.max_clusters.saturating_mul(2))?;
+    check_declared_length(&merge_type_path, "merge_type", limits.max_clusters)?;
+
+    if !load_structure_from_file(&structure_path) {return Err(Box::new(DecodeError::CannotLoad("structure")))}
+    if !load_merge_type_from_file(&merge_type_path) {return Err(Box::new(DecodeError::CannotLoad("merge_type")))}
+
+    let structure_length = get_structure_vector_length();
+    let mut words = vec![0u64; (structure_length + 63) / 64];
+    for (chunk, words) in words.chunks_mut(CHUNK_SIZE).enumerate() {
+        get_structure_words(chunk * CHUNK_SIZE, words);
+    }
+    let structure = BitVector::from_words(structure_length, words);
+
+    let number_of_clusters = get_merge_type_vector_length();
+    let mut merge_type = IntVector::zeros(number_of_clusters, 2);
+    let mut buffer = vec![0i32; CHUNK_SIZE.min(number_of_clusters)];
+    for first in (0..number_of_clusters).step_by(CHUNK_SIZE) {
+        let buffer = &mut buffer[..CHUNK_SIZE.min(number_of_clusters - first)];
+        get_merge_type_range(first, buffer);
+        for (index, elem) in buffer.iter().enumerate() {
+            if MergeType::from_i32(*elem).is_none() { return Err(Box::new(DecodeError::UnknownMergeType(*elem))) }
+            merge_type.set(first + index, *elem as usize);
+        }
+    }
+
+    let label = decode_labels(path, &meta, load_sdsl_label(path, &meta, limits)?, limits)?;
+    let pointer = load_succinct_pointers(path, &meta, limits, label.len().saturating_add(number_of_clusters))?;
+
+    Ok((meta, SuccinctStreams { structure, pointer, merge_type, label }))
+}
+
+///every pointer has to be below bound, the number of labels and clusters
+fn load_succinct_pointers(path: &str, meta: &Meta, limits: &DecodeLimits, bound: usize) -> GenResult<IntVector> {
+    let width = (64 - (bound.saturating_sub(1) as u64).leading_zeros() as usize).max(1);
+    let check = |value: usize| if value < bound { Ok(value) } else { Err(DecodeError::InvalidPointer(value)) };
+
+    let pointer_path = format!("{}/{}", path, pointer_file_name(meta.pointer_encoding));
+    match meta.pointer_encoding {
+        PointerEncoding::Vlc => {
+            check_declared_length(&pointer_path, "pointer", limits.max_clusters.saturating_mul(2))?;
+            if !load_pointer_from_file(&pointer_path) {return Err(Box::new(DecodeError::CannotLoad("pointer")))}
+
+            let number_of_pointers = get_pointer_vector_length();
+            let mut pointer = IntVector::zeros(number_of_pointers, width);
+            let mut buffer = vec![0usize; CHUNK_SIZE.min(number_of_pointers)];
+            for first in (0..number_of_pointers).step_by(CHUNK_SIZE) {
+                let buffer = &mut buffer[..CHUNK_SIZE.min(number_of_pointers - first)];
+                get_pointer_range(first, buffer);
+                for (index, elem) in buffer.iter().enumerate() {
+                    pointer.set(first + index, check(*elem)?);
+                }
+            }
+            Ok(pointer)
+        },
+
+        PointerEncoding::Huffman => {
+            //a code takes at most 8 bytes and an entry of the code table at most 11
+            let max_pointers = limits.max_clusters.saturating_mul(2);
+            let bytes = read_file(&pointer_path, "pointer", max_pointers.saturating_mul(20))?;
+
+            let number_of_pointers = huffman_pointer_count(&bytes, max_pointers)?;
+            //every pointer needs at least one bit so this bounds the allocation
+            if number_of_pointers > bytes.len().saturating_mul(8) { return Err(Box::new(DecodeError::CannotLoad("pointer"))) }
+            let mut pointer = IntVector::zeros(number_of_pointers, width);
+            let mut index = 0;
+            decode_huffman_with(&bytes, max_pointers, |value| {
+                pointer.set(index, check(value)?);
+                index += 1;
+                Ok(())
+            })?;
+            Ok(pointer)
+        },
+    }
+}
+
+///writes the streams to the archive directory and packs it to path.tar
+///number_of_nodes is the size of the decompressed tree, it is only used for the report
+pub fn save_streams(path: &str, streams: Streams, meta: &Meta, number_of_nodes: usize) -> SizeReport {
+    let Streams { structure, pointer, merge_type, label } = streams;
+    let number_of_clusters = merge_type.len();
+    let _global_vectors = lock_global_vectors();
+
+    set_structure_vector(structure);
+    set_merge_type_vector(merge_type);
+
+    run_command!("mkdir", "-p", path);
+
+    let mut structure_path = path.to_owned();
+    structure_path.push_str("/structure.sdsl");
+
+    let mut merge_type_path = path.to_owned();
+    merge_type_path.push_str("/merge_type.sdsl");
+
+    if !save_structure_to_file(&structure_path) {panic!("Error: Could not save structure_vector")}
+    if !save_pointers(path, pointer, meta.pointer_encoding) {panic!("Error: Could not save pointer_vector")}
+    if !save_merge_type_to_file(&merge_type_path) {panic!("Error: Could not save merge_type_vector")}
+    if !save_labels(path, label, meta.label_encoding) {panic!("Error: Could not save label_vector")}
+    if meta.save(path).is_err() {panic!("Error: Could not save meta")}
+
+    let mut size_report = section_sizes(path, meta).expect("Error: Could not read the saved sections");
+    size_report.number_of_nodes = number_of_nodes;
+    size_report.number_of_clusters = number_of_clusters;
+
+    let mut path_list = path.split('/').collect::<Vec<&str>>();
+    let name = path_list.pop().unwrap();
+    let mut directory = String::new();
+    for elem in path_list {
+        directory.push_str(elem);
+        directory.push('/');
+    }
+    run_command!(&directory; "tar", "cf", &format!("{}.tar", name), name);
+    run_command!("rm", "-r", path);
+
+    size_report
+}
+
+///the size breakdown of an existing archive
+pub fn size_report(path: &str) -> GenResult<SizeReport> {
+    open_archive(path, |directory| {
+        let meta = Meta::load(directory)?;
+        let mut size_report = section_sizes(directory, &meta)?;
+
+        let limits = DecodeLimits::default();
+        let (_meta, streams) = load_succinct_streams(directory, &limits)?;
+        let compressed_top_dag = CompressedTopDag::from_succinct_streams(streams, &limits)?;
+        size_report.number_of_nodes = compressed_top_dag.number_of_nodes();
+        size_report.number_of_clusters = compressed_top_dag.number_of_clusters();
+        Ok(size_report)
+    })
+}
+
+///the sizes of the section files in the archive directory
+fn section_sizes(path: &str, meta: &Meta) -> GenResult<SizeReport> {
+    let file_bits = |name: &str| -> GenResult<usize> {
+        Ok(File::open(format!("{}/{}", path, name))?.metadata()?.len() as usize * 8)
+    };
+
+    Ok(SizeReport {
+        kind: SizeKind::EncodedFile,
+        structure_bits: file_bits("structure.sdsl")?,
+        pointer_bits: file_bits(pointer_file_name(meta.pointer_encoding))?,
+        merge_type_bits: file_bits("merge_type.sdsl")?,
+        label_bits: file_bits(label_file_name(meta.label_encoding))?,
+        number_of_nodes: 0,
+        number_of_clusters: 0,
+    })
+}
+
+fn label_file_name(label_encoding: LabelEncoding) -> &'static str {
+    match label_encoding {
+        LabelEncoding::WaveletTree => "label.sdsl",
+        LabelEncoding::FrontCoding => "label.fc",
+        LabelEncoding::Plain => "label.plain",
+    }
+}
+
+fn pointer_file_name(pointer_encoding: PointerEncoding) -> &'static str {
+    match pointer_encoding {
+        PointerEncoding::Vlc => "pointer.sdsl",
+        PointerEncoding::Huffman => "pointer.huff",
+    }
+}
+
+///path is the archive directory
+fn save_pointers(path: &str, pointer: Vec<usize>, pointer_encoding: PointerEncoding) -> bool {
+    match pointer_encoding {
+        PointerEncoding::Vlc => {
+            set_pointer_vector(pointer);
+            save_pointer_to_file(&format!("{}/{}", path, pointer_file_name(pointer_encoding)))
+        },
+
+        PointerEncoding::Huffman => {
+            write_file(&format!("{}/{}", path, pointer_file_name(pointer_encoding)), &encode_huffman(&pointer)).is_ok()
+        },
+    }
+}
+
+///path is the archive directory
+fn save_labels(path: &str, label: LabelDictionary, label_encoding: LabelEncoding) -> bool {
+    match (label_encoding, label) {
+        (LabelEncoding::WaveletTree, label) => {
+            set_label_vector(label.into_vec());
+            save_label_to_file(&format!("{}/{}", path, label_file_name(label_encoding)))
+        },
+
+        (LabelEncoding::FrontCoding, LabelDictionary::FrontCoded(dictionary)) => {
+            write_file(&format!("{}/{}", path, label_file_name(label_encoding)), &dictionary.serialize()).is_ok()
+        },
+
+        (LabelEncoding::FrontCoding, label) => {
+            let dictionary = FrontCodedDictionary::new(&label.into_vec());
+            write_file(&format!("{}/{}", path, label_file_name(label_encoding)), &dictionary.serialize()).is_ok()
+        },
+
+        (LabelEncoding::Plain, label) => {
+            write_file(&format!("{}/{}", path, label_file_name(label_encoding)), &encode_plain(&label.into_vec())).is_ok()
+        },
+    }
+}
+
+///path is the archive directory
+///only the wavelet tree is stored by sdsl, the other label encodings have no sdsl vector
+fn load_sdsl_label(path: &str, meta: &Meta, limits: &DecodeLimits) -> GenResult<Option<Vec<u8>>> {
+    match meta.label_encoding {
+        LabelEncoding::WaveletTree => {
+            let label_path = format!("{}/{}", path, label_file_name(meta.label_encoding));
+            check_declared_length(&label_path, "label", limits.max_label_bytes)?;
+            if !load_label_from_file(&label_path) {return Err(Box::new(DecodeError::CannotLoad("label")))}
+            Ok(Some(get_label_bytes()))
+        },
+        LabelEncoding::FrontCoding | LabelEncoding::Plain => Ok(None),
+    }
+}
+
+///path is the archive directory, sdsl_label are the bytes of the wavelet tree
+///a front coded dictionary is not decoded, the labels are looked up in it
+fn decode_labels(path: &str, meta: &Meta, sdsl_label: Option<Vec<u8>>, limits: &DecodeLimits) -> GenResult<LabelDictionary> {
+    match meta.label_encoding {
+        LabelEncoding::WaveletTree => {
+            let bytes = sdsl_label.ok_or(DecodeError::CannotLoad("label"))?;
+            Ok(LabelDictionary::Plain(labels_from_bytes(bytes, meta.version != VERSION_1)))
+        },
+
+        LabelEncoding::FrontCoding => {
+            let bytes = read_file(&format!("{}/{}", path, label_file_name(meta.label_encoding)), "label", limits.max_label_bytes)?;
+            Ok(LabelDictionary::FrontCoded(FrontCodedDictionary::deserialize(&bytes, limits.max_labels)?))
+        },
+
+        LabelEncoding::Plain => {
+            let bytes = read_file(&format!("{}/{}", path, label_file_name(meta.label_encoding)), "label", limits.max_label_bytes)?;
+            Ok(LabelDictionary::Plain(decode_plain(&bytes, limits.max_labels)?))
+        },
+    }
+}
+
+fn label_encoding_name(label_encoding: LabelEncoding) -> &'static str {
+    match label_encoding {
+        LabelEncoding::WaveletTree => "wt_huff",
+        LabelEncoding::FrontCoding => "front_coding",
+        LabelEncoding::Plain => "plain",
+    }
+}
+
+fn pointer_encoding_name(pointer_encoding: PointerEncoding) -> &'static str {
+    match pointer_encoding {
+        PointerEncoding::Vlc => "vlc",
+        PointerEncoding::Huffman => "huffman",
+    }
+}
+
+fn pointer_encoding_from_name(name: &str) -> Option<PointerEncoding> {
+    match name {
+        "vlc" => Some(PointerEncoding::Vlc),
+        "huffman" => Some(PointerEncoding::Huffman),
+        _ => None,
+    }
+}
+
+fn label_encoding_from_name(name: &str) -> Option<LabelEncoding> {
+    match name {
+        "wt_huff" => Some(LabelEncoding::WaveletTree),
+        "front_coding" => Some(LabelEncoding::FrontCoding),
+        "plain" => Some(LabelEncoding::Plain),
+        _ => None,
+    }
+}
+
+///reads a section written by this crate, files larger than max_length are rejected without reading them
+pub fn read_file(path: &str, section: &'static str, max_length: usize) -> std::result::Result<Vec<u8>, DecodeError> {
+    let file = File::open(path).map_err(|_| DecodeError::CannotLoad(section))?;
+    let length = file.metadata().map_err(|_| DecodeError::CannotLoad(section))?.len();
+    if length > max_length as u64 {
+        return Err(DecodeError::SectionTooLarge(section));
+    }
+
+    let mut bytes = Vec::with_capacity(length as usize);
+    file.take(length).read_to_end(&mut bytes).map_err(|_| DecodeError::CannotLoad(section))?;
+    Ok(bytes)
+}
+
+pub fn write_file(path: &str, bytes: &[u8]) -> std::io::Result<()> {
+    File::create(path)?.write_all(bytes)
+}
@@ -1,8 +1,8 @@
 use std::collections::VecDeque;
 use std::fs::File;
 use std::error::Error;
-use std::io::BufReader;
-use std::fmt::{Formatter, Result, Display};
+use std::io::{BufReader, Write};
+use std::fmt::{Debug, Formatter, Result, Display};
 
 use quick_xml::Reader;
 use quick_xml::events::Event;
@@ -34,11 +34,13 @@ impl Display for ParseError {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+///Clone, PartialEq, Debug and Drop walk the tree with an explicit stack
+///so trees that are deeper than the call stack can be handled
 #[allow(non_camel_case_types)]
-pub struct IO_Tree {
-    pub label: String,
-    pub children: VecDeque<IO_Tree>,
+pub struct IO_Tree<L = Vec<u8>> {
+    ///for xml the raw bytes of the label, no encoding is assumed
+    pub label: L,
+    pub children: VecDeque<IO_Tree<L>>,
 }
 
 impl IO_Tree {
@@ -53,7 +55,7 @@ impl IO_Tree {
         'filereader: loop {
             match reader.read_event(&mut buf) {
                 Ok(Event::Start(ref elem)) => {
-                    let label = String::from_utf8_lossy(elem.name()).to_string();
+                    let label = elem.name().to_vec();
 
                     node_stack.push(IO_Tree {
                         label,
@@ -62,9 +64,8 @@ impl IO_Tree {
                 },
 
                 Ok(Event::End(ref elem)) => {
-                    let label = String::from_utf8_lossy(elem.name()).to_string();
                     if let Some(node) = node_stack.pop() {
-                        if node.label != label {return Err(Box::new(ParseError::CannotParse));}
+                        if &node.label[..] != elem.name() {return Err(Box::new(ParseError::CannotParse));}
                         if let Some(last) = node_stack.len().checked_sub(1) {
                             //node is not root so we push it to its parent
                             node_stack[last].children.push_back(node);
@@ -99,4 +100,142 @@ impl IO_Tree {
             Err(Box::new(ParseError::CannotParse))
         }
     }
+
+    ///writes the tree as xml that new_from_xml reads back to the same tree
+    ///leafs are written with an end tag because empty elements are ignored by the parser
+    ///a label that is no xml name is an InvalidData error, the xml written before it is incomplete
+    pub fn write_xml<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        //(node, number of written children)
+        let mut stack = vec![(self, 0)];
+        write_tag(writer, b"<", &self.label)?;
+
+        while let Some((node, next_child)) = stack.pop() {
+            if let Some(child) = node.children.get(next_child) {
+                stack.push((node, next_child + 1));
+                write_tag(writer, b"<", &child.label)?;
+                stack.push((child, 0));
+            } else {
+                write_tag(writer, b"</", &node.label)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_tag<W: Write>(writer: &mut W, start: &[u8], label: &[u8]) -> std::io::Result<()> {
+    if !is_xml_name(label) {
+        let message = format!("Error: The label {:?} is not a valid xml name", String::from_utf8_lossy(label));
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, message));
+    }
+    writer.write_all(start)?;
+    writer.write_all(label)?;
+    writer.write_all(b">")
+}
+
+///checks the ascii bytes against the name characters of xml
+///no encoding is assumed, so every byte above ascii is accepted like the parser does
+fn is_xml_name(label: &[u8]) -> bool {
+    let is_name_start = |byte: u8| byte >= 0x80 || byte.is_ascii_alphabetic() || byte == b'_' || byte == b':';
+    match label.split_first() {
+        Some((&first, rest)) => is_name_start(first) && rest.iter().all(|&byte| is_name_start(byte) || byte.is_ascii_digit() || byte == b'-' || byte == b'.'),
+        None => false,
+    }
+}
+
+impl<L> IO_Tree<L> {
+    ///returns the number of inner nodes and the number of leafs
+    pub fn count(&self) -> (usize, usize) {
+        let mut number_of_nodes = 0;
+        let mut number_of_leafs = 0;
+
+        let mut stack = vec![self];
+        while let Some(tree) = stack.pop() {
+            if tree.children.is_empty() {
+                number_of_leafs += 1;
+            } else {
+                number_of_nodes += 1;
+                stack.extend(tree.children.iter());
+            }
+        }
+        (number_of_nodes, number_of_leafs)
+    }
+}
+
+impl<L: Clone> Clone for IO_Tree<L> {
+    fn clone(&self) -> IO_Tree<L> {
+        //(original node, copies of the children that are finished)
+        let mut stack = vec![(self, VecDeque::with_capacity(self.children.len()))];
+
+        loop {
+            let next_child = {
+                let (original, children) = stack.last().unwrap();
+                let original: &IO_Tree<L> = *original;
+                original.children.get(children.len())
+            };
+
+            match next_child {
+                Some(child) => stack.push((child, VecDeque::with_capacity(child.children.len()))),
+
+                None => {
+                    //all children are copied so the node is finished
+                    let (original, children) = stack.pop().unwrap();
+                    let copy = IO_Tree { label: original.label.clone(), children };
+                    match stack.last_mut() {
+                        Some((_, siblings)) => siblings.push_back(copy),
+                        None => return copy,
+                    }
+                },
+            }
+        }
+    }
+}
+
+impl<L: PartialEq> PartialEq for IO_Tree<L> {
+    fn eq(&self, other: &IO_Tree<L>) -> bool {
+        let mut stack = vec![(self, other)];
+        while let Some((first, second)) = stack.pop() {
+            if first.label != second.label || first.children.len() != second.children.len() {
+                return false;
+            }
+            stack.extend(first.children.iter().zip(second.children.iter()));
+        }
+        true
+    }
+}
+
+impl<L: Eq> Eq for IO_Tree<L> {}
+
+///prints the same as a derived Debug without the alternate flag
+impl<L: Debug> Debug for IO_Tree<L> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f, "IO_Tree {{ label: {:?}, children: [", self.label)?;
+
+        //(node, number of printed children)
+        let mut stack = vec![(self, 0)];
+        while let Some((node, next_child)) = stack.pop() {
+            match node.children.get(next_child) {
+                Some(child) => {
+                    if next_child > 0 {
+                        write!(f, ", ")?;
+                    }
+                    stack.push((node, next_child + 1));
+                    write!(f, "IO_Tree {{ label: {:?}, children: [", child.label)?;
+                    stack.push((child, 0));
+                },
+
+                None => write!(f, "] }}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<L> Drop for IO_Tree<L> {
+    ///takes the children out before they are dropped so every node is dropped without children
+    fn drop(&mut self) {
+        let mut stack: Vec<IO_Tree<L>> = self.children.drain(..).collect();
+        while let Some(mut tree) = stack.pop() {
+            stack.extend(tree.children.drain(..));
+        }
+    }
 }
@@ -0,0 +1,64 @@
+use std::hash::Hash;
+
+
+
+///What the builder needs from a label
+///labels are only compared, hashed and cloned, so token ids or interned symbols work as well as text
+pub trait Label: Eq + Hash + Clone {}
+
+impl<T: Eq + Hash + Clone> Label for T {}
+
+///A label that can be written to an archive
+pub trait LabelBytes: Label {
+    fn to_bytes(&self) -> Vec<u8>;
+
+    ///None if the bytes do not describe a label of this type
+    fn from_bytes(bytes: &[u8]) -> Option<Self>;
+}
+
+impl LabelBytes for Vec<u8> {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Some(bytes.to_vec())
+    }
+}
+
+impl LabelBytes for String {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+///integers are stored in little endian with their full width
+impl LabelBytes for u32 {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_le_bytes().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != 4 { return None }
+        let mut buffer = [0u8; 4];
+        buffer.copy_from_slice(bytes);
+        Some(u32::from_le_bytes(buffer))
+    }
+}
+
+impl LabelBytes for u64 {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_le_bytes().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != 8 { return None }
+        let mut buffer = [0u8; 8];
+        buffer.copy_from_slice(bytes);
+        Some(u64::from_le_bytes(buffer))
+    }
+}
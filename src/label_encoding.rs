@@ -0,0 +1,287 @@
+use std::borrow::Cow;
+
+use validation::DecodeError;
+
+
+
+///number of labels in one front coding bucket
+///the first label of a bucket is stored completely, the others relative to their predecessor
+const BUCKET_SIZE: usize = 16;
+
+///A front coded dictionary
+///the labels are sorted and stored in buckets, a permutation maps the label id to the sorted position
+#[derive(Clone, Debug)]
+pub struct FrontCodedDictionary {
+    ///sorted position of every label id
+    ranks: Vec<usize>,
+    ///start of every bucket in data
+    bucket_offsets: Vec<usize>,
+    data: Vec<u8>,
+}
+
+impl FrontCodedDictionary {
+    pub fn new(labels: &[Vec<u8>]) -> FrontCodedDictionary {
+        let mut sorted: Vec<usize> = (0..labels.len()).collect();
+        sorted.sort_by(|first, second| labels[*first].cmp(&labels[*second]));
+
+        let mut ranks = vec![0; labels.len()];
+        for (rank, id) in sorted.iter().enumerate() {
+            ranks[*id] = rank;
+        }
+
+        let mut bucket_offsets = Vec::with_capacity(labels.len() / BUCKET_SIZE + 1);
+        let mut data = Vec::new();
+        for (rank, id) in sorted.iter().enumerate() {
+            let label = &labels[*id];
+            if rank % BUCKET_SIZE == 0 {
+                bucket_offsets.push(data.len());
+                write_varint(label.len(), &mut data);
+                data.extend_from_slice(label);
+            } else {
+                //only the suffix after the common prefix with the predecessor is stored
+                let previous = &labels[sorted[rank - 1]];
+                let common_prefix = previous.iter().zip(label.iter()).take_while(|(first, second)| first == second).count();
+                write_varint(common_prefix, &mut data);
+                write_varint(label.len() - common_prefix, &mut data);
+                data.extend_from_slice(&label[common_prefix..]);
+            }
+        }
+
+        FrontCodedDictionary { ranks, bucket_offsets, data }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ranks.len()
+    }
+
+    pub fn size_in_bits(&self) -> usize {
+        (self.ranks.len() + self.bucket_offsets.len()) * 64 + self.data.len() * 8
+    }
+
+    ///returns the label with the given id
+    pub fn get(&self, id: usize) -> Option<Vec<u8>> {
+        let rank = *self.ranks.get(id)?;
+        let bucket = rank / BUCKET_SIZE;
+        let mut position = *self.bucket_offsets.get(bucket)?;
+
+        let length = read_varint(&self.data, &mut position).ok()?;
+        let mut label = read_bytes(&self.data, &mut position, length).ok()?.to_vec();
+        for _ in 0..rank % BUCKET_SIZE {
+            let common_prefix = read_varint(&self.data, &mut position).ok()?;
+            let suffix_length = read_varint(&self.data, &mut position).ok()?;
+            if common_prefix > label.len() { return None }
+            label.truncate(common_prefix);
+            label.extend_from_slice(read_bytes(&self.data, &mut position, suffix_length).ok()?);
+        }
+        Some(label)
+    }
+
+    ///returns all labels ordered by id
+    pub fn to_vec(&self) -> Vec<Vec<u8>> {
+        (0..self.len()).map(|id| self.get(id).unwrap()).collect()
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        write_varint(self.ranks.len(), &mut output);
+        for rank in &self.ranks {
+            write_varint(*rank, &mut output);
+        }
+        write_varint(self.data.len(), &mut output);
+        output.extend_from_slice(&self.data);
+        output
+    }
+
+    ///the bucket offsets are not stored, they are rebuilt by scanning the data once
+    pub fn deserialize(input: &[u8], max_labels: usize) -> Result<FrontCodedDictionary, DecodeError> {
+        let mut position = 0;
+        let number_of_labels = read_varint(input, &mut position)?;
+        if number_of_labels > max_labels { return Err(DecodeError::SectionTooLarge("label")) }
+
+        //every label needs at least one byte so this bounds the allocation
+        if number_of_labels > input.len() { return Err(DecodeError::CannotLoad("label")) }
+        let mut ranks = Vec::with_capacity(number_of_labels);
+        let mut seen = vec![false; number_of_labels];
+        for _ in 0..number_of_labels {
+            let rank = read_varint(input, &mut position)?;
+            if rank >= number_of_labels || seen[rank] { return Err(DecodeError::CannotLoad("label")) }
+            seen[rank] = true;
+            ranks.push(rank);
+        }
+
+        let data_length = read_varint(input, &mut position)?;
+        let data = read_bytes(input, &mut position, data_length)?.to_vec();
+
+        let mut bucket_offsets = Vec::with_capacity(number_of_labels / BUCKET_SIZE + 1);
+        let mut data_position = 0;
+        let mut previous_length = 0;
+        for rank in 0..number_of_labels {
+            if rank % BUCKET_SIZE == 0 {
+                bucket_offsets.push(data_position);
+                previous_length = read_varint(&data, &mut data_position)?;
+                read_bytes(&data, &mut data_position, previous_length)?;
+            } else {
+                let common_prefix = read_varint(&data, &mut data_position)?;
+                let suffix_length = read_varint(&data, &mut data_position)?;
+                if common_prefix > previous_length { return Err(DecodeError::CannotLoad("label")) }
+                read_bytes(&data, &mut data_position, suffix_length)?;
+                previous_length = common_prefix + suffix_length;
+            }
+        }
+
+        Ok(FrontCodedDictionary { ranks, bucket_offsets, data })
+    }
+}
+
+///The labels of an archive, a front coded dictionary stays compressed after loading
+#[derive(Clone, Debug)]
+pub enum LabelDictionary {
+    Plain(Vec<Vec<u8>>),
+
+    ///every lookup decodes the part of one bucket up to the label
+    FrontCoded(FrontCodedDictionary),
+}
+
+impl LabelDictionary {
+    pub fn len(&self) -> usize {
+        match self {
+            LabelDictionary::Plain(labels) => labels.len(),
+            LabelDictionary::FrontCoded(dictionary) => dictionary.len(),
+        }
+    }
+
+    pub fn get(&self, id: usize) -> Option<Cow<[u8]>> {
+        match self {
+            LabelDictionary::Plain(labels) => labels.get(id).map(|label| Cow::Borrowed(&label[..])),
+            LabelDictionary::FrontCoded(dictionary) => dictionary.get(id).map(Cow::Owned),
+        }
+    }
+
+    ///all labels ordered by id, this decodes the whole front coded dictionary
+    pub fn into_vec(self) -> Vec<Vec<u8>> {
+        match self {
+            LabelDictionary::Plain(labels) => labels,
+            LabelDictionary::FrontCoded(dictionary) => dictionary.to_vec(),
+        }
+    }
+}
+
+///every label is stored as its length followed by its bytes
+pub fn encode_plain(labels: &[Vec<u8>]) -> Vec<u8> {
+    let mut output = Vec::new();
+    write_varint(labels.len(), &mut output);
+    for label in labels {
+        write_varint(label.len(), &mut output);
+        output.extend_from_slice(label);
+    }
+    output
+}
+
+pub fn decode_plain(input: &[u8], max_labels: usize) -> Result<Vec<Vec<u8>>, DecodeError> {
+    let mut position = 0;
+    let number_of_labels = read_varint(input, &mut position)?;
+    if number_of_labels > max_labels { return Err(DecodeError::SectionTooLarge("label")) }
+    if number_of_labels > input.len() { return Err(DecodeError::CannotLoad("label")) }
+
+    let mut labels = Vec::with_capacity(number_of_labels);
+    for _ in 0..number_of_labels {
+        let length = read_varint(input, &mut position)?;
+        labels.push(read_bytes(input, &mut position, length)?.to_vec());
+    }
+    Ok(labels)
+}
+
+///LEB128, seven bits per byte, the highest bit marks that more bytes follow
+pub fn write_varint(mut value: usize, output: &mut Vec<u8>) {
+    while value >= 0x80 {
+        output.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+pub fn read_varint(input: &[u8], position: &mut usize) -> Result<usize, DecodeError> {
+    let mut value: usize = 0;
+    let mut shift = 0;
+    loop {
+        let byte = *input.get(*position).ok_or(DecodeError::CannotLoad("label"))?;
+        *position += 1;
+        if shift >= 64 || (shift == 63 && byte > 1) { return Err(DecodeError::CannotLoad("label")) }
+        value |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
+}
+
+fn read_bytes<'a>(input: &'a [u8], position: &mut usize, length: usize) -> Result<&'a [u8], DecodeError> {
+    let end = position.checked_add(length).ok_or(DecodeError::CannotLoad("label"))?;
+    let bytes = input.get(*position..end).ok_or(DecodeError::CannotLoad("label"))?;
+    *position = end;
+    Ok(bytes)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls() -> Vec<Vec<u8>> {
+        let mut labels: Vec<Vec<u8>> = (0..100).map(|index| format!("https://example.org/items/{}/{}", index % 13, index).into_bytes()).collect();
+        labels.push(Vec::new());
+        labels.push(b"https://example.org/items".to_vec());
+        labels.push(vec![0, 255, b'\n', 0]);
+        labels
+    }
+
+    #[test]
+    fn front_coding_round_trip() {
+        let labels = urls();
+        let dictionary = FrontCodedDictionary::new(&labels);
+        let loaded = FrontCodedDictionary::deserialize(&dictionary.serialize(), labels.len()).unwrap();
+
+        assert_eq!(loaded.len(), labels.len());
+        for (id, label) in labels.iter().enumerate() {
+            assert_eq!(loaded.get(id).as_ref(), Some(label));
+        }
+        assert_eq!(loaded.get(labels.len()), None);
+        assert_eq!(loaded.to_vec(), labels);
+        //the common prefixes are stored once
+        assert!(loaded.size_in_bits() < labels.iter().map(|label| label.len() * 8).sum::<usize>());
+    }
+
+    #[test]
+    fn plain_round_trip() {
+        let labels = urls();
+        assert_eq!(decode_plain(&encode_plain(&labels), labels.len()).unwrap(), labels);
+        assert_eq!(decode_plain(&encode_plain(&[]), 0).unwrap(), Vec::<Vec<u8>>::new());
+    }
+
+    #[test]
+    fn dictionary_lookup_does_not_decode_everything() {
+        let labels = urls();
+        let front_coded = LabelDictionary::FrontCoded(FrontCodedDictionary::new(&labels));
+        let plain = LabelDictionary::Plain(labels.clone());
+        for dictionary in &[front_coded, plain] {
+            assert_eq!(dictionary.len(), labels.len());
+            assert_eq!(dictionary.get(5).unwrap().as_ref(), &labels[5][..]);
+            assert!(dictionary.get(labels.len()).is_none());
+            assert_eq!(dictionary.clone().into_vec(), labels);
+        }
+    }
+
+    #[test]
+    fn corrupt_input_is_rejected() {
+        let labels = urls();
+        let serialized = FrontCodedDictionary::new(&labels).serialize();
+        assert!(FrontCodedDictionary::deserialize(&serialized[..serialized.len() - 1], labels.len()).is_err());
+        assert!(FrontCodedDictionary::deserialize(&serialized, labels.len() - 1).is_err());
+        //two labels with the same rank
+        assert!(FrontCodedDictionary::deserialize(&[2, 0, 0, 2, 1, b'a'], 2).is_err());
+
+        let encoded = encode_plain(&labels);
+        assert!(decode_plain(&encoded[..encoded.len() - 1], labels.len()).is_err());
+        assert!(decode_plain(&encoded, labels.len() - 1).is_err());
+    }
+}
\ No newline at end of file
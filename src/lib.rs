@@ -7,73 +7,114 @@ extern crate quick_xml;
 mod macros;
 mod structs;
 pub mod io_tree;
+pub mod arena_tree;
+pub mod label;
 pub mod flags_and_statistic;
 mod uninitialized;
 mod sdsl_interface;
-
-use structs::{Node, Leaf, Edge, Cluster, Child, NodeHandle, MergeType, Data};
+pub mod validation;
+pub mod format;
+mod label_encoding;
+mod pointer_encoding;
+mod succinct;
+mod expand;
+pub mod shared_tree;
+mod parallel;
+pub mod push_builder;
+pub mod progress;
+pub mod memory;
+pub mod top_dag;
+pub mod compressed;
+pub mod container;
+pub mod verify;
+
+use structs::{Node, Leaf, Edge, Cluster, Child, NodeHandle, Data};
 use io_tree::{IO_Tree, GenResult};
-use flags_and_statistic::{Statistic, Flags, MergeRule};
+use arena_tree::ArenaTree;
+use flags_and_statistic::{Statistic, RoundStatistic, Flags, MergeRule};
 use uninitialized::Uninitialized;
-use sdsl_interface::{
-    load_structure_from_file,
-    load_pointer_from_file,
-    load_merge_type_from_file,
-    load_label_from_file,
-
-    save_structure_to_file,
-    save_pointer_to_file,
-    save_merge_type_to_file,
-    save_label_to_file,
-
-    set_structure_vector,
-    get_structure_vector,
-
-    set_pointer_vector,
-    get_pointer_vector,
+use validation::{DecodeLimits, DecodeError, check_stream_lengths};
+use label::Label;
+use top_dag::TopDag;
+use progress::{Phase, Progress, Control, Observer, Cancelled};
+use memory::{MemoryEstimate, MemoryError};
+use push_builder::BuildError;
+use parallel::WorkerPool;
 
-    set_merge_type_vector,
-    get_merge_type_vector,
-
-    //set_label_vector_from_str,
-    set_label_vector_from_string,
-    get_label_vector,
-};
+pub use structs::MergeType;
 
 use std::collections::{HashMap, VecDeque};
 use std::fmt::{Debug, Formatter, Result, Write};
 use std::time::Instant;
-use std::str::FromStr;
-
+use std::mem;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
 
 
-const DUMMY_NODE_LABEL: &str = "Dummy_node";
 
-pub struct TopTreeBuilder {
+pub struct TopTreeBuilder<L: Label = Vec<u8>> {
     nodes: Vec<Node>,
     leafs: Vec<Leaf>,
     edges: Vec<Edge>,
 
 
     clusters: HashMap<Cluster, usize>,
-    labels: HashMap<String, usize>,
+    labels: HashMap<L, usize>,
 
     cluster_vector: Vec<Cluster>,
     cluster_size: Vec<usize>,
-    label_vector: Vec<String>,
+    label_vector: Vec<L>,
 
     flags: Flags,
     statistic: Statistic,
+    observer: Option<Box<Observer + Send>>,
+    ///the statistic of the running merge round
+    round_statistic: RoundStatistic,
+    ///nodes and leafs that are not merged into a cluster jet, without the dummy node
+    ///every merge removes one of them and one edge, so apply_merge keeps both up to date
+    live_nodes: usize,
+    ///edges between the live nodes, without the edge of the dummy node
+    live_edges: usize,
+    ///the threads of the parallel merge, from the first parallel phase until the end of the build
+    workers: Option<WorkerPool>,
 
     number_of_steps: usize,
 }
 
 impl TopTreeBuilder {
+    ///reads the tree, the merge rounds run in build
     pub fn new_from_xml(path: &str, flags: Option<Flags>) -> GenResult<TopTreeBuilder> {
-        let mut builder = TopTreeBuilder {
-            nodes: Vec::with_capacity(40_000_000),
-            leafs: Vec::with_capacity(40_000_000),
-            edges: Vec::with_capacity(40_000_000),
+        let file = File::open(path)?;
+        TopTreeBuilder::from_xml_reader(BufReader::new(file), flags)
+    }
+
+    ///like new_from_xml but reads from any buffered source, for example stdin
+    pub fn from_xml_reader<R: BufRead>(source: R, flags: Option<Flags>) -> GenResult<TopTreeBuilder> {
+        let mut builder = TopTreeBuilder::empty(flags.unwrap_or_default());
+
+        //the limit is checked while parsing so a huge document fails before it is in memory
+        let memory_limit = builder.flags.memory_limit;
+        let tree = measure_performance!(ArenaTree::from_xml_reader_with_limit(source, memory_limit)?, builder.statistic.time_for_xml_parsing);
+
+        builder.insert_arena_tree(&tree)?;
+
+        Ok(builder)
+    }
+
+    ///like build_verified but streams the xml again, so the input tree is never in memory
+    pub fn build_verified_xml(self, path: &str) -> GenResult<TopDag> {
+        let top_dag = self.try_build()?;
+        top_dag.verify_against_xml(path)?;
+        Ok(top_dag)
+    }
+}
+
+impl<L: Label> TopTreeBuilder<L> {
+    fn empty(flags: Flags) -> TopTreeBuilder<L> {
+        TopTreeBuilder {
+            nodes: Vec::new(),
+            leafs: Vec::new(),
+            edges: Vec::new(),
 
 
             clusters: HashMap::new(),
@@ -83,305 +124,311 @@ impl TopTreeBuilder {
             cluster_size: Vec::new(),
             label_vector: Vec::new(),
 
-            flags: flags.unwrap_or_default(),
+            flags,
             statistic: Statistic::new(),
+            observer: None,
+            round_statistic: RoundStatistic::default(),
+            live_nodes: 0,
+            live_edges: 0,
+            workers: None,
 
             number_of_steps: 0,
-        };
-
-        let root = measure_performance!(IO_Tree::new_from_xml(path)?, builder.statistic.time_for_xml_parsing);
-
-        builder.build_from_IO_tree(root);
-
-        Ok(builder)
+        }
     }
 
+    ///takes the tree, the merge rounds run in build
+    ///fails if the tree needs more memory than the memory_limit of the flags
     #[allow(non_snake_case)]
-    pub fn new_from_IO_tree(tree: IO_Tree, flags: Option<Flags>) -> TopTreeBuilder {
-        let mut builder = TopTreeBuilder {
-            nodes: Vec::with_capacity(40_000_000),
-            leafs: Vec::with_capacity(40_000_000),
-            edges: Vec::with_capacity(40_000_000),
+    pub fn new_from_IO_tree(tree: IO_Tree<L>, flags: Option<Flags>) -> std::result::Result<TopTreeBuilder<L>, MemoryError> {
+        let mut builder = TopTreeBuilder::empty(flags.unwrap_or_default());
 
+        builder.insert_IO_tree(tree)?;
 
-            clusters: HashMap::new(),
-            labels: HashMap::new(),
+        Ok(builder)
+    }
 
-            cluster_vector: Vec::new(),
-            cluster_size: Vec::new(),
-            label_vector: Vec::new(),
+    ///takes the tree, the merge rounds run in build
+    ///fails if the tree has no node or needs more memory than the memory_limit of the flags
+    pub fn new_from_arena_tree(tree: &ArenaTree<L>, flags: Option<Flags>) -> std::result::Result<TopTreeBuilder<L>, BuildError> {
+        let mut builder = TopTreeBuilder::empty(flags.unwrap_or_default());
 
-            flags: flags.unwrap_or_default(),
-            statistic: Statistic::new(),
+        builder.insert_arena_tree(tree)?;
 
-            number_of_steps: 0,
-        };
+        Ok(builder)
+    }
 
-        builder.build_from_IO_tree(tree);
+    ///the observer is called after every horizontal and vertical merge of build
+    pub fn set_observer<O: Observer + Send + 'static>(&mut self, observer: O) {
+        self.observer = Some(Box::new(observer));
+    }
 
-        builder
+    ///runs the merge rounds and returns the finished TopDAG
+    ///panics if the observer aborts, use try_build if it can
+    pub fn build(self) -> TopDag<L> {
+        self.try_build().expect("Error: The build was cancelled")
     }
 
-    pub fn new_fom_file(path: &str) -> GenResult<TopTreeBuilder> {
-        let mut path_list = path.split('/').collect::<Vec<&str>>();
-        let name = path_list.pop().unwrap();
-        let mut directory = String::new();
-        for elem in path_list {
-            directory.push_str(elem);
-            directory.push('/');
-        }
+    ///runs the merge rounds and returns the finished TopDAG or the progress at which the observer aborted
+    pub fn try_build(mut self) -> std::result::Result<TopDag<L>, Cancelled> {
+        self.build_top_dag()?;
+        Ok(TopDag::from_builder(self))
+    }
 
-        if path.ends_with(".tar") {
-            run_command!(&directory; "tar", "-xf", name);
-            run_command!("rm", path);
-        } else {
-            run_command!(&directory; "tar", "-xf", &format!("{}.tar", name));
-            run_command!("rm", &format!("{}.tar", path));
-        }
+    ///like try_build but also checks that the TopDAG decompresses to the tree it was built from
+    ///the error is the Cancelled of the observer or the first Mismatch
+    pub fn build_verified(self, tree: &IO_Tree<L>) -> GenResult<TopDag<L>> {
+        let top_dag = self.try_build()?;
+        top_dag.verify_against(tree)?;
+        Ok(top_dag)
+    }
 
-        let mut structure_path = path.to_owned();
-        structure_path.push_str("/structure.sdsl");
+    #[allow(non_snake_case)]
+    fn insert_IO_tree(&mut self, mut tree: IO_Tree<L>) -> std::result::Result<(), MemoryError> {
+        //every child gets exactly the edges it needs so no edge has to be moved
+        let (number_of_nodes, number_of_leafs) = tree.count();
+        self.reserve(number_of_nodes, number_of_leafs)?;
 
-        let mut pointer_path = path.to_owned();
-        pointer_path.push_str("/pointer.sdsl");
+        self.insert_dummy_node();
 
-        let mut merge_type_path = path.to_owned();
-        merge_type_path.push_str("/merge_type.sdsl");
+        //insert the root
+        let children = mem::replace(&mut tree.children, VecDeque::new());
+        let root_label_id = self.insert_label(&tree.label);
+        let child = if children.len() == 0 {
+            Child::Leaf(Leaf {deleted: false, data: Data::Label(root_label_id)})
+        } else {
+            Child::Node(Node::new(root_label_id), Some(children.len()))
+        };
+        let root_addr = self.push_child(0, child);
 
-        let mut label_path = path.to_owned();
-        label_path.push_str("/label.sdsl");
+        //insert the tree
+        measure_performance!(self.insert_tree(root_addr, children), self.statistic.time_for_io_tree_parsing);
+        Ok(())
+    }
 
-        load_structure_from_file(&structure_path);
-        load_pointer_from_file(&pointer_path);
-        load_merge_type_from_file(&merge_type_path);
-        load_label_from_file(&label_path);
+    fn insert_arena_tree(&mut self, tree: &ArenaTree<L>) -> std::result::Result<(), BuildError> {
+        let root = tree.root().ok_or(BuildError::Empty)?;
 
-        let mut top_tree_builder = TopTreeBuilder {
-            nodes: Vec::new(),
-            leafs: Vec::new(),
-            edges: Vec::new(),
+        let (number_of_nodes, number_of_leafs) = tree.count();
+        self.reserve(number_of_nodes, number_of_leafs).map_err(BuildError::Memory)?;
 
-            clusters: HashMap::new(),
-            labels: HashMap::new(),
+        self.insert_dummy_node();
 
-            cluster_vector: Vec::new(),
-            cluster_size: Vec::new(),
-            label_vector:  Vec::new(),
+        //the labels are already interned so every label is hashed only once
+        let label_ids: Vec<usize> = tree.labels().iter().map(|label| self.insert_label(label)).collect();
 
-            flags: Flags::default(),
+        //the root is the child of the dummy node
+        measure_performance!(self.insert_arena_nodes(tree, root, &label_ids), self.statistic.time_for_io_tree_parsing);
+        Ok(())
+    }
 
-            statistic: Statistic::new(),
+    ///checks the memory limit with the size of the tree before anything is allocated and reserves the arrays
+    ///the capacity_hint of the flags replaces the size for the reservation only
+    fn reserve(&mut self, number_of_nodes: usize, number_of_leafs: usize) -> std::result::Result<(), MemoryError> {
+        self.check_memory(number_of_nodes, number_of_leafs)?;
 
-            number_of_steps: 0,
+        let (number_of_nodes, number_of_leafs) = match self.flags.capacity_hint {
+            Some(capacity_hint) => (capacity_hint, capacity_hint),
+            None => (number_of_nodes, number_of_leafs),
         };
+        self.nodes.reserve(number_of_nodes + 1);
+        self.leafs.reserve(number_of_leafs);
+        self.edges.reserve(number_of_nodes + number_of_leafs);
+        Ok(())
+    }
 
-        let structure = get_structure_vector();
-        let pointer = get_pointer_vector();
-        let merge_type = get_merge_type_vector();
-        let label = get_label_vector();
+    ///fails if a tree of this size can not be addressed or exceeds the memory_limit of the flags
+    fn check_memory(&self, number_of_nodes: usize, number_of_leafs: usize) -> std::result::Result<(), MemoryError> {
+        //leafs are addressed above usize::max_value() >> 1 so both have to stay below it
+        if number_of_nodes.max(number_of_leafs) >= (usize::max_value() >> 1) - 1 {
+            return Err(MemoryError::TooManyNodes(number_of_nodes.saturating_add(number_of_leafs)));
+        }
 
-        top_tree_builder.detraverse(structure, pointer, merge_type, label);
+        if let Some(limit) = self.flags.memory_limit {
+            let needed = MemoryEstimate::new(number_of_nodes, number_of_leafs).total();
+            if needed > limit { return Err(MemoryError::BudgetExceeded { needed, limit }) }
+        }
+        Ok(())
+    }
 
-        run_command!(&directory; "tar", "cf", &format!("{}.tar", name), name);
-        run_command!("rm", "-r", path);
+    ///inserts the nodes in preorder, the same order as insert_tree
+    fn insert_arena_nodes(&mut self, tree: &ArenaTree<L>, root: usize, label_ids: &[usize]) {
+        //(parent in the builder, next child in the arena)
+        let mut stack = vec![(0, Some(root))];
+        while let Some((parent, child)) = stack.pop() {
+            if let Some(child) = child {
+                stack.push((parent, tree.next_sibling(child)));
+
+                let label_id = label_ids[tree.label_id(child)];
+                let number_of_children = tree.number_of_children(child);
+                let new_child = if number_of_children == 0 {
+                    Child::Leaf(Leaf{ deleted: false, data: Data::Label(label_id) })
+                } else {
+                    Child::Node(Node::new(label_id), Some(number_of_children))
+                };
 
-        Ok(top_tree_builder)
+                let pos = self.push_child(parent, new_child);
+                if number_of_children > 0 {
+                    stack.push((pos, tree.first_child(child)));
+                }
+            }
+        }
     }
 
-    #[allow(non_snake_case)]
-    fn build_from_IO_tree(&mut self, tree: IO_Tree) {
-        //insert the dummy label
-        let dummy_label_id = self.insert_label(&DUMMY_NODE_LABEL.to_owned());
-        self.nodes.push(Node::new(dummy_label_id));
+    ///the dummy node is the parent of the root, it has place for exactly one child
+    ///it has no label because its data is never part of a cluster
+    fn insert_dummy_node(&mut self) {
+        self.nodes.push(Node::new(usize::max_value()));
 
         //insert a place holder child
         self.edges.push(Edge::default());
+        self.nodes[0].reserved_end = 1;
+    }
 
-        //insert the root
-        let root_label_id = self.insert_label(&tree.label);
-        let child = if tree.children.len() == 0 {
-            Child::Leaf(Leaf {deleted: false, data: Data::Label(root_label_id)})
-        } else {
-            Child::Node(Node::new(root_label_id), Some(tree.children.len()))
-        };
-        let root_addr = self.push_child(0, child);
-
-        //insert the tree
-        measure_performance!(self.rec_insert_tree(root_addr, tree), self.statistic.time_for_io_tree_parsing);
+    ///runs the merge rounds on the inserted tree
+    fn build_top_dag(&mut self) -> std::result::Result<(), Cancelled> {
+        self.record_input_statistic();
 
         //build the TopDag
         //stop if dummy has only a leaf as child
         while self.edges[self.nodes[0].first_child].index < usize::max_value() >> 1 {
+            let number_of_clusters = self.cluster_vector.len();
             if cfg!(feature = "performance_test") {
                 self.number_of_steps += 1;
+                self.start_round();
                 let mut time_stamp = Instant::now();
 
                 self.horizontal_merge();
 
                 let first_timestamp = time_stamp.elapsed();
-                debug!("Horizontal merge finished\n{:?}", self);
+                debug!("Horizontal merge finished\n{}", self.debug_arrays());
+                self.notify_observer(Phase::Horizontal)?;
                 time_stamp = Instant::now();
 
                 self.vertical_merge();
 
                 let second_timestamp = time_stamp.elapsed();
-                debug!("Vertical merge finished\n{:?}", self);
+                debug!("Vertical merge finished\n{}", self.debug_arrays());
                 self.statistic.timestamps_vector.push((first_timestamp, second_timestamp));
+                self.finish_round(number_of_clusters);
+                self.notify_observer(Phase::Vertical)?;
             } else {
                 self.number_of_steps += 1;
+                self.start_round();
                 self.horizontal_merge();
-                debug!("Horizontal merge finished\n{:?}", self);
+                debug!("Horizontal merge finished\n{}", self.debug_arrays());
+                self.notify_observer(Phase::Horizontal)?;
                 self.vertical_merge();
-                debug!("Vertical merge finished\n{:?}", self);
+                debug!("Vertical merge finished\n{}", self.debug_arrays());
+                self.finish_round(number_of_clusters);
+                self.notify_observer(Phase::Vertical)?;
             }
         }
         //make statistic
         self.statistic.number_of_merge_rounds = self.number_of_steps;
-        self.statistic.number_of_nodes_in_io_tree = self.nodes.len();
-        self.statistic.number_of_leafs_in_io_tree = self.leafs.len();
-        self.statistic.number_of_edges_in_io_tree = self.edges.len();
 
-        self.statistic.number_of_leafs_in_top_dag = self.label_vector.len() - 1; //exclude the dummy lable
+        self.statistic.number_of_leafs_in_top_dag = self.label_vector.len();
         self.statistic.number_of_nodes_in_top_dag = self.cluster_vector.len();
 
+        //stop the threads of the parallel merge
+        self.workers = None;
+
         //clear the unneeded vectors
         self.nodes.clear();
         self.leafs.clear();
         self.edges.clear();
+        Ok(())
     }
 
-    pub fn get_statistic(&self) -> &Statistic {
-        &self.statistic
-    }
-
-    pub fn save_to_file(&self, path: &str) {
-        let (structure, pointer, merge_type, label) = self.traverse();
-
-        set_structure_vector(structure);
-        set_pointer_vector(pointer);
-        set_merge_type_vector(merge_type);
-        set_label_vector_from_string(label);
-
-        run_command!("mkdir", "-p", path);
-
-        let mut structure_path = path.to_owned();
-        structure_path.push_str("/structure.sdsl");
-
-        let mut pointer_path = path.to_owned();
-        pointer_path.push_str("/pointer.sdsl");
-
-        let mut merge_type_path = path.to_owned();
-        merge_type_path.push_str("/merge_type.sdsl");
+    ///counts the inserted tree, nothing is merged or deleted jet
+    fn record_input_statistic(&mut self) {
+        //the dummy node is not part of the tree
+        let number_of_nodes = self.nodes.len() - 1;
+        let number_of_leafs = self.leafs.len();
 
-        let mut label_path = path.to_owned();
-        label_path.push_str("/label.sdsl");
+        self.statistic.number_of_nodes_in_io_tree = number_of_nodes;
+        self.statistic.number_of_leafs_in_io_tree = number_of_leafs;
+        self.statistic.number_of_edges_in_io_tree = (number_of_nodes + number_of_leafs).saturating_sub(1);
+        self.statistic.number_of_labels_in_io_tree = self.label_vector.len();
+        self.statistic.depth_of_io_tree = self.depth();
 
-        if !save_structure_to_file(&structure_path) {panic!("Error: Could not save structure_vector")}
-        if !save_pointer_to_file(&pointer_path) {panic!("Error: Could not save pointer_vector")}
-        if !save_merge_type_to_file(&merge_type_path) {panic!("Error: Could not save merge_type_vector")}
-        if !save_label_to_file(&label_path) {panic!("Error: Could not save label_vector")}
+        self.live_nodes = number_of_nodes + number_of_leafs;
+        self.live_edges = self.statistic.number_of_edges_in_io_tree;
+    }
 
-        let mut path_list = path.split('/').collect::<Vec<&str>>();
-        let name = path_list.pop().unwrap();
-        let mut directory = String::new();
-        for elem in path_list {
-            directory.push_str(elem);
-            directory.push('/');
+    ///nodes on the longest path from the root to a leaf
+    fn depth(&self) -> usize {
+        let mut depth = 0;
+        //(node, depth of the node), the dummy node has depth 0
+        let mut stack = vec![(0, 0)];
+        while let Some((node, node_depth)) = stack.pop() {
+            for edge in &self.edges[self.nodes[node].first_child..self.nodes[node].last_child] {
+                if edge.index < usize::max_value() >> 1 {
+                    stack.push((edge.index, node_depth + 1));
+                } else {
+                    depth = depth.max(node_depth + 1);
+                }
+            }
         }
-        run_command!(&directory; "tar", "cf", &format!("{}.tar", name), name);
-        run_command!("rm", "-r", path);
+        depth
     }
 
-    #[allow(non_snake_case)]
-    pub fn get_IO_tree(&mut self) -> IO_Tree {
-        let mut dummy_node = IO_Tree {
-            label: DUMMY_NODE_LABEL.to_owned(),
-            children: VecDeque::new(),
+    fn start_round(&mut self) {
+        self.round_statistic = RoundStatistic {
+            round: self.number_of_steps,
+            live_nodes_before: self.live_nodes,
+            live_edges_before: self.live_edges,
+            ..RoundStatistic::default()
         };
-
-        dummy_node.children.push_back(IO_Tree {
-            label: (self.cluster_vector.len() - 1 + self.label_vector.len()).to_string(),
-            children: VecDeque::new(),
-        });
-
-        measure_performance!(self.rec_get_IO_tree(&mut dummy_node, 0), self.statistic.time_for_decompression);
-
-        assert!(dummy_node.children.len() == 1);
-        dummy_node.children.pop_back().unwrap()
     }
 
-    #[allow(non_snake_case)]
-    fn rec_get_IO_tree(&self, parent: &mut IO_Tree, index: usize) {
-        if let Ok(mut cluster_index) = usize::from_str(&parent.children[index].label) {
-            if cluster_index < self.label_vector.len() {
-                //we have a leaf
-                let label = &self.label_vector[cluster_index];
-                parent.children[index].label = label.to_owned();
-            } else {
-                cluster_index -= self.label_vector.len();
-                let cluster = &self.cluster_vector[cluster_index];
-
-                match cluster {
-                    Cluster {merge_type: MergeType::AB, first_child, second_child} => {
-                        //we add the first cluster over the second. The existing child transforms to the second cluster
-
-                        let new_node = IO_Tree {
-                            label: first_child.to_string(),
-                            children: VecDeque::new(),
-                        };
-
-                        //swap child and new_node
-                        parent.children.push_back(new_node);
-                        let mut second_cluster = parent.children.swap_remove_back(index).unwrap();
-
-                        //change the label of the old child
-                        second_cluster.label = second_child.to_string();
-
-                        //push the child back into the tree
-                        parent.children[index].children.push_back(second_cluster);
-
-                        //rec call on both nodes
-                        self.rec_get_IO_tree(&mut parent.children[index], 0);
-
-                        self.rec_get_IO_tree(parent, index);
-                    },
-
-                    Cluster {merge_type: MergeType::CE, first_child, second_child} => {
-                        parent.children[index].label = first_child.to_string();
-
-                        parent.children.insert(index + 1, IO_Tree {
-                            label: second_child.to_string(),
-                            children: VecDeque::new(),
-                        });
-
-                        //rec call on both nodes
-                        self.rec_get_IO_tree(parent, index + 1);
-
-                        self.rec_get_IO_tree(parent, index);
-                    },
-
-                    Cluster {merge_type: MergeType::DE, first_child, second_child} => {
-                        parent.children[index].label = second_child.to_string();
-
-                        parent.children.insert(index, IO_Tree {
-                            label: first_child.to_string(),
-                            children: VecDeque::new(),
-                        });
+    ///number_of_clusters is the number of clusters before the round
+    fn finish_round(&mut self, number_of_clusters: usize) {
+        debug_assert_eq!(self.live_nodes, self.number_of_live_nodes());
+        debug_assert_eq!(self.live_edges, self.number_of_live_edges());
+
+        let mut round_statistic = mem::replace(&mut self.round_statistic, RoundStatistic::default());
+        round_statistic.live_nodes_after = self.live_nodes;
+        round_statistic.live_edges_after = self.live_edges;
+        round_statistic.new_clusters = self.cluster_vector.len() - number_of_clusters;
+        round_statistic.reused_clusters = round_statistic.merges() - round_statistic.new_clusters;
+        self.statistic.rounds.push(round_statistic);
+    }
 
-                        //rec call on both nodes
-                        self.rec_get_IO_tree(parent, index + 1);
+    fn notify_observer(&mut self, phase: Phase) -> std::result::Result<(), Cancelled> {
+        if self.observer.is_none() { return Ok(()) }
 
-                        self.rec_get_IO_tree(parent, index);
-                    },
-                }
-            }
+        let progress = Progress {
+            round: self.number_of_steps,
+            phase,
+            live_nodes: self.live_nodes,
+            number_of_clusters: self.cluster_vector.len(),
+        };
+        match self.observer.as_mut().unwrap().on_merge(&progress) {
+            Control::Continue => Ok(()),
+            Control::Abort => Err(Cancelled { progress }),
+        }
+    }
 
+    ///counts what live_nodes should be, only to check the counter in debug builds
+    fn number_of_live_nodes(&self) -> usize {
+        //the dummy node is not part of the tree
+        let live_nodes = self.nodes.iter().skip(1).filter(|node| !node.deleted).count();
+        let live_leafs = self.leafs.iter().filter(|leaf| !leaf.deleted).count();
+        live_nodes + live_leafs
+    }
 
-        } else {panic!("Error: Database is corrupt")}
+    fn number_of_live_edges(&self) -> usize {
+        //the edge of the dummy node is not part of the tree
+        self.edges.iter().filter(|edge| !edge.deleted).count().saturating_sub(1)
     }
 
     fn horizontal_merge(&mut self) {
         use MergeRule::{SimplifiedStandardRules, FastAdvancedRules, SlowAdvancedRules};
+        if self.flags.threads > 1 {
+            self.parallel_horizontal_merge();
+            return;
+        }
+
         let mut index = 0;
         while index < self.nodes.len() {
             //check if node is not deleted
@@ -566,6 +613,11 @@ impl TopTreeBuilder {
 
     fn vertical_merge(&mut self) {
         use MergeRule::{SimplifiedStandardRules, FastAdvancedRules, SlowAdvancedRules};
+        if self.flags.threads > 1 {
+            self.parallel_vertical_merge();
+            return;
+        }
+
         let mut index = 0;
         while index < self.nodes.len() {
             //check if node is not deleted
@@ -615,7 +667,7 @@ impl TopTreeBuilder {
 
             if self.edges[first_cluster.child].index >= usize::max_value() >> 1 { return } //child is a leaf
             first_cluster.parent = self.edges[first_cluster.child].index;
-            if !self.nodes[first_cluster.parent].first_child + 1 == self.nodes[first_cluster.parent].last_child { return } //child has more than one child
+            if self.nodes[first_cluster.parent].first_child + 1 != self.nodes[first_cluster.parent].last_child { return } //child has more than one child
             first_cluster.child = self.nodes[first_cluster.parent].first_child;
         }
     }
@@ -736,7 +788,6 @@ impl TopTreeBuilder {
 
     fn merge(&mut self, first_cluster: NodeHandle, second_cluster: NodeHandle, merge_type: MergeType) {
         assert!(first_cluster.parent != second_cluster.parent || merge_type != MergeType::AB);
-        use MergeType::{AB,CE,DE};
         //get the id of the new cluster
         let first_node = self.edges[first_cluster.child].index;
         let second_node = self.edges[second_cluster.child].index;
@@ -746,12 +797,25 @@ impl TopTreeBuilder {
             second_child: self.get_cluster_index(second_node),
         };
         //check for slowing down
-        if      cluster.first_child as f64 > self.flags.slowing_down.powf(self.number_of_steps as f64)
-             || cluster.second_child as f64 > self.flags.slowing_down.powf(self.number_of_steps as f64) {
+        if is_slowed_down(&cluster, self.slowing_down_bound()) {
+            self.round_statistic.slowed_down_merges += 1;
             return;
         }
 
         let cluster_id = self.add_cluster(cluster);
+        self.apply_merge(first_cluster, second_cluster, merge_type, cluster_id);
+    }
+
+    ///replaces the two clusters by the cluster with the given id
+    fn apply_merge(&mut self, first_cluster: NodeHandle, second_cluster: NodeHandle, merge_type: MergeType, cluster_id: usize) {
+        use MergeType::{AB,CE,DE};
+        match merge_type {
+            AB => self.round_statistic.ab_merges += 1,
+            CE => self.round_statistic.ce_merges += 1,
+            DE => self.round_statistic.de_merges += 1,
+        }
+        self.live_nodes -= 1;
+        self.live_edges -= 1;
 
         match merge_type {
             AB => { //means A or B
@@ -809,6 +873,11 @@ impl TopTreeBuilder {
         }
     }
 
+    ///clusters with a child id above this are not built in this round
+    fn slowing_down_bound(&self) -> f64 {
+        self.flags.slowing_down.powf(self.number_of_steps as f64)
+    }
+
     /// builds a cluster from the node
     /// node must be an index from the node array
     /// returns the index of the cluster of the node
@@ -838,17 +907,18 @@ impl TopTreeBuilder {
             self.clusters.insert(cluster.clone(), old_cluster_id);
             cluster_id = old_cluster_id;
         } else { //cluster was not inserted jet
-            let mut size = 1;
+            //saturating so a forged archive with a lot of sharing can not overflow
+            let mut size: usize = 1;
             if cluster.first_child < self.label_vector.len() {
                 size += 1;
             } else {
-                size += self.cluster_size[cluster.first_child - self.label_vector.len()];
+                size = size.saturating_add(self.cluster_size[cluster.first_child - self.label_vector.len()]);
             }
 
             if cluster.second_child < self.label_vector.len() {
-                size += 1;
+                size = size.saturating_add(1);
             } else {
-                size += self.cluster_size[cluster.second_child - self.label_vector.len()];
+                size = size.saturating_add(self.cluster_size[cluster.second_child - self.label_vector.len()]);
             }
 
             self.cluster_size.push(size);
@@ -858,32 +928,44 @@ impl TopTreeBuilder {
         cluster_id
     }
 
-    fn rec_insert_tree(&mut self, node: usize, tree: IO_Tree) {
-        for child in tree.children {
-            //insert label
-            if tree.label == DUMMY_NODE_LABEL { panic!("Error: Node must not be called {}", DUMMY_NODE_LABEL) }
-            let label_id = self.insert_label(&child.label);
+    ///inserts the children in preorder, the same order as a recursive insertion
+    ///uses an explicit stack so the depth of the tree is not limited by the call stack
+    fn insert_tree(&mut self, node: usize, children: VecDeque<IO_Tree<L>>) {
+        //(node, children of the node that are not inserted jet)
+        let mut stack = vec![(node, children.into_iter())];
 
-            let number_of_children = child.children.len();
-            let new_child = if number_of_children == 0 {
-                Child::Leaf(Leaf{ deleted: false, data: Data::Label(label_id) })
-            } else {
-                Child::Node(Node::new(label_id), Some(number_of_children))
-            };
+        while let Some((node, mut children)) = stack.pop() {
+            if let Some(mut child) = children.next() {
+                stack.push((node, children));
 
-            let pos = self.push_child(node, new_child);
-            self.rec_insert_tree(pos, child);
+                //insert label
+                let label_id = self.insert_label(&child.label);
+                let grand_children = mem::replace(&mut child.children, VecDeque::new());
+
+                let number_of_children = grand_children.len();
+                let new_child = if number_of_children == 0 {
+                    Child::Leaf(Leaf{ deleted: false, data: Data::Label(label_id) })
+                } else {
+                    Child::Node(Node::new(label_id), Some(number_of_children))
+                };
+
+                let pos = self.push_child(node, new_child);
+                if number_of_children > 0 {
+                    stack.push((pos, grand_children.into_iter()));
+                }
+            }
         }
     }
 
-    fn insert_label(&mut self, name: &String) -> usize {
-        let mut label_id = self.label_vector.len();
-        if let Some(old_label) = self.labels.insert(name.clone(), label_id) {
-            self.labels.insert(name.clone(), old_label);
-            label_id = old_label;
-        } else { //label was not inserted jet
-            self.label_vector.push(name.to_string());
+    ///the label is only cloned the first time it is seen
+    fn insert_label(&mut self, label: &L) -> usize {
+        if let Some(old_label) = self.labels.get(label) {
+            return *old_label;
         }
+        //label was not inserted jet
+        let label_id = self.label_vector.len();
+        self.label_vector.push(label.clone());
+        self.labels.insert(label.clone(), label_id);
         label_id
     }
 
@@ -891,8 +973,6 @@ impl TopTreeBuilder {
     fn push_child(&mut self, parent: usize, child: Child) -> usize {
         use structs::Child::{Node, Leaf};
         if parent < usize::max_value() >> 1 {
-            let last_child = self.nodes[parent].last_child;
-
             //add the child to the Vector
             let child_addr;
             match child {
@@ -916,54 +996,65 @@ impl TopTreeBuilder {
                     for _ in 0..number_of_children {
                         self.edges.push(Edge::default());
                     }
+                    self.nodes[child_addr].reserved_end = self.edges.len();
                 },
             }
 
-            //add the addr to child
-            //if     last child is not greater than the array
-            //   and the edge at that position is deleted so we can overwrite it
-            //   and we do not interfere with the next node first_child pointer
-            if     last_child < self.edges.len() 
-                && self.edges[last_child].deleted 
-                && (( parent + 1 < self.nodes.len() 
-                      && self.nodes[parent + 1].first_child > last_child)
-                      || parent + 1 >= self.nodes.len()) {
-
-                //replace edge
-                self.edges[last_child].deleted = false;
-                self.edges[last_child].index = child_addr;
-
-                //adjust last child addr
-                self.nodes[parent].last_child += 1;
-            } else {
-                //insert child
-                self.edges.insert(last_child, Edge {
-                    deleted: false,
-                    index: child_addr});
-
-                //adjust last child addr
-                self.nodes[parent].last_child += 1;
-
-                //check if we are at the end of the edges array
-                if last_child < self.edges.len() {
-                    println!("Warning: Edge buffer is empty");
-
-                    //adjust all node child addr
-                    let mut index = parent + 1;
-                    while index < self.nodes.len() {
-                        self.nodes[index].first_child += 1;
-                        self.nodes[index].last_child += 1;
-                        index += 1;
-                    }
-                }
+            //the reserved space is full so the children move to the end of the edges array
+            if self.nodes[parent].last_child >= self.nodes[parent].reserved_end {
+                self.grow_children(parent);
             }
 
+            //add the addr to child
+            let last_child = self.nodes[parent].last_child;
+            self.edges[last_child].deleted = false;
+            self.edges[last_child].index = child_addr;
+
+            //adjust last child addr
+            self.nodes[parent].last_child += 1;
+
             child_addr
         } else {
             panic!("Error: Can not push a Child to a Leaf");
         }
     }
 
+    ///moves the children of the node to the end of the edges array and doubles the reserved space
+    ///the old edges stay deleted, every child is moved at most a constant number of times on average
+    fn grow_children(&mut self, node: usize) {
+        let first_child = self.nodes[node].first_child;
+        let last_child = self.nodes[node].last_child;
+        let number_of_children = last_child - first_child;
+
+        let new_first_child = self.edges.len();
+        for index in first_child..last_child {
+            let edge = self.edges[index].clone();
+            self.edges.push(edge);
+            self.edges[index] = Edge::default();
+        }
+        for _ in 0..number_of_children.max(1) {
+            self.edges.push(Edge::default());
+        }
+
+        self.nodes[node].first_child = new_first_child;
+        self.nodes[node].last_child = new_first_child + number_of_children;
+        self.nodes[node].reserved_end = self.edges.len();
+    }
+
+    ///moves the children of every node next to each other in the order of the nodes
+    ///and drops the deleted edges grow_children left behind, afterwards every node has exactly the edges of its children
+    fn compact_edges(&mut self) {
+        let mut edges = Vec::with_capacity(self.edges.len());
+        for node in self.nodes.iter_mut() {
+            let first_child = edges.len();
+            edges.extend_from_slice(&self.edges[node.first_child..node.last_child]);
+            node.first_child = first_child;
+            node.last_child = edges.len();
+            node.reserved_end = edges.len();
+        }
+        self.edges = edges;
+    }
+
     ///swaps the children around to restore the assertion from the Node struct
     fn compress_children(&mut self, parent: usize) {
         //find first not deleted edge
@@ -988,88 +1079,22 @@ impl TopTreeBuilder {
         self.nodes[parent].last_child = backward_index;
     }
 
-    fn traverse(&self) -> (Vec<bool>, Vec<usize>, Vec<i32>, Vec<String>) {
-        let mut structure = Vec::new();
-        let mut merge_types = Vec::new();
-        let lable = self.label_vector.clone();
-
-        let mut cluster_pointer: Vec<Uninitialized<usize>> = vec![Uninitialized::new(); self.cluster_vector.len()];
-        let mut pointer: Vec<Uninitialized<usize>> = vec![Uninitialized::new(); self.cluster_vector.len()*2];
-
-        //(current index, first occurrence)
-        let mut work_stack = vec![(self.cluster_vector.len() - 1, true, 0)];
-        let mut current_traverse_index: usize = 0;
-
-        //current_index is the true index in the cluster vector
-        while let Some((current_index, first_occurrence, own_traverse_index)) = work_stack.pop() {
-            let Cluster{ merge_type, first_child, second_child } = &self.cluster_vector[current_index];
-
-            if first_occurrence {
-                //increase the traverse index one time for each true cluster
-                current_traverse_index += 1;
-
-                //push us on the stack again
-                work_stack.push((current_index, false, own_traverse_index));
-
-                //push the merge type
-                merge_types.push(merge_type.get_i32());
-
-                //first child
-                if *first_child < self.label_vector.len() {
-                    //it is a leaf
-                    structure.push(false);
-                    pointer[own_traverse_index*2].set_value(*first_child);
-                } else if cluster_pointer[first_child - self.label_vector.len()].is_initialized() {
-                    //we already have this cluster
-                    structure.push(false);
-                    pointer[own_traverse_index*2].set_value(*cluster_pointer[first_child - self.label_vector.len()]);
-                } else {
-                    //it is a true cluster
-                    structure.push(true);
-                    work_stack.push((first_child - self.label_vector.len(), true, current_traverse_index));
-                }
-
-                //we need to push a dummy value
-                structure.push(false);
-            } else {
-                //second child
-                if *second_child < self.label_vector.len() {
-                    //it is a leaf
-                    structure[own_traverse_index*2 + 1] = false; //should already be false
-                    pointer[own_traverse_index*2 + 1].set_value(*second_child);
-                } else if cluster_pointer[second_child - self.label_vector.len()].is_initialized() {
-                    //we already have this cluster
-                    structure[own_traverse_index*2 + 1] = false; //should already be false
-                    pointer[own_traverse_index*2 + 1].set_value(*cluster_pointer[second_child - self.label_vector.len()]);
-                } else {
-                    //it is a true cluster
-                    structure[own_traverse_index*2 + 1] = true;
-                    work_stack.push((second_child - self.label_vector.len(), true, current_traverse_index));
-                }
-
-                //we have finished this cluster so we mark it as finished
-                //we set the pointer to the number of clusters we already have plus the offset for the labels
-                cluster_pointer[current_index].set_value(own_traverse_index + self.label_vector.len());
-            }
-        }
-        let pointer = pointer.iter().filter_map(|elem| elem.clone().try_into_inner()).collect();
-        (structure, pointer, merge_types, lable)
-    }
-
-    fn detraverse(&mut self, structure: Vec<bool>, pointer: Vec<usize>, merge_types: Vec<i32>, labels: Vec<String>) {
-        //build label Hash Map
-        for (index, label) in labels.iter().enumerate() {
-            self.labels.insert(label.clone(), index);
-        }
+    fn detraverse(&mut self, structure: Vec<bool>, pointer: Vec<usize>, merge_types: Vec<i32>, labels: Vec<L>, limits: &DecodeLimits) -> std::result::Result<(), DecodeError> {
+        check_stream_lengths(&structure, &pointer, &merge_types, labels.len(), limits)?;
 
         //clear data if we have some
         self.label_vector = labels;
         self.labels.clear();
         self.cluster_vector.clear();
+        self.cluster_size.clear();
         self.clusters.clear();
 
+        //a tree of a single node is only its label
+        if merge_types.is_empty() { return Ok(()) }
+
         //build index Hash Map
         //the traversal index is not the index in the cluster vector so this maps the traversal index to the cluster index
+        //only finished clusters are inserted so a pointer can not reference itself or a cluster that comes later
         let mut traversal_index_to_cluster_index = HashMap::new();
 
         //build rank Hash Map
@@ -1091,22 +1116,18 @@ impl TopTreeBuilder {
         while let Some((index, mut merge_type, mut first_child)) = workstack.pop() {
             if merge_type.is_uninitialized() {
                 //first encounter of this cluster prototype
-                merge_type.set_value(MergeType::from_i32(merge_types[index]));
+                let new_merge_type = MergeType::from_i32(merge_types[index]).ok_or(DecodeError::UnknownMergeType(merge_types[index]))?;
+                merge_type.set_value(new_merge_type);
                 if structure[index*2] {
                     //push self on stack
                     workstack.push((index, merge_type, first_child));
 
                     //first child is a true cluster so we push it on the stack
                     global_index += 1;
+                    if global_index >= merge_types.len() { return Err(DecodeError::LengthMismatch) }
                     workstack.push((global_index, Uninitialized::new(), Uninitialized::new()));
                 } else {
-                    return_value = if pointer[*rank.get(&(index*2)).unwrap()] < self.label_vector.len() {
-                        //we have a leaf
-                        pointer[*rank.get(&(index*2)).unwrap()]
-                    } else {
-                        //we have a cluster copy
-                        *traversal_index_to_cluster_index.get(&(pointer[*rank.get(&(index*2)).unwrap()] - self.label_vector.len())).unwrap()
-                    };
+                    return_value = self.resolve_pointer(pointer[rank[&(index*2)]], &traversal_index_to_cluster_index)?;
 
                     //push self on stack
                     workstack.push((index, merge_type, first_child));
@@ -1123,19 +1144,14 @@ impl TopTreeBuilder {
 
                     //second child is a true cluster so we push it on the stack
                     global_index += 1;
+                    if global_index >= merge_types.len() { return Err(DecodeError::LengthMismatch) }
                     workstack.push((global_index, Uninitialized::new(), Uninitialized::new()));
                 } else {
                     //second child is either a leaf or a copy of a already known cluster so we do not need to push it
-                    let second_child = if pointer[*rank.get(&(index*2 + 1)).unwrap()] < self.label_vector.len() {
-                        //we have a leaf
-                        pointer[*rank.get(&(index*2 + 1)).unwrap()]
-                    } else {
-                        //we have a cluster copy
-                        *traversal_index_to_cluster_index.get(&(pointer[*rank.get(&(index*2 + 1)).unwrap()] - self.label_vector.len())).unwrap()
-                    };
+                    let second_child = self.resolve_pointer(pointer[rank[&(index*2 + 1)]], &traversal_index_to_cluster_index)?;
                     //build a new cluster and push it to vector and hash map
                     let cluster = Cluster { merge_type: merge_type.clone().into_inner(), first_child: *first_child, second_child};
-                    let cluster_index = self.add_cluster(cluster);
+                    let cluster_index = self.add_decoded_cluster(cluster, limits)?;
                     traversal_index_to_cluster_index.insert(index, cluster_index);
                     return_value = cluster_index;
                 }
@@ -1144,58 +1160,214 @@ impl TopTreeBuilder {
                 let second_child = return_value;
                 //build a new cluster and push it to vector and hash map
                 let cluster = Cluster { merge_type: merge_type.clone().into_inner(), first_child: *first_child, second_child};
-                let cluster_index = self.add_cluster(cluster);
+                let cluster_index = self.add_decoded_cluster(cluster, limits)?;
                 traversal_index_to_cluster_index.insert(index, cluster_index);
                 return_value = cluster_index;
             }
         }
+
+        //every stored cluster has to be reached from the root
+        if global_index + 1 != merge_types.len() { return Err(DecodeError::LengthMismatch) }
+
+        //the root has to expand to exactly one node on the top level
+        //children are always decoded before their parent so one pass in order is enough
+        let mut number_of_top_nodes: Vec<usize> = Vec::with_capacity(self.cluster_vector.len());
+        for cluster in &self.cluster_vector {
+            let first = self.get_number_of_top_nodes(cluster.first_child, &number_of_top_nodes);
+            let top_nodes = if cluster.merge_type == MergeType::AB {
+                first
+            } else {
+                first.saturating_add(self.get_number_of_top_nodes(cluster.second_child, &number_of_top_nodes))
+            };
+            number_of_top_nodes.push(top_nodes);
+        }
+        if number_of_top_nodes.last() != Some(&1) { return Err(DecodeError::InvalidRoot) }
+
+        Ok(())
     }
-}
 
-impl Debug for TopTreeBuilder {
-    fn fmt(&self, f: &mut Formatter) -> Result {
-        let mut output = String::new();
-        write!(output, "Label HashMap: \n")?;
-        let mut labels = vec![];
-        for label in self.labels.clone() {
-            labels.push(label);
+    ///maps a stored pointer to a label id or to the cluster id of an already decoded cluster
+    fn resolve_pointer(&self, pointer: usize, traversal_index_to_cluster_index: &HashMap<usize, usize>) -> std::result::Result<usize, DecodeError> {
+        if pointer < self.label_vector.len() {
+            //we have a leaf
+            Ok(pointer)
+        } else {
+            //we have a cluster copy
+            traversal_index_to_cluster_index.get(&(pointer - self.label_vector.len()))
+                .cloned()
+                .ok_or(DecodeError::InvalidPointer(pointer))
         }
-        labels.sort_by_key(|value| {value.1});
-        for label in labels {
-            write!(output, "{:?} \n", label)?;
+    }
+
+    ///adds a cluster read from an archive and checks that it is new and not too large
+    fn add_decoded_cluster(&mut self, cluster: Cluster, limits: &DecodeLimits) -> std::result::Result<usize, DecodeError> {
+        let number_of_clusters = self.cluster_vector.len();
+        let cluster_index = self.add_cluster(cluster);
+        if self.cluster_vector.len() == number_of_clusters { return Err(DecodeError::DuplicateCluster) }
+
+        //a cluster of size s expands to (s + 1) / 2 nodes
+        if self.cluster_size[number_of_clusters] / 2 >= limits.max_decompressed_nodes { return Err(DecodeError::TooLarge) }
+        Ok(cluster_index)
+    }
+
+    fn get_number_of_top_nodes(&self, id: usize, number_of_top_nodes: &[usize]) -> usize {
+        if id < self.label_vector.len() {
+            1
+        } else {
+            number_of_top_nodes[id - self.label_vector.len()]
         }
+    }
+}
 
-        write!(output, "\nNodes Vector: \n")?;
-        for node in self.nodes.clone() {
-            write!(output, "{:?} \n", node)?;
+fn is_slowed_down(cluster: &Cluster, slowing_down_bound: f64) -> bool {
+    cluster.first_child as f64 > slowing_down_bound || cluster.second_child as f64 > slowing_down_bound
+}
+
+impl<L: Label> TopTreeBuilder<L> {
+    ///the arrays of the builder, the labels are printed as ids so they do not need Debug
+    fn debug_arrays(&self) -> String {
+        let mut output = String::new();
+        write!(output, "Nodes Vector: \n").unwrap();
+        for node in &self.nodes {
+            write!(output, "{:?} \n", node).unwrap();
         }
 
-        write!(output, "\nLeaf Vector: \n")?;
-        for leaf in self.leafs.clone() {
-            write!(output, "{:?} \n", leaf)?;
+        write!(output, "\nLeaf Vector: \n").unwrap();
+        for leaf in &self.leafs {
+            write!(output, "{:?} \n", leaf).unwrap();
         }
 
-        write!(output, "\nEdge Vector: \n")?;
+        write!(output, "\nEdge Vector: \n").unwrap();
         for mut edge in self.edges.clone() {
             if edge.index < usize::max_value() >> 1 { //node
-                write!(output, "Node: ")?;
+                write!(output, "Node: ").unwrap();
             } else { //leaf
-                write!(output, "Leaf: ")?;
+                write!(output, "Leaf: ").unwrap();
             }
             edge.index %= usize::max_value() >> 1;
-            write!(output, "{:?} \n", edge)?;
+            write!(output, "{:?} \n", edge).unwrap();
         }
 
-        write!(output, "\nCluster HashMap: \n")?;
+        write!(output, "\nCluster HashMap: \n").unwrap();
         let mut clusters = vec![];
         for cluster in self.clusters.clone() {
             clusters.push(cluster);
         }
         clusters.sort_by_key(|value| {value.1});
         for (index, cluster) in clusters.iter().enumerate() {
-            write!(output, "{:?} size: {} \n", cluster, self.cluster_size[index])?;
+            write!(output, "{:?} size: {} \n", cluster, self.cluster_size[index]).unwrap();
         }
+        output
+    }
+}
+
+impl<L: Label + Debug> Debug for TopTreeBuilder<L> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let mut output = String::new();
+        write!(output, "Label HashMap: \n")?;
+        let mut labels = vec![];
+        for label in self.labels.clone() {
+            labels.push(label);
+        }
+        labels.sort_by_key(|value| {value.1});
+        for (label, id) in labels {
+            write!(output, "({:?}, {}) \n", label, id)?;
+        }
+
+        write!(f, "Debug info: \n{}\n{}", output, self.debug_arrays())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use arena_tree::ArenaTree;
+    use flags_and_statistic::Flags;
+    use io_tree::IO_Tree;
+    use push_builder::PushBuilder;
+    use TopTreeBuilder;
+
+    fn node(label: &[u8], children: Vec<IO_Tree>) -> IO_Tree {
+        IO_Tree { label: label.to_vec(), children: children.into_iter().collect::<VecDeque<_>>() }
+    }
+
+    ///6 inner nodes and 6 leafs
+    fn small_tree() -> IO_Tree {
+        node(b"a", vec![
+            node(b"b", vec![node(b"c", vec![]), node(b"d", vec![])]),
+            node(b"b", vec![node(b"c", vec![]), node(b"d", vec![]), node(b"e", vec![node(b"f", vec![])])]),
+            node(b"g", vec![node(b"h", vec![node(b"i", vec![])])]),
+        ])
+    }
+
+    ///every node gets exactly the edges of its children, so no edge is moved and none is left deleted
+    fn assert_exact_layout(builder: &TopTreeBuilder) {
+        assert_eq!(builder.nodes.len(), 6 + 1);
+        assert_eq!(builder.leafs.len(), 6);
+        assert_eq!(builder.edges.len(), 6 + 6);
+        assert!(builder.edges.iter().all(|edge| !edge.deleted));
+        assert!(builder.nodes.iter().all(|node| node.last_child == node.reserved_end));
+    }
+
+    #[test]
+    fn arrays_are_sized_from_the_input() {
+        let builder = TopTreeBuilder::new_from_IO_tree(small_tree(), None).unwrap();
+        assert_exact_layout(&builder);
+        //no fixed preallocation, only what the tree needs
+        assert!(builder.edges.capacity() < 64);
+        assert!(builder.nodes.capacity() < 64);
+        assert!(builder.leafs.capacity() < 64);
+
+        let mut xml = Vec::new();
+        small_tree().write_xml(&mut xml).unwrap();
+        let arena_tree = ArenaTree::from_xml_reader(&xml[..]).unwrap();
+        assert_exact_layout(&TopTreeBuilder::new_from_arena_tree(&arena_tree, None).unwrap());
+        assert_exact_layout(&TopTreeBuilder::from_xml_reader(&xml[..], None).unwrap());
+    }
+
+    fn push(push_builder: &mut PushBuilder, tree: &IO_Tree) {
+        if tree.children.is_empty() {
+            push_builder.leaf(tree.label.clone()).unwrap();
+        } else {
+            push_builder.open(tree.label.clone()).unwrap();
+            for child in tree.children.iter() {
+                push(push_builder, child);
+            }
+            push_builder.close().unwrap();
+        }
+    }
+
+    #[test]
+    fn pushed_trees_are_compacted_before_the_build() {
+        let mut push_builder = PushBuilder::new(None);
+        push(&mut push_builder, &small_tree());
+        assert_exact_layout(&push_builder.into_builder().unwrap());
+
+        //nodes opened without children become leafs and leave no edge behind
+        let mut push_builder = PushBuilder::new(None);
+        push_builder.open(b"a".to_vec()).unwrap();
+        for _ in 0..5 {
+            push_builder.open(b"b".to_vec()).unwrap();
+            push_builder.close().unwrap();
+        }
+        push_builder.close().unwrap();
+        let builder = push_builder.into_builder().unwrap();
+        assert_eq!(builder.edges.len(), 1 + 5);
+        assert!(builder.edges.iter().all(|edge| !edge.deleted));
+    }
 
-        write!(f, "Debug info: \n{}", output)
+    #[test]
+    fn capacity_hint_replaces_the_size_of_the_input() {
+        let flags = Flags { capacity_hint: Some(1000), ..Flags::default() };
+        let builder = TopTreeBuilder::new_from_IO_tree(small_tree(), Some(flags)).unwrap();
+        assert!(builder.edges.capacity() >= 2000);
+        assert!(builder.leafs.capacity() >= 1000);
+        assert_exact_layout(&builder);
+
+        //a hint that is too small only costs reallocations, the layout is the same
+        let flags = Flags { capacity_hint: Some(1), ..Flags::default() };
+        assert_exact_layout(&TopTreeBuilder::new_from_IO_tree(small_tree(), Some(flags)).unwrap());
     }
 }
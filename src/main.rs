@@ -0,0 +1,467 @@
+extern crate top_tree_compression;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::process;
+use std::time::{Duration, Instant};
+
+use top_tree_compression::TopTreeBuilder;
+use top_tree_compression::top_dag::TopDag;
+use top_tree_compression::arena_tree::ArenaTree;
+use top_tree_compression::compressed::CompressedTopDag;
+use top_tree_compression::flags_and_statistic::{Flags, MergeRule, Statistic};
+use top_tree_compression::io_tree::{GenError, GenResult};
+use top_tree_compression::validation::{DecodeLimits, DecodeError};
+
+
+
+const USAGE: &str = "Usage:
+    top_tree_compression compress <in.xml> <out> [--merge-rule <rule>] [--slowing-down <factor>] [--format <format>]
+    top_tree_compression decompress <archive> <out.xml> [--format <format>] [--max-nodes <number>]
+    top_tree_compression stats <in.xml> [--merge-rule <rule>] [--slowing-down <factor>] [--json | --csv]
+    top_tree_compression verify <in.xml> [--merge-rule <rule>] [--slowing-down <factor>]
+    top_tree_compression benchmark <directory> [--merge-rule <rules>] [--slowing-down <factors>] [--csv]
+
+A path of - reads from stdin or writes to stdout.
+Formats: container (default) is the single file container,
+archive is the tar archive of save_to_file, a path of an archive ends with .tar
+decompress rejects archives that expand to more than --max-nodes nodes, by default 2^26
+Merge rules: simplified (default), fast-advanced, slow-advanced
+benchmark compresses every file of the directory with every combination of the comma separated
+merge rules and slowing down factors, by default all merge rules with a slowing down of 4";
+
+const MERGE_RULES: [(&str, MergeRule); 3] = [
+    ("simplified", MergeRule::SimplifiedStandardRules),
+    ("fast-advanced", MergeRule::FastAdvancedRules),
+    ("slow-advanced", MergeRule::SlowAdvancedRules),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum StatisticFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ArchiveFormat {
+    ///the single file container of write_container
+    Container,
+    ///the tar archive of save_to_file and new_fom_file
+    Archive,
+}
+
+///the arguments after the subcommand
+struct Options {
+    paths: Vec<String>,
+    ///empty if not given
+    merge_rules: Vec<MergeRule>,
+    ///empty if not given
+    slowing_downs: Vec<f64>,
+    format: StatisticFormat,
+    archive_format: ArchiveFormat,
+    ///the default limit if not given
+    max_nodes: Option<usize>,
+}
+
+impl Options {
+    ///the flags for the commands that compress with a single configuration
+    fn flags(&self) -> GenResult<Flags> {
+        if self.merge_rules.len() > 1 || self.slowing_downs.len() > 1 {
+            return Err(usage_error("Only benchmark takes more than one merge rule or slowing down factor"));
+        }
+
+        let mut flags = Flags::default();
+        if let Some(merge_rule) = self.merge_rules.first() {
+            flags.merge_rule = *merge_rule;
+        }
+        if let Some(slowing_down) = self.slowing_downs.first() {
+            flags.slowing_down = *slowing_down;
+        }
+        Ok(flags)
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args[0] == "--help" || args[0] == "-h" {
+        println!("{}", USAGE);
+        return;
+    }
+
+    if let Err(error) = run(&args[0], &args[1..]) {
+        eprintln!("Error: {}", error);
+        process::exit(1);
+    }
+}
+
+fn run(command: &str, args: &[String]) -> GenResult<()> {
+    let options = parse_options(args)?;
+    let paths: Vec<&str> = options.paths.iter().map(|path| path.as_str()).collect();
+
+    match (command, &paths[..]) {
+        ("compress", &[input, output]) => compress(input, output, options.flags()?, options.archive_format),
+        ("decompress", &[input, output]) => decompress(input, output, options.archive_format, options.max_nodes),
+        ("stats", &[input]) => stats(input, options.flags()?, options.format),
+        ("verify", &[input]) => verify(input, options.flags()?),
+        ("benchmark", &[directory]) => benchmark(directory, &options),
+        ("compress", _) | ("decompress", _) | ("stats", _) | ("verify", _) | ("benchmark", _) => Err(usage_error(&format!("Wrong number of paths for {}", command))),
+        _ => Err(usage_error(&format!("Unknown command {}", command))),
+    }
+}
+
+fn parse_options(args: &[String]) -> GenResult<Options> {
+    let mut options = Options {
+        paths: Vec::new(),
+        merge_rules: Vec::new(),
+        slowing_downs: Vec::new(),
+        format: StatisticFormat::Text,
+        archive_format: ArchiveFormat::Container,
+        max_nodes: None,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--merge-rule" => {
+                options.merge_rules = match args.next().map(|values| parse_list(values, merge_rule)) {
+                    Some(Some(merge_rules)) => merge_rules,
+                    _ => return Err(usage_error("--merge-rule needs simplified, fast-advanced or slow-advanced")),
+                };
+            },
+
+            "--slowing-down" => {
+                options.slowing_downs = match args.next().map(|values| parse_list(values, slowing_down)) {
+                    Some(Some(slowing_downs)) => slowing_downs,
+                    _ => return Err(usage_error("--slowing-down needs a finite number greater than 1")),
+                };
+            },
+
+            "--format" => {
+                options.archive_format = match args.next().map(|value| value.as_str()) {
+                    Some("container") => ArchiveFormat::Container,
+                    Some("archive") => ArchiveFormat::Archive,
+                    _ => return Err(usage_error("--format needs container or archive")),
+                };
+            },
+
+            "--max-nodes" => {
+                options.max_nodes = match args.next().map(|value| value.parse()) {
+                    Some(Ok(max_nodes)) => Some(max_nodes),
+                    _ => return Err(usage_error("--max-nodes needs a number")),
+                };
+            },
+
+            "--json" => options.format = StatisticFormat::Json,
+            "--csv" => options.format = StatisticFormat::Csv,
+
+            //a single - is stdin or stdout
+            _ if arg.starts_with("--") => return Err(usage_error(&format!("Unknown option {}", arg))),
+            _ => options.paths.push(arg.clone()),
+        }
+    }
+    Ok(options)
+}
+
+///parses the comma separated values, None if one of them cannot be parsed
+fn parse_list<T, F: Fn(&str) -> Option<T>>(values: &str, parse: F) -> Option<Vec<T>> {
+    values.split(',').map(|value| parse(value)).collect()
+}
+
+///the bound of a round is slowing_down to the power of the round, with 1 or less it never grows and the build does not end
+fn slowing_down(value: &str) -> Option<f64> {
+    match value.parse::<f64>() {
+        Ok(slowing_down) if slowing_down.is_finite() && slowing_down > 1.0 => Some(slowing_down),
+        _ => None,
+    }
+}
+
+fn merge_rule(name: &str) -> Option<MergeRule> {
+    MERGE_RULES.iter().find(|(rule_name, _)| *rule_name == name).map(|(_, merge_rule)| *merge_rule)
+}
+
+fn merge_rule_name(merge_rule: MergeRule) -> &'static str {
+    MERGE_RULES.iter().find(|(_, rule)| *rule == merge_rule).map(|(name, _)| *name).unwrap()
+}
+
+fn usage_error(message: &str) -> GenError {
+    From::from(format!("{}\n\n{}", message, USAGE))
+}
+
+fn open_input(path: &str) -> GenResult<Box<BufRead>> {
+    if path == "-" {
+        Ok(Box::new(BufReader::new(io::stdin())))
+    } else {
+        Ok(Box::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+fn create_output(path: &str) -> GenResult<Box<Write>> {
+    if path == "-" {
+        Ok(Box::new(BufWriter::new(io::stdout())))
+    } else {
+        Ok(Box::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+///a new directory for archives that are read from stdin or written to stdout, the caller removes it
+fn temp_directory() -> GenResult<String> {
+    let directory = env::temp_dir().join(format!("top_tree_cli_{}", process::id()));
+    fs::create_dir_all(&directory)?;
+    Ok(directory.to_str().ok_or("Error: The temporary directory is not valid unicode")?.to_owned())
+}
+
+///the path of the archive without .tar, save_to_file and new_fom_file append it
+fn archive_path(path: &str) -> GenResult<&str> {
+    if path.ends_with(".tar") {
+        Ok(path.trim_right_matches(".tar"))
+    } else {
+        Err(usage_error(&format!("The archive {} has to end with .tar", path)))
+    }
+}
+
+///the name of the directory in the first header of the tar archive
+///save_to_file packs a single directory and new_fom_file expects the archive to be named like it
+fn archive_name(bytes: &[u8]) -> Option<String> {
+    let name = bytes.get(..100)?;
+    let name = &name[..name.iter().position(|&byte| byte == 0).unwrap_or(name.len())];
+    let name = std::str::from_utf8(name).ok()?.split('/').next()?;
+    if name.is_empty() || name == "." || name == ".." {
+        None
+    } else {
+        Some(name.to_owned())
+    }
+}
+
+fn compress(input: &str, output: &str, flags: Flags, archive_format: ArchiveFormat) -> GenResult<()> {
+    let top_dag = TopTreeBuilder::from_xml_reader(open_input(input)?, Some(flags))?.build();
+
+    match archive_format {
+        ArchiveFormat::Container => {
+            let mut writer = create_output(output)?;
+            top_dag.to_compressed().write_container(&mut writer)?;
+            writer.flush()?;
+        },
+
+        ArchiveFormat::Archive if output == "-" => {
+            let directory = temp_directory()?;
+            let path = format!("{}/archive", directory);
+            top_dag.save_to_file(&path);
+            let result = File::open(format!("{}.tar", path)).and_then(|mut archive| {
+                let mut stdout = io::stdout();
+                io::copy(&mut archive, &mut stdout)?;
+                stdout.flush()
+            });
+            let _ = fs::remove_dir_all(&directory);
+            result?;
+        },
+
+        ArchiveFormat::Archive => {
+            top_dag.save_to_file(archive_path(output)?);
+        },
+    }
+    Ok(())
+}
+
+fn decompress(input: &str, output: &str, archive_format: ArchiveFormat, max_nodes: Option<usize>) -> GenResult<()> {
+    let mut limits = DecodeLimits::default();
+    if let Some(max_nodes) = max_nodes {
+        limits.max_decompressed_nodes = max_nodes;
+    }
+    let tree = match archive_format {
+        //a file is mapped, stdin has to be read completely
+        ArchiveFormat::Container if input == "-" => CompressedTopDag::from_reader(&mut io::stdin(), &limits)?.get_IO_tree()?,
+        ArchiveFormat::Container => CompressedTopDag::open_mmap_with_limits(input, &limits)?.get_IO_tree()?,
+
+        //new_fom_file extracts a file, so stdin is written to a temporary archive first
+        ArchiveFormat::Archive if input == "-" => {
+            let mut bytes = Vec::new();
+            io::stdin().read_to_end(&mut bytes)?;
+            let name = archive_name(&bytes).ok_or(DecodeError::CannotLoad("archive"))?;
+
+            let directory = temp_directory()?;
+            let path = format!("{}/{}", directory, name);
+            let result = File::create(format!("{}.tar", path))
+                .and_then(|mut archive| archive.write_all(&bytes))
+                .map_err(GenError::from)
+                .and_then(|_| TopDag::<Vec<u8>>::new_fom_file_with_limits(&path, &limits));
+            let _ = fs::remove_dir_all(&directory);
+            result?.get_IO_tree()
+        },
+        ArchiveFormat::Archive => TopDag::<Vec<u8>>::new_fom_file_with_limits(archive_path(input)?, &limits)?.get_IO_tree(),
+    };
+
+    let mut writer = create_output(output)?;
+    tree.write_xml(&mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn stats(input: &str, flags: Flags, format: StatisticFormat) -> GenResult<()> {
+    let top_dag = TopTreeBuilder::from_xml_reader(open_input(input)?, Some(flags))?.build();
+    let statistic = top_dag.get_statistic();
+
+    match format {
+        StatisticFormat::Text => print!("{}", statistic),
+        StatisticFormat::Json => println!("{}", statistic.to_json()),
+        StatisticFormat::Csv => {
+            println!("{}", Statistic::csv_header());
+            println!("{}", statistic.to_csv_row());
+        },
+    }
+    Ok(())
+}
+
+fn verify(input: &str, flags: Flags) -> GenResult<()> {
+    if input == "-" {
+        //stdin can only be read once so it is kept in memory
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes)?;
+        verify_source(|| Ok(&bytes[..]), flags)
+    } else {
+        verify_source(|| Ok(BufReader::new(File::open(input)?)), flags)
+    }
+}
+
+///checks the TopDAG after construction and again after it is written to a container in memory and loaded
+///the input is streamed for every check, so it is never stored as a tree next to the decompressed one
+fn verify_source<R: BufRead, F: Fn() -> GenResult<R>>(open: F, flags: Flags) -> GenResult<()> {
+    let top_dag = TopTreeBuilder::from_xml_reader(open()?, Some(flags))?.build();
+    top_dag.get_shared_tree().verify_against_xml_reader(open()?)?;
+
+    let mut container = Vec::new();
+    top_dag.to_compressed().write_container(&mut container)?;
+    //the input was parsed completely, so its TopDAG is not limited like an unknown archive
+    let loaded = CompressedTopDag::from_reader(&mut &container[..], &DecodeLimits::unlimited())?;
+    loaded.get_shared_tree()?.verify_against_xml_reader(open()?)?;
+
+    //the archive of save_to_file is checked as well, it is loaded by new_fom_file
+    let archive = env::temp_dir().join(format!("top_tree_verify_{}", process::id()));
+    let archive = archive.to_str().ok_or("Error: The temporary directory is not valid unicode")?.to_owned();
+    top_dag.save_to_file(&archive);
+    let loaded = TopDag::<Vec<u8>>::new_fom_file_with_limits(&archive, &DecodeLimits::unlimited());
+    let _ = fs::remove_file(format!("{}.tar", archive));
+    loaded?.get_shared_tree().verify_against_xml_reader(open()?)?;
+
+    let statistic = top_dag.get_statistic();
+    println!("Ok: {} nodes and {} leafs, {} bytes compressed", statistic.number_of_nodes_in_io_tree, statistic.number_of_leafs_in_io_tree, container.len());
+    Ok(())
+}
+
+///the result of compressing one file with one configuration
+struct Measurement {
+    file: String,
+    merge_rule: MergeRule,
+    slowing_down: f64,
+    ///insertion and merge rounds, the xml is parsed once for all configurations
+    build_time: Duration,
+    ///clusters and labels
+    top_dag_size: usize,
+    ///bytes of the container
+    encoded_size: usize,
+    number_of_merge_rounds: usize,
+}
+
+///compresses every file of the directory with every configuration and prints a table
+fn benchmark(directory: &str, options: &Options) -> GenResult<()> {
+    let merge_rules = if options.merge_rules.is_empty() {
+        MERGE_RULES.iter().map(|(_, merge_rule)| *merge_rule).collect()
+    } else {
+        options.merge_rules.clone()
+    };
+    let slowing_downs = if options.slowing_downs.is_empty() {
+        vec![Flags::default().slowing_down]
+    } else {
+        options.slowing_downs.clone()
+    };
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            files.push(entry.path());
+        }
+    }
+    files.sort();
+
+    let mut measurements = Vec::new();
+    for path in &files {
+        let file = path.display().to_string();
+        //a file that cannot be parsed is reported and skipped so the rest of the corpus is measured
+        let tree = match File::open(path).map_err(GenError::from).and_then(|input| ArenaTree::from_xml_reader(BufReader::new(input))) {
+            Ok(tree) => tree,
+            Err(error) => {
+                eprintln!("Skipping {}: {}", file, error);
+                continue;
+            },
+        };
+
+        for merge_rule in &merge_rules {
+            for slowing_down in &slowing_downs {
+                let mut flags = Flags::default();
+                flags.merge_rule = *merge_rule;
+                flags.slowing_down = *slowing_down;
+
+                let start = Instant::now();
+                let top_dag = TopTreeBuilder::new_from_arena_tree(&tree, Some(flags))?.build();
+                let build_time = start.elapsed();
+
+                let mut container = Vec::new();
+                top_dag.to_compressed().write_container(&mut container)?;
+
+                let statistic = top_dag.get_statistic();
+                measurements.push(Measurement {
+                    file: file.clone(),
+                    merge_rule: *merge_rule,
+                    slowing_down: *slowing_down,
+                    build_time,
+                    top_dag_size: statistic.number_of_nodes_in_top_dag + statistic.number_of_leafs_in_top_dag,
+                    encoded_size: container.len(),
+                    number_of_merge_rounds: statistic.number_of_merge_rounds,
+                });
+            }
+        }
+    }
+
+    if options.format == StatisticFormat::Csv {
+        println!("file,merge_rule,slowing_down,build_seconds,top_dag_size,encoded_size,number_of_merge_rounds");
+        for measurement in &measurements {
+            println!("{},{},{},{},{},{},{}", measurement.file, merge_rule_name(measurement.merge_rule), measurement.slowing_down,
+                seconds(measurement.build_time), measurement.top_dag_size, measurement.encoded_size, measurement.number_of_merge_rounds);
+        }
+        return Ok(());
+    }
+
+    println!("{:<40} {:<14} {:>8} {:>12} {:>14} {:>14} {:>7}", "file", "merge rule", "slowing", "seconds", "TopDAG size", "encoded bytes", "rounds");
+    for measurement in &measurements {
+        println!("{:<40} {:<14} {:>8} {:>12.3} {:>14} {:>14} {:>7}", measurement.file, merge_rule_name(measurement.merge_rule), measurement.slowing_down,
+            seconds(measurement.build_time), measurement.top_dag_size, measurement.encoded_size, measurement.number_of_merge_rounds);
+    }
+
+    //the configurations summed over all files, the smallest encoded size first
+    let mut totals: Vec<(MergeRule, f64, f64, usize, usize)> = Vec::new();
+    for merge_rule in &merge_rules {
+        for slowing_down in &slowing_downs {
+            let mut total = (*merge_rule, *slowing_down, 0.0, 0, 0);
+            for measurement in measurements.iter().filter(|measurement| measurement.merge_rule == *merge_rule && measurement.slowing_down == *slowing_down) {
+                total.2 += seconds(measurement.build_time);
+                total.3 += measurement.top_dag_size;
+                total.4 += measurement.encoded_size;
+            }
+            totals.push(total);
+        }
+    }
+    totals.sort_by_key(|total| total.4);
+
+    println!();
+    println!("{:<14} {:>8} {:>12} {:>14} {:>14}", "total", "slowing", "seconds", "TopDAG size", "encoded bytes");
+    for (merge_rule, slowing_down, build_seconds, top_dag_size, encoded_size) in totals {
+        println!("{:<14} {:>8} {:>12.3} {:>14} {:>14}", merge_rule_name(merge_rule), slowing_down, build_seconds, top_dag_size, encoded_size);
+    }
+    Ok(())
+}
+
+fn seconds(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) * 1e-9
+}
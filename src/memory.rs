@@ -0,0 +1,78 @@
+use std::error::Error;
+use std::fmt::{Formatter, Result, Display};
+use std::mem::size_of;
+
+use structs::{Node, Leaf, Edge, Cluster};
+
+
+
+///a hash map needs about twice the memory of its entries because of its load factor and the stored hashes
+const HASH_MAP_OVERHEAD: usize = 2;
+
+///The memory the builder needs for a tree, in bytes
+///
+///Every merge removes one node, so a tree with n nodes and leafs creates at most n - 1 clusters.
+///The labels are not included, they are stored once each and do not grow during the merge rounds.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryEstimate {
+    pub nodes: usize,
+    pub leafs: usize,
+    pub edges: usize,
+    ///the cluster vector, the cluster sizes and the cluster hash map
+    pub clusters: usize,
+}
+
+impl MemoryEstimate {
+    ///number_of_nodes are the inner nodes of the tree, every node and leaf except the root has one edge
+    pub fn new(number_of_nodes: usize, number_of_leafs: usize) -> MemoryEstimate {
+        let number_of_elements = number_of_nodes.saturating_add(number_of_leafs);
+        let cluster_entry = size_of::<Cluster>() + size_of::<usize>()
+            + HASH_MAP_OVERHEAD * (size_of::<Cluster>() + size_of::<usize>());
+
+        MemoryEstimate {
+            //plus the dummy node
+            nodes: number_of_nodes.saturating_add(1).saturating_mul(size_of::<Node>()),
+            leafs: number_of_leafs.saturating_mul(size_of::<Leaf>()),
+            edges: number_of_elements.saturating_mul(size_of::<Edge>()),
+            clusters: number_of_elements.saturating_mul(cluster_entry),
+        }
+    }
+
+    ///a lower bound for a tree that is still parsed, it is not known jet which elements are leafs
+    ///so all of them are counted as leafs, the smaller kind
+    pub fn while_parsing(number_of_elements: usize) -> MemoryEstimate {
+        MemoryEstimate::new(0, number_of_elements)
+    }
+
+    pub fn total(&self) -> usize {
+        self.nodes.saturating_add(self.leafs).saturating_add(self.edges).saturating_add(self.clusters)
+    }
+}
+
+#[derive(Debug)]
+pub enum MemoryError {
+    ///the tree needs more memory than the limit of the flags
+    BudgetExceeded { needed: usize, limit: usize },
+    ///the tree has more nodes or leafs than can be addressed
+    TooManyNodes(usize),
+}
+
+impl Error for MemoryError {
+    fn description(&self) -> &str {
+        "Memory limit exceeded"
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        None
+    }
+}
+
+impl Display for MemoryError {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        use self::MemoryError::*;
+        match self {
+            BudgetExceeded { needed, limit } => write!(f, "The tree needs about {} bytes but the limit is {} bytes", needed, limit),
+            TooManyNodes(number_of_nodes) => write!(f, "The tree has {} nodes, that are to many to address", number_of_nodes),
+        }
+    }
+}
@@ -0,0 +1,675 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender, Receiver};
+use std::thread::{self, JoinHandle};
+
+use structs::{Node, Leaf, Edge, Cluster, NodeHandle, MergeType, Data};
+use flags_and_statistic::MergeRule;
+use label::Label;
+use {TopTreeBuilder, is_slowed_down};
+
+
+
+///number of dictionary shards per thread, more shards mean less waiting for a lock
+const SHARDS_PER_THREAD: usize = 16;
+
+///A merge that was planned on the snapshot of the round
+struct Merge {
+    first_cluster: NodeHandle,
+    second_cluster: NodeHandle,
+    merge_type: MergeType,
+    cluster: Cluster,
+}
+
+///How the id of a planned cluster is found
+enum Resolution {
+    ///the cluster was built in an earlier round
+    Known(usize),
+    ///first occurrence of the cluster in this round, it gets the next free id
+    New,
+    ///the cluster is planned earlier in this round at (task, position)
+    Duplicate(usize, usize),
+}
+
+///The parts of the builder the planning threads read
+///all merges of one phase only read the state from before the phase so they can be planned independently
+struct Snapshot {
+    nodes: Vec<Node>,
+    leafs: Vec<Leaf>,
+    edges: Vec<Edge>,
+    clusters: HashMap<Cluster, usize>,
+
+    merge_rule: MergeRule,
+    slowing_down_bound: f64,
+    number_of_steps: usize,
+
+    ///merges of all threads that were skipped because of slowing_down
+    slowed_down_merges: AtomicUsize,
+}
+
+///The clusters that are new in this round with the position of their first occurrence
+///split into shards with a lock each
+struct ConcurrentDictionary {
+    shards: Vec<Mutex<HashMap<Cluster, (usize, usize)>>>,
+}
+
+impl ConcurrentDictionary {
+    fn new(number_of_shards: usize) -> ConcurrentDictionary {
+        ConcurrentDictionary {
+            shards: (0..number_of_shards).map(|_| Mutex::new(HashMap::new())).collect(),
+        }
+    }
+
+    fn shard(&self, cluster: &Cluster) -> &Mutex<HashMap<Cluster, (usize, usize)>> {
+        let mut hasher = DefaultHasher::new();
+        cluster.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % self.shards.len()]
+    }
+
+    ///keeps the smallest position so the result does not depend on the order of the threads
+    fn insert(&self, cluster: &Cluster, position: (usize, usize)) {
+        let mut shard = self.shard(cluster).lock().unwrap();
+        let first = shard.entry(cluster.clone()).or_insert(position);
+        if position < *first {
+            *first = position;
+        }
+    }
+
+    fn get(&self, cluster: &Cluster) -> Option<(usize, usize)> {
+        self.shard(cluster).lock().unwrap().get(cluster).cloned()
+    }
+}
+
+///A job of a worker
+enum Job {
+    ///plans the merges of the nodes and enters the new clusters in the dictionary
+    Plan(Arc<Snapshot>, Arc<ConcurrentDictionary>, fn(&Snapshot, usize, &mut Vec<Merge>), Range<usize>),
+    ///resolves the merges of the last Plan, it is only sent after every worker finished planning
+    Resolve,
+}
+
+///the answer of a worker to a job, the merges and their resolutions after Resolve
+type Answer = Option<(Vec<Merge>, Vec<Resolution>)>;
+
+///The threads of the parallel merge
+///they are started with the first parallel phase of a build and stop when the pool is dropped
+pub(crate) struct WorkerPool {
+    jobs: Vec<Sender<Job>>,
+    ///(task, answer or the panic of the worker)
+    answers: Receiver<(usize, thread::Result<Answer>)>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl WorkerPool {
+    pub(crate) fn new(number_of_threads: usize) -> WorkerPool {
+        let (answer_sender, answers) = mpsc::channel();
+        let mut jobs = Vec::with_capacity(number_of_threads);
+        let mut handles = Vec::with_capacity(number_of_threads);
+        for task in 0..number_of_threads {
+            let (job_sender, job_receiver) = mpsc::channel();
+            let answer_sender = answer_sender.clone();
+            handles.push(thread::spawn(move || run_worker(task, job_receiver, answer_sender)));
+            jobs.push(job_sender);
+        }
+
+        WorkerPool { jobs, answers, handles }
+    }
+
+    fn number_of_threads(&self) -> usize {
+        self.jobs.len()
+    }
+
+    ///sends a job to every worker and waits for all answers, in the order of the tasks
+    ///the panic of a worker is resumed on this thread, so no worker waits for a job that never comes
+    fn run<F: Fn(usize) -> Job>(&self, job: F) -> Vec<Answer> {
+        for (task, jobs) in self.jobs.iter().enumerate() {
+            jobs.send(job(task)).expect("Error: Merge thread stopped");
+        }
+
+        let mut answers: Vec<Answer> = (0..self.number_of_threads()).map(|_| None).collect();
+        for _ in 0..self.number_of_threads() {
+            match self.answers.recv().expect("Error: Merge thread stopped") {
+                (task, Ok(answer)) => answers[task] = answer,
+                (_, Err(payload)) => panic::resume_unwind(payload),
+            }
+        }
+        answers
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        //without a sender the workers leave their loop
+        self.jobs.clear();
+        for handle in self.handles.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
+///answers the jobs until the pool is dropped or the worker panicked
+fn run_worker(task: usize, jobs: Receiver<Job>, answers: Sender<(usize, thread::Result<Answer>)>) {
+    //the merges of the last Plan with the snapshot and the dictionary they were planned on
+    let mut planned = None;
+
+    for job in jobs.iter() {
+        let answer = panic::catch_unwind(AssertUnwindSafe(|| match job {
+            Job::Plan(snapshot, dictionary, plan, nodes) => {
+                let mut merges = Vec::new();
+                for node in nodes {
+                    plan(&snapshot, node, &mut merges);
+                }
+
+                for (position, merge) in merges.iter().enumerate() {
+                    if !snapshot.clusters.contains_key(&merge.cluster) {
+                        dictionary.insert(&merge.cluster, (task, position));
+                    }
+                }
+                planned = Some((snapshot, dictionary, merges));
+                None
+            },
+
+            Job::Resolve => {
+                let (snapshot, dictionary, merges) = planned.take().expect("Error: Resolve without Plan");
+                let resolutions = merges.iter().enumerate().map(|(position, merge)| {
+                    if let Some(cluster_id) = snapshot.clusters.get(&merge.cluster) {
+                        Resolution::Known(*cluster_id)
+                    } else {
+                        match dictionary.get(&merge.cluster).unwrap() {
+                            (first_task, first_position) if (first_task, first_position) == (task, position) => Resolution::New,
+                            (first_task, first_position) => Resolution::Duplicate(first_task, first_position),
+                        }
+                    }
+                }).collect();
+                //the snapshot is released before the answer is sent, the builder takes it back afterwards
+                Some((merges, resolutions))
+            },
+        }));
+
+        let panicked = answer.is_err();
+        if answers.send((task, answer)).is_err() || panicked { return }
+    }
+}
+
+impl<L: Label> TopTreeBuilder<L> {
+    ///the horizontal merges of different parents are independent
+    pub(crate) fn parallel_horizontal_merge(&mut self) {
+        let mut parents = self.parallel_merge(plan_horizontal_merge);
+
+        //restore the assertion
+        parents.dedup();
+        for parent in parents {
+            self.compress_children(parent);
+        }
+    }
+
+    ///the vertical merges of different unary chains are independent
+    pub(crate) fn parallel_vertical_merge(&mut self) {
+        self.parallel_merge(plan_vertical_merge);
+    }
+
+    ///plans the merges of every node range on an own worker, resolves the cluster ids in the order
+    ///the sequential merge would build them and applies the merges
+    ///returns the parents of the first clusters of all applied merges
+    fn parallel_merge(&mut self, plan: fn(&Snapshot, usize, &mut Vec<Merge>)) -> Vec<usize> {
+        if self.workers.is_none() {
+            self.workers = Some(WorkerPool::new(self.flags.threads.max(1)));
+        }
+        let number_of_threads = self.workers.as_ref().unwrap().number_of_threads();
+
+        let snapshot = Arc::new(self.take_snapshot());
+        let dictionary = Arc::new(ConcurrentDictionary::new(number_of_threads * SHARDS_PER_THREAD));
+
+        let number_of_nodes = snapshot.nodes.len();
+        let chunk_size = (number_of_nodes + number_of_threads - 1) / number_of_threads;
+
+        let results: Vec<(Vec<Merge>, Vec<Resolution>)> = {
+            let workers = self.workers.as_ref().unwrap();
+            workers.run(|task| {
+                let nodes = task * chunk_size..((task + 1) * chunk_size).min(number_of_nodes);
+                Job::Plan(snapshot.clone(), dictionary.clone(), plan, nodes)
+            });
+
+            //all first occurrences are in the dictionary after every worker answered the Plan
+            workers.run(|_| Job::Resolve).into_iter()
+                .map(|answer| answer.expect("Error: Resolve without an answer"))
+                .collect()
+        };
+
+        drop(dictionary);
+        match Arc::try_unwrap(snapshot) {
+            Ok(snapshot) => {
+                self.round_statistic.slowed_down_merges += snapshot.slowed_down_merges.load(Ordering::Relaxed);
+                self.restore_snapshot(snapshot);
+            },
+            Err(_) => panic!("Error: Snapshot is still in use"),
+        }
+
+        //the merges are applied in the order of the nodes, so a new cluster gets the id the sequential merge gives it
+        //if both planned the same merges. That holds for the simplified rules, they never look at the dictionary.
+        //The advanced rules call try_merge on the snapshot, so unlike the sequential merge they do not see
+        //the clusters built earlier in the same phase and can choose other merges.
+        let mut offsets = Vec::with_capacity(results.len());
+        let mut number_of_merges = 0;
+        for (merges, _) in &results {
+            offsets.push(number_of_merges);
+            number_of_merges += merges.len();
+        }
+
+        let mut cluster_ids = Vec::with_capacity(number_of_merges);
+        let mut parents = Vec::with_capacity(number_of_merges);
+        for (merges, resolutions) in results {
+            for (merge, resolution) in merges.into_iter().zip(resolutions.into_iter()) {
+                let cluster_id = match resolution {
+                    Resolution::Known(cluster_id) => cluster_id,
+                    Resolution::New => self.add_cluster(merge.cluster),
+                    Resolution::Duplicate(task, position) => cluster_ids[offsets[task] + position],
+                };
+                cluster_ids.push(cluster_id);
+                parents.push(merge.first_cluster.parent);
+
+                self.apply_merge(merge.first_cluster, merge.second_cluster, merge.merge_type, cluster_id);
+            }
+        }
+        parents
+    }
+
+    fn take_snapshot(&mut self) -> Snapshot {
+        Snapshot {
+            nodes: mem::replace(&mut self.nodes, Vec::new()),
+            leafs: mem::replace(&mut self.leafs, Vec::new()),
+            edges: mem::replace(&mut self.edges, Vec::new()),
+            clusters: mem::replace(&mut self.clusters, HashMap::new()),
+
+            merge_rule: self.flags.merge_rule,
+            slowing_down_bound: self.slowing_down_bound(),
+            number_of_steps: self.number_of_steps,
+
+            slowed_down_merges: AtomicUsize::new(0),
+        }
+    }
+
+    fn restore_snapshot(&mut self, snapshot: Snapshot) {
+        self.nodes = snapshot.nodes;
+        self.leafs = snapshot.leafs;
+        self.edges = snapshot.edges;
+        self.clusters = snapshot.clusters;
+    }
+}
+
+///the same decisions as the horizontal merge rules of the builder
+fn plan_horizontal_merge(snapshot: &Snapshot, parent: usize, merges: &mut Vec<Merge>) {
+    use MergeRule::{SimplifiedStandardRules, FastAdvancedRules, SlowAdvancedRules};
+    if snapshot.nodes[parent].deleted { return }
+
+    match snapshot.merge_rule {
+        SimplifiedStandardRules => snapshot.ssr_horizontal_merge(parent, merges),
+        FastAdvancedRules => snapshot.far_horizontal_merge(parent, merges),
+        SlowAdvancedRules => {
+            if snapshot.number_of_steps % 5 == 0 {
+                snapshot.far_horizontal_merge(parent, merges);
+            } else {
+                snapshot.sar_horizontal_merge(parent, merges);
+            }
+        },
+    }
+}
+
+///the same decisions as the vertical merge rules of the builder
+fn plan_vertical_merge(snapshot: &Snapshot, node: usize, merges: &mut Vec<Merge>) {
+    use MergeRule::{SimplifiedStandardRules, FastAdvancedRules, SlowAdvancedRules};
+    if snapshot.nodes[node].deleted { return }
+    //node has more than one child or is the dummy node
+    if !(snapshot.nodes[node].first_child + 1 < snapshot.nodes[node].last_child || node == 0) { return }
+
+    for child in snapshot.nodes[node].first_child..snapshot.nodes[node].last_child {
+        let first_cluster = NodeHandle { parent: node, child };
+        match snapshot.merge_rule {
+            SimplifiedStandardRules => snapshot.ssr_vertical_merge(first_cluster, merges),
+            FastAdvancedRules => snapshot.far_vertical_merge(first_cluster, merges),
+            SlowAdvancedRules => {
+                if snapshot.number_of_steps % 5 == 0 {
+                    snapshot.far_vertical_merge(first_cluster, merges);
+                } else {
+                    snapshot.sar_vertical_merge(first_cluster, merges);
+                }
+            },
+        }
+    }
+}
+
+impl Snapshot {
+    fn is_leaf(&self, child: usize) -> bool {
+        self.edges[child].index >= usize::max_value() >> 1
+    }
+
+    ///true if the node is no leaf and has exactly one child
+    fn is_unary(&self, node: usize) -> bool {
+        node < usize::max_value() >> 1 && self.nodes[node].first_child + 1 == self.nodes[node].last_child
+    }
+
+    ///the handle of the edge to the only child of the node
+    fn below(&self, node: usize) -> NodeHandle {
+        NodeHandle { parent: node, child: self.nodes[node].first_child }
+    }
+
+    fn get_cluster_index(&self, node: usize) -> usize {
+        let node_data = if node < usize::max_value() >> 1 {
+            &self.nodes[node].data
+        } else {
+            &self.leafs[node - (usize::max_value() >> 1)].data
+        };
+
+        match node_data {
+            Data::Label(id) => *id,
+            Data::Cluster(id) => *id,
+        }
+    }
+
+    fn cluster(&self, first_cluster: &NodeHandle, second_cluster: &NodeHandle, merge_type: MergeType) -> Cluster {
+        Cluster {
+            merge_type,
+            first_child: self.get_cluster_index(self.edges[first_cluster.child].index),
+            second_child: self.get_cluster_index(self.edges[second_cluster.child].index),
+        }
+    }
+
+    ///returns true if the cluster exists already
+    fn try_merge(&self, first_cluster: &NodeHandle, second_cluster: &NodeHandle, merge_type: MergeType) -> bool {
+        self.clusters.contains_key(&self.cluster(first_cluster, second_cluster, merge_type))
+    }
+
+    ///returns false if the merge is slowed down
+    fn merge(&self, first_cluster: NodeHandle, second_cluster: NodeHandle, merge_type: MergeType, merges: &mut Vec<Merge>) -> bool {
+        let cluster = self.cluster(&first_cluster, &second_cluster, merge_type.clone());
+        if is_slowed_down(&cluster, self.slowing_down_bound) {
+            self.slowed_down_merges.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+
+        merges.push(Merge { first_cluster, second_cluster, merge_type, cluster });
+        true
+    }
+
+    ///the node the edge of first_cluster points to after the merge of first_cluster and second_cluster
+    ///an AB merge moves the child of the second cluster up, a slowed down merge changes nothing
+    fn after_merge(&self, first_cluster: &NodeHandle, second_cluster: &NodeHandle, merged: bool) -> usize {
+        if merged {
+            self.edges[second_cluster.child].index
+        } else {
+            self.edges[first_cluster.child].index
+        }
+    }
+
+    fn ssr_horizontal_merge(&self, parent: usize, merges: &mut Vec<Merge>) {
+        let mut index = self.nodes[parent].first_child;
+        while index + 1 < self.nodes[parent].last_child {
+            let first_cluster = NodeHandle { parent, child: index };
+            let second_cluster = NodeHandle { parent, child: index + 1 };
+
+            if self.is_leaf(index) {
+                self.merge(first_cluster, second_cluster, MergeType::DE, merges);
+            } else if self.is_leaf(index + 1) {
+                self.merge(first_cluster, second_cluster, MergeType::CE, merges);
+            }
+
+            index += 2;
+        }
+    }
+
+    fn far_horizontal_merge(&self, parent: usize, merges: &mut Vec<Merge>) {
+        let mut index = self.nodes[parent].first_child;
+
+        loop {
+            //check first two clusters
+            if index + 1 >= self.nodes[parent].last_child { break }
+            let first_cluster = NodeHandle { parent, child: index };
+            let second_cluster = NodeHandle { parent, child: index + 1 };
+
+            let could_merge = if self.is_leaf(index) {
+                Some(MergeType::DE)
+            } else if self.is_leaf(index + 1) {
+                Some(MergeType::CE)
+            } else {
+                None
+            };
+            if let Some(ref merge_type) = could_merge {
+                if self.try_merge(&first_cluster, &second_cluster, merge_type.clone()) {
+                    self.merge(first_cluster, second_cluster, merge_type.clone(), merges);
+                    index += 2;
+                    continue;
+                }
+            }
+
+            //check if we have a thrid cluster
+            if index + 2 >= self.nodes[parent].last_child {
+                if let Some(merge_type) = could_merge {
+                    self.merge(first_cluster, second_cluster, merge_type, merges);
+                }
+                break;
+            }
+            let third_cluster = NodeHandle { parent, child: index + 2 };
+
+            //check last two clusters
+            if self.try_merge_last_two(&second_cluster, &third_cluster, index, merges) {
+                index += 3;
+                continue;
+            }
+
+            //no merge with thrid cluster so merge first two if possible
+            if let Some(merge_type) = could_merge {
+                self.merge(first_cluster, second_cluster, merge_type, merges);
+            }
+            index += 2;
+        }
+    }
+
+    fn sar_horizontal_merge(&self, parent: usize, merges: &mut Vec<Merge>) {
+        let mut index = self.nodes[parent].first_child;
+
+        loop {
+            //check first two clusters
+            if index + 1 >= self.nodes[parent].last_child { break }
+            let first_cluster = NodeHandle { parent, child: index };
+            let second_cluster = NodeHandle { parent, child: index + 1 };
+
+            let could_merge = if self.is_leaf(index) {
+                Some(MergeType::DE)
+            } else if self.is_leaf(index + 1) {
+                Some(MergeType::CE)
+            } else {
+                None
+            };
+            if let Some(merge_type) = could_merge {
+                if self.try_merge(&first_cluster, &second_cluster, merge_type.clone()) {
+                    self.merge(first_cluster, second_cluster, merge_type, merges);
+                    index += 2;
+                    continue;
+                }
+            }
+
+            //check if we have a thrid cluster
+            if index + 2 >= self.nodes[parent].last_child { break }
+            let third_cluster = NodeHandle { parent, child: index + 2 };
+
+            //check last two clusters
+            if self.try_merge_last_two(&second_cluster, &third_cluster, index, merges) {
+                index += 3;
+                continue;
+            }
+
+            index += 2;
+        }
+    }
+
+    ///merges the second and the third cluster of a horizontal window if that cluster exists already
+    fn try_merge_last_two(&self, second_cluster: &NodeHandle, third_cluster: &NodeHandle, index: usize, merges: &mut Vec<Merge>) -> bool {
+        let merge_type = if self.is_leaf(index + 1) {
+            MergeType::DE
+        } else if self.is_leaf(index + 2) {
+            MergeType::CE
+        } else {
+            return false;
+        };
+
+        if self.try_merge(second_cluster, third_cluster, merge_type.clone()) {
+            self.merge(second_cluster.clone(), third_cluster.clone(), merge_type, merges);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn ssr_vertical_merge(&self, mut first_cluster: NodeHandle, merges: &mut Vec<Merge>) {
+        loop {
+            let second_parent = self.edges[first_cluster.child].index;
+            if !self.is_unary(second_parent) { return }
+            let second_cluster = self.below(second_parent);
+
+            let merged = self.merge(first_cluster.clone(), second_cluster.clone(), MergeType::AB, merges);
+
+            let next = self.after_merge(&first_cluster, &second_cluster, merged);
+            if !self.is_unary(next) { return }
+            first_cluster = self.below(next);
+        }
+    }
+
+    fn far_vertical_merge(&self, mut first_cluster: NodeHandle, merges: &mut Vec<Merge>) {
+        loop {
+            let second_parent = self.edges[first_cluster.child].index;
+            if !self.is_unary(second_parent) { return }
+            let second_cluster = self.below(second_parent);
+
+            if self.try_merge(&first_cluster, &second_cluster, MergeType::AB) {
+                let merged = self.merge(first_cluster.clone(), second_cluster.clone(), MergeType::AB, merges);
+
+                let next = self.after_merge(&first_cluster, &second_cluster, merged);
+                if !self.is_unary(next) { return }
+                first_cluster = self.below(next);
+                continue
+            }
+
+            //build third cluster
+            let third_parent = self.edges[second_cluster.child].index;
+            if !self.is_unary(third_parent) {
+                self.merge(first_cluster, second_cluster, MergeType::AB, merges);
+                return
+            }
+            let third_cluster = self.below(third_parent);
+
+            let next = if self.try_merge(&second_cluster, &third_cluster, MergeType::AB) {
+                let merged = self.merge(second_cluster.clone(), third_cluster.clone(), MergeType::AB, merges);
+                self.after_merge(&second_cluster, &third_cluster, merged)
+            } else {
+                let merged = self.merge(first_cluster.clone(), second_cluster.clone(), MergeType::AB, merges);
+                self.after_merge(&first_cluster, &second_cluster, merged)
+            };
+            if !self.is_unary(next) { return }
+            first_cluster = self.below(next);
+        }
+    }
+
+    fn sar_vertical_merge(&self, mut first_cluster: NodeHandle, merges: &mut Vec<Merge>) {
+        loop {
+            let second_parent = self.edges[first_cluster.child].index;
+            if !self.is_unary(second_parent) { return }
+            let second_cluster = self.below(second_parent);
+
+            if self.try_merge(&first_cluster, &second_cluster, MergeType::AB) {
+                let merged = self.merge(first_cluster.clone(), second_cluster.clone(), MergeType::AB, merges);
+
+                let next = self.after_merge(&first_cluster, &second_cluster, merged);
+                if !self.is_unary(next) { return }
+                first_cluster = self.below(next);
+                continue
+            }
+
+            //build third cluster
+            let third_parent = self.edges[second_cluster.child].index;
+            if !self.is_unary(third_parent) { return }
+            let third_cluster = self.below(third_parent);
+
+            if self.try_merge(&second_cluster, &third_cluster, MergeType::AB) {
+                let merged = self.merge(second_cluster.clone(), third_cluster.clone(), MergeType::AB, merges);
+
+                let next = self.after_merge(&second_cluster, &third_cluster, merged);
+                if !self.is_unary(next) { return }
+                first_cluster = self.below(next);
+            } else {
+                first_cluster = third_cluster;
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicUsize;
+
+    use flags_and_statistic::MergeRule;
+    use super::{Snapshot, ConcurrentDictionary, WorkerPool, Job, Merge};
+
+    fn empty_snapshot() -> Snapshot {
+        Snapshot {
+            nodes: Vec::new(),
+            leafs: Vec::new(),
+            edges: Vec::new(),
+            clusters: HashMap::new(),
+
+            merge_rule: MergeRule::SimplifiedStandardRules,
+            slowing_down_bound: 4.0,
+            number_of_steps: 1,
+
+            slowed_down_merges: AtomicUsize::new(0),
+        }
+    }
+
+    fn panic_at_node_1(_: &Snapshot, node: usize, _: &mut Vec<Merge>) {
+        if node == 1 { panic!("Error: Planned panic") }
+    }
+
+    #[test]
+    fn the_panic_of_a_worker_is_resumed() {
+        let workers = WorkerPool::new(4);
+        let snapshot = Arc::new(empty_snapshot());
+        let dictionary = Arc::new(ConcurrentDictionary::new(4));
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            workers.run(|task| Job::Plan(snapshot.clone(), dictionary.clone(), panic_at_node_1, task..task + 1));
+        }));
+        assert_eq!(result.unwrap_err().downcast_ref::<&str>(), Some(&"Error: Planned panic"));
+
+        //the other workers do not wait for a Resolve, dropping the pool stops all of them
+        drop(workers);
+        assert!(Arc::try_unwrap(snapshot).is_ok());
+    }
+
+    #[test]
+    fn workers_answer_every_phase() {
+        let workers = WorkerPool::new(3);
+        for _ in 0..10 {
+            let snapshot = Arc::new(empty_snapshot());
+            let dictionary = Arc::new(ConcurrentDictionary::new(3));
+            workers.run(|task| Job::Plan(snapshot.clone(), dictionary.clone(), |_, _, _| (), task..task + 1));
+            let answers = workers.run(|_| Job::Resolve);
+            for answer in answers {
+                let (merges, resolutions) = answer.unwrap();
+                assert!(merges.is_empty() && resolutions.is_empty());
+            }
+
+            //the workers released the snapshot with their answer
+            drop(dictionary);
+            assert!(Arc::try_unwrap(snapshot).is_ok());
+        }
+    }
+}
@@ -0,0 +1,332 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use validation::DecodeError;
+use label_encoding::{write_varint, read_varint};
+
+
+
+///longest code the encoder writes and the decoder accepts, a code has to fit into an u64
+///Huffman codes longer than this are shortened by limit_code_lengths
+const MAX_CODE_LENGTH: usize = 64;
+
+///Canonical Huffman code over the pointer values
+///a few clusters are referenced most of the time so they get short codes
+///
+///Layout: number of pointers, number of symbols, every symbol as delta to its predecessor with its code length,
+///followed by the codes, the highest bit of a byte first
+pub fn encode_huffman(pointer: &[usize]) -> Vec<u8> {
+    encode_huffman_with_max_length(pointer, MAX_CODE_LENGTH)
+}
+
+fn encode_huffman_with_max_length(pointer: &[usize], max_length: usize) -> Vec<u8> {
+    let mut frequencies: HashMap<usize, usize> = HashMap::new();
+    for elem in pointer {
+        *frequencies.entry(*elem).or_insert(0) += 1;
+    }
+    let mut symbols: Vec<(usize, usize)> = frequencies.into_iter().collect();
+    symbols.sort();
+
+    let frequencies = symbols.iter().map(|(_, frequency)| *frequency).collect::<Vec<usize>>();
+    let mut code_lengths = code_lengths(&frequencies);
+    limit_code_lengths(&mut code_lengths, &frequencies, max_length);
+    let lengths: Vec<(usize, usize)> = symbols.iter().zip(code_lengths.iter()).map(|((symbol, _), length)| (*symbol, *length)).collect();
+
+    let mut output = Vec::new();
+    write_varint(pointer.len(), &mut output);
+    write_varint(lengths.len(), &mut output);
+    let mut previous = 0;
+    for (symbol, length) in &lengths {
+        write_varint(symbol - previous, &mut output);
+        output.push(*length as u8);
+        previous = *symbol;
+    }
+
+    let codes: HashMap<usize, (u64, usize)> = canonical_codes(&lengths).into_iter().collect();
+    let mut writer = BitWriter { output, current: 0, used: 0 };
+    for elem in pointer {
+        let (code, length) = codes[elem];
+        writer.write(code, length);
+    }
+    writer.finish()
+}
+
+pub fn decode_huffman(input: &[u8], max_pointers: usize) -> Result<Vec<usize>, DecodeError> {
+    let mut pointer = Vec::new();
+    decode_huffman_with(input, max_pointers, |value| { pointer.push(value); Ok(()) })?;
+    Ok(pointer)
+}
+
+///the number of pointers the encoded stream declares
+pub fn huffman_pointer_count(input: &[u8], max_pointers: usize) -> Result<usize, DecodeError> {
+    let mut position = 0;
+    let number_of_pointers = read_varint(input, &mut position).map_err(|_| DecodeError::CannotLoad("pointer"))?;
+    if number_of_pointers > max_pointers { return Err(DecodeError::SectionTooLarge("pointer")) }
+    Ok(number_of_pointers)
+}
+
+///passes the pointers in order to push instead of collecting them
+pub fn decode_huffman_with<F: FnMut(usize) -> Result<(), DecodeError>>(input: &[u8], max_pointers: usize, mut push: F) -> Result<(), DecodeError> {
+    let corrupt = || DecodeError::CannotLoad("pointer");
+
+    let number_of_pointers = huffman_pointer_count(input, max_pointers)?;
+    let mut position = 0;
+    read_varint(input, &mut position)?;
+    let number_of_symbols = read_varint(input, &mut position).map_err(|_| corrupt())?;
+    //every symbol needs at least two bytes so this bounds the allocation
+    if number_of_symbols > input.len() { return Err(corrupt()) }
+
+    let mut lengths = Vec::with_capacity(number_of_symbols);
+    let mut symbol: usize = 0;
+    for index in 0..number_of_symbols {
+        let delta = read_varint(input, &mut position).map_err(|_| corrupt())?;
+        if index > 0 && delta == 0 { return Err(corrupt()) }
+        symbol = symbol.checked_add(delta).ok_or_else(corrupt)?;
+        let length = *input.get(position).ok_or_else(corrupt)? as usize;
+        position += 1;
+        if length == 0 || length > MAX_CODE_LENGTH { return Err(corrupt()) }
+        lengths.push((symbol, length));
+    }
+    if number_of_pointers > 0 && lengths.is_empty() { return Err(corrupt()) }
+
+    //Kraft inequality, otherwise two symbols would get the same code
+    let mut kraft: u128 = 0;
+    for (_, length) in &lengths {
+        kraft += 1u128 << (MAX_CODE_LENGTH - length);
+    }
+    if kraft > 1u128 << MAX_CODE_LENGTH { return Err(corrupt()) }
+
+    //canonical decoding table: for every length the first code and the position of its first symbol
+    let mut sorted = canonical_codes(&lengths);
+    sorted.sort_by_key(|(_, (code, length))| (*length, *code));
+    let mut first_code = [0u64; MAX_CODE_LENGTH + 1];
+    let mut first_index = [0usize; MAX_CODE_LENGTH + 1];
+    let mut count = [0usize; MAX_CODE_LENGTH + 1];
+    for (index, (_, (code, length))) in sorted.iter().enumerate() {
+        if count[*length] == 0 {
+            first_code[*length] = *code;
+            first_index[*length] = index;
+        }
+        count[*length] += 1;
+    }
+
+    //every pointer needs at least one bit
+    if number_of_pointers > (input.len() - position).saturating_mul(8) { return Err(corrupt()) }
+    let mut reader = BitReader { input, position: position * 8 };
+    for _ in 0..number_of_pointers {
+        let mut code: u64 = 0;
+        let mut length = 0;
+        loop {
+            code = (code << 1) | reader.read().ok_or_else(corrupt)? as u64;
+            length += 1;
+            if length > MAX_CODE_LENGTH { return Err(corrupt()) }
+            if count[length] > 0 && code >= first_code[length] && code - first_code[length] < count[length] as u64 {
+                push(sorted[first_index[length] + (code - first_code[length]) as usize].0)?;
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+///Huffman code lengths for the frequencies, a single symbol gets length 1
+fn code_lengths(frequencies: &[usize]) -> Vec<usize> {
+    if frequencies.len() <= 1 { return vec![1; frequencies.len()] }
+
+    //leaves are 0..n, inner nodes are appended
+    let mut parent: Vec<usize> = vec![0; frequencies.len()];
+    let mut heap: BinaryHeap<Reverse<(usize, usize)>> = frequencies.iter().enumerate().map(|(index, frequency)| Reverse((*frequency, index))).collect();
+    while heap.len() > 1 {
+        let Reverse((first_frequency, first)) = heap.pop().unwrap();
+        let Reverse((second_frequency, second)) = heap.pop().unwrap();
+        let node = parent.len();
+        parent.push(node);
+        parent[first] = node;
+        parent[second] = node;
+        heap.push(Reverse((first_frequency + second_frequency, node)));
+    }
+
+    //parents are created after their children so the depths are computed from the root down
+    let mut depth = vec![0; parent.len()];
+    for node in (0..parent.len() - 1).rev() {
+        depth[node] = depth[parent[node]] + 1;
+    }
+    depth[..frequencies.len()].to_vec()
+}
+
+///shortens the codes that are longer than max_length, the code stays prefix free
+///this only happens for skewed frequencies like the Fibonacci numbers, so a heuristic is enough:
+///the long codes are cut to max_length and the Kraft sum is repaired by lengthening the codes just below max_length,
+///the rarest ones first, afterwards the spare room is given to the most frequent symbols
+fn limit_code_lengths(lengths: &mut [usize], frequencies: &[usize], max_length: usize) {
+    if lengths.iter().all(|length| *length <= max_length) { return }
+    assert!(lengths.len() as u128 <= 1u128 << max_length);
+
+    //the Kraft sum in units of 2^-max_length, a prefix free code needs kraft <= limit
+    let limit = 1u128 << max_length;
+    let mut kraft: u128 = 0;
+    for length in lengths.iter_mut() {
+        *length = (*length).min(max_length);
+        kraft += 1u128 << (max_length - *length);
+    }
+
+    //rarest symbols first
+    let mut order: Vec<usize> = (0..lengths.len()).collect();
+    order.sort_by_key(|index| (frequencies[*index], *index));
+
+    //every step lowers the sum by at least one unit and the sum was at most one unit too high for every cut code
+    while kraft > limit {
+        let longest = order.iter().rev().cloned()
+            .filter(|index| lengths[*index] < max_length)
+            //the last maximum is the rarest symbol
+            .max_by_key(|index| lengths[*index])
+            .expect("Error: More symbols than codes of max_length");
+        lengths[longest] += 1;
+        kraft -= 1u128 << (max_length - lengths[longest]);
+    }
+
+    for index in order.into_iter().rev() {
+        while lengths[index] > 1 && kraft + (1u128 << (max_length - lengths[index])) <= limit {
+            kraft += 1u128 << (max_length - lengths[index]);
+            lengths[index] -= 1;
+        }
+    }
+}
+
+///assigns the canonical codes, symbols with the same length get consecutive codes in ascending order
+fn canonical_codes(lengths: &[(usize, usize)]) -> Vec<(usize, (u64, usize))> {
+    let mut sorted: Vec<(usize, usize)> = lengths.to_vec();
+    sorted.sort_by_key(|(symbol, length)| (*length, *symbol));
+
+    let mut codes = Vec::with_capacity(sorted.len());
+    let mut code: u64 = 0;
+    let mut previous_length = match sorted.first() { Some((_, length)) => *length, None => 0 };
+    for (symbol, length) in sorted {
+        code <<= length - previous_length;
+        codes.push((symbol, (code, length)));
+        code = code.wrapping_add(1);
+        previous_length = length;
+    }
+    codes
+}
+
+struct BitWriter {
+    output: Vec<u8>,
+    current: u8,
+    used: usize,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u64, length: usize) {
+        for bit in (0..length).rev() {
+            self.current = (self.current << 1) | ((code >> bit) & 1) as u8;
+            self.used += 1;
+            if self.used == 8 {
+                self.output.push(self.current);
+                self.current = 0;
+                self.used = 0;
+            }
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.used > 0 {
+            self.output.push(self.current << (8 - self.used));
+        }
+        self.output
+    }
+}
+
+struct BitReader<'a> {
+    input: &'a [u8],
+    ///position in bits
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn read(&mut self) -> Option<u8> {
+        let byte = *self.input.get(self.position / 8)?;
+        let bit = (byte >> (7 - self.position % 8)) & 1;
+        self.position += 1;
+        Some(bit)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///pointers whose frequencies are the Fibonacci numbers, they give the deepest Huffman tree
+    fn fibonacci_pointers(number_of_symbols: usize) -> Vec<usize> {
+        let mut pointer = Vec::new();
+        let (mut current, mut next) = (1, 1);
+        for symbol in 0..number_of_symbols {
+            pointer.extend(std::iter::repeat(symbol).take(current));
+            let sum = current + next;
+            current = next;
+            next = sum;
+        }
+        pointer
+    }
+
+    fn kraft_sum(lengths: &[usize], max_length: usize) -> u128 {
+        lengths.iter().map(|length| 1u128 << (max_length - length)).sum()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mixed: Vec<usize> = (0..1000).map(|index| (index * index) % 37 + if index % 3 == 0 { 1 << 40 } else { 0 }).collect();
+        for pointer in &[vec![], vec![7], vec![3; 10], vec![0, usize::max_value(), 0, 5], mixed] {
+            assert_eq!(&decode_huffman(&encode_huffman(pointer), pointer.len()).unwrap(), pointer);
+        }
+    }
+
+    #[test]
+    fn skewed_frequencies_are_limited() {
+        let pointer = fibonacci_pointers(20);
+        let frequencies: Vec<usize> = (1..=20).map(|symbol| pointer.iter().filter(|elem| **elem == symbol - 1).count()).collect();
+        let mut lengths = code_lengths(&frequencies);
+        assert_eq!(*lengths.iter().max().unwrap(), 19);
+
+        limit_code_lengths(&mut lengths, &frequencies, 8);
+        assert!(lengths.iter().all(|length| *length <= 8));
+        assert!(kraft_sum(&lengths, 8) <= 1 << 8);
+        //the frequent symbols keep the short codes
+        assert!(lengths[19] <= lengths[0]);
+
+        for max_length in 5..12 {
+            let encoded = encode_huffman_with_max_length(&pointer, max_length);
+            assert_eq!(decode_huffman(&encoded, pointer.len()).unwrap(), pointer);
+        }
+    }
+
+    #[test]
+    fn limit_is_tight() {
+        //16 symbols need all codes of length 4
+        let frequencies: Vec<usize> = (0..16).map(|index| 1 << index).collect();
+        let mut lengths = code_lengths(&frequencies);
+        limit_code_lengths(&mut lengths, &frequencies, 4);
+        assert_eq!(lengths, vec![4; 16]);
+    }
+
+    #[test]
+    fn short_codes_are_unchanged() {
+        let frequencies = vec![5, 1, 1, 3];
+        let mut lengths = code_lengths(&frequencies);
+        let expected = lengths.clone();
+        limit_code_lengths(&mut lengths, &frequencies, MAX_CODE_LENGTH);
+        assert_eq!(lengths, expected);
+    }
+
+    #[test]
+    fn corrupt_input_is_rejected() {
+        let encoded = encode_huffman(&[1, 2, 3, 1, 1]);
+        assert!(decode_huffman(&encoded, 4).is_err());
+        assert!(decode_huffman(&encoded[..encoded.len() - 1], 5).is_err());
+        //a code length of 65
+        assert!(decode_huffman(&[1, 1, 0, 65, 0], 1).is_err());
+        //two codes of length 1 and one of length 2 violate the Kraft inequality
+        assert!(decode_huffman(&[3, 3, 0, 1, 1, 1, 1, 2, 0], 3).is_err());
+    }
+}
\ No newline at end of file
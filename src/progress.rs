@@ -0,0 +1,64 @@
+use std::error::Error;
+use std::fmt::{Formatter, Result, Display};
+
+
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Phase {
+    Horizontal,
+    Vertical,
+}
+
+///The state of the builder after a horizontal or vertical merge
+#[derive(Clone, Debug)]
+pub struct Progress {
+    ///starts with 1
+    pub round: usize,
+    pub phase: Phase,
+    ///nodes and leafs of the input tree that are not merged into a cluster jet
+    pub live_nodes: usize,
+    ///distinct clusters created so far
+    pub number_of_clusters: usize,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Control {
+    Continue,
+    ///stops the build after the current merge
+    Abort,
+}
+
+///Receives an event after every horizontal and vertical merge
+///
+///Closures taking a Progress and returning a Control are observers.
+pub trait Observer {
+    fn on_merge(&mut self, progress: &Progress) -> Control;
+}
+
+impl<F: FnMut(&Progress) -> Control> Observer for F {
+    fn on_merge(&mut self, progress: &Progress) -> Control {
+        self(progress)
+    }
+}
+
+///The observer aborted the build
+#[derive(Debug)]
+pub struct Cancelled {
+    pub progress: Progress,
+}
+
+impl Error for Cancelled {
+    fn description(&self) -> &str {
+        "Build cancelled"
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        None
+    }
+}
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f, "The build was cancelled in round {} with {} nodes left", self.progress.round, self.progress.live_nodes)
+    }
+}
@@ -0,0 +1,159 @@
+use std::error::Error;
+use std::fmt::{Formatter, Result, Display};
+
+use structs::{Node, Leaf, Child, Data};
+use flags_and_statistic::Flags;
+use label::Label;
+use top_dag::TopDag;
+use progress::{Observer, Cancelled};
+use memory::MemoryError;
+use TopTreeBuilder;
+
+
+
+#[derive(Debug)]
+pub enum BuildError {
+    ///close was called without an open node
+    NoOpenNode,
+    ///the tree already has a root
+    SecondRoot,
+    ///finish was called while nodes are open
+    UnclosedNodes(usize),
+    ///finish was called before a node was added or the arena tree has no node
+    Empty,
+    ///the observer aborted the merge rounds
+    Cancelled(Cancelled),
+    ///the next node would exceed the memory limit
+    Memory(MemoryError),
+    ///a node without children was not the last node that was pushed
+    MisplacedLeaf(usize),
+}
+
+impl Error for BuildError {
+    fn description(&self) -> &str {
+        "Invalid build order"
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match self {
+            BuildError::Cancelled(cancelled) => Some(cancelled),
+            BuildError::Memory(memory_error) => Some(memory_error),
+            _ => None,
+        }
+    }
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        use self::BuildError::*;
+        match self {
+            NoOpenNode => write!(f, "There is no open node to close"),
+            SecondRoot => write!(f, "The tree already has a root"),
+            UnclosedNodes(number_of_nodes) => write!(f, "{} nodes are not closed", number_of_nodes),
+            Empty => write!(f, "The tree has no node"),
+            BuildError::Cancelled(cancelled) => write!(f, "{}", cancelled),
+            BuildError::Memory(memory_error) => write!(f, "{}", memory_error),
+            MisplacedLeaf(node) => write!(f, "The node {} has no children but is not the last node", node),
+        }
+    }
+}
+
+///Builds the TopDAG from a tree that is given node by node in preorder
+///the nodes are written directly into the arrays of the builder so no IO_Tree is needed
+///
+///open starts a node, close ends the last open node, leaf adds a node without children.
+///An opened node that gets no children is stored as a leaf.
+pub struct PushBuilder<L: Label = Vec<u8>> {
+    builder: TopTreeBuilder<L>,
+    ///the open nodes, the last one is the parent of the next node
+    open_nodes: Vec<usize>,
+    has_root: bool,
+}
+
+impl<L: Label> PushBuilder<L> {
+    pub fn new(flags: Option<Flags>) -> PushBuilder<L> {
+        let mut builder = TopTreeBuilder::empty(flags.unwrap_or_default());
+
+        //the number of nodes is not known so the hint is the only estimate
+        if let Some(capacity_hint) = builder.flags.capacity_hint {
+            builder.nodes.reserve(capacity_hint + 1);
+            builder.leafs.reserve(capacity_hint);
+            builder.edges.reserve(capacity_hint * 2);
+        }
+        builder.insert_dummy_node();
+
+        PushBuilder {
+            builder,
+            open_nodes: Vec::new(),
+            has_root: false,
+        }
+    }
+
+    pub fn open(&mut self, label: L) -> std::result::Result<(), BuildError> {
+        //the nodes contain the dummy node so their number is the number of inner nodes after the push
+        self.builder.check_memory(self.builder.nodes.len(), self.builder.leafs.len()).map_err(BuildError::Memory)?;
+        let parent = self.parent()?;
+        let label_id = self.builder.insert_label(&label);
+
+        //the number of children is not known yet, the node grows its edges on demand and finish compacts them
+        let node = self.builder.push_child(parent, Child::Node(Node::new(label_id), Some(0)));
+        self.open_nodes.push(node);
+        Ok(())
+    }
+
+    pub fn leaf(&mut self, label: L) -> std::result::Result<(), BuildError> {
+        self.builder.check_memory(self.builder.nodes.len() - 1, self.builder.leafs.len() + 1).map_err(BuildError::Memory)?;
+        let parent = self.parent()?;
+        let label_id = self.builder.insert_label(&label);
+
+        self.builder.push_child(parent, Child::Leaf(Leaf { deleted: false, data: Data::Label(label_id) }));
+        Ok(())
+    }
+
+    pub fn close(&mut self) -> std::result::Result<(), BuildError> {
+        let node = self.open_nodes.pop().ok_or(BuildError::NoOpenNode)?;
+
+        if self.builder.nodes[node].first_child == self.builder.nodes[node].last_child {
+            //a node without children is a leaf, nothing was pushed after it so it is the last node
+            if node + 1 != self.builder.nodes.len() { return Err(BuildError::MisplacedLeaf(node)) }
+            let Node { data, .. } = self.builder.nodes.pop().unwrap();
+
+            let parent = *self.open_nodes.last().unwrap_or(&0);
+            let edge = self.builder.nodes[parent].last_child - 1;
+            self.builder.edges[edge].index = self.builder.leafs.len() + (usize::max_value() >> 1);
+            self.builder.leafs.push(Leaf { deleted: false, data });
+        }
+        Ok(())
+    }
+
+    ///the observer is called after every horizontal and vertical merge of finish
+    pub fn set_observer<O: Observer + Send + 'static>(&mut self, observer: O) {
+        self.builder.set_observer(observer);
+    }
+
+    ///runs the merge rounds and returns the finished TopDAG
+    pub fn finish(self) -> std::result::Result<TopDag<L>, BuildError> {
+        self.into_builder()?.try_build().map_err(BuildError::Cancelled)
+    }
+
+    ///the builder with the edges of every node next to each other, like after new_from_IO_tree
+    pub(crate) fn into_builder(mut self) -> std::result::Result<TopTreeBuilder<L>, BuildError> {
+        if !self.open_nodes.is_empty() { return Err(BuildError::UnclosedNodes(self.open_nodes.len())) }
+        if !self.has_root { return Err(BuildError::Empty) }
+
+        self.builder.compact_edges();
+        Ok(self.builder)
+    }
+
+    ///the node the next node is added to
+    fn parent(&mut self) -> std::result::Result<usize, BuildError> {
+        if let Some(parent) = self.open_nodes.last() {
+            return Ok(*parent);
+        }
+        if self.has_root { return Err(BuildError::SecondRoot) }
+
+        //the root is the child of the dummy node
+        self.has_root = true;
+        Ok(0)
+    }
+}
@@ -1,7 +1,9 @@
 #![allow(dead_code)]
 pub extern crate libc;
-use self::libc::{c_int, wchar_t, size_t};
+use self::libc::{c_int, c_char, size_t};
 use std::cell::RefCell;
+use std::ffi::CString;
+use std::sync::{Mutex, MutexGuard, Once, ONCE_INIT};
 
 
 #[link(name = "sdsl_interface", kind="static")]
@@ -9,23 +11,23 @@ extern "C" {
 
 //-----------------------------------------------------------------------------------\\
 
-    fn load_structure_from_file_c(path: *const wchar_t) -> bool;
+    fn load_structure_from_file_c(path: *const c_char) -> bool;
 
-    fn load_pointer_from_file_c(path: *const wchar_t) -> bool;
+    fn load_pointer_from_file_c(path: *const c_char) -> bool;
 
-    fn load_merge_type_from_file_c(path: *const wchar_t) -> bool;
+    fn load_merge_type_from_file_c(path: *const c_char) -> bool;
 
-    fn load_label_from_file_c(path: *const wchar_t) -> bool;
+    fn load_label_from_file_c(path: *const c_char) -> bool;
 
 //-----------------------------------------------------------------------------------\\
 
-    fn save_structure_to_file_c(path: *const wchar_t) -> bool;
+    fn save_structure_to_file_c(path: *const c_char) -> bool;
 
-    fn save_pointer_to_file_c(path: *const wchar_t) -> bool;
+    fn save_pointer_to_file_c(path: *const c_char) -> bool;
 
-    fn save_merge_type_to_file_c(path: *const wchar_t) -> bool;
+    fn save_merge_type_to_file_c(path: *const c_char) -> bool;
 
-    fn save_label_to_file_c(path: *const wchar_t) -> bool;
+    fn save_label_to_file_c(path: *const c_char) -> bool;
 
 //-----------------------------------------------------------------------------------\\
 
@@ -35,6 +37,8 @@ extern "C" {
 
     fn get_structure_vector_c(new_structure: *mut bool);
 
+    fn get_structure_words_c(first_word: size_t, number_of_words: size_t, words: *mut u64);
+
 //-----------------------------------------------------------------------------------\\
 
     fn set_pointer_vector_c(length: size_t, new_pointer: *const size_t);
@@ -43,6 +47,8 @@ extern "C" {
 
     fn get_pointer_vector_c(new_pointer: *mut size_t);
 
+    fn get_pointer_range_c(first: size_t, length: size_t, new_pointer: *mut size_t);
+
 //-----------------------------------------------------------------------------------\\
 
     fn set_merge_type_vector_c(length: size_t, new_merge_type: *const c_int);
@@ -51,13 +57,15 @@ extern "C" {
 
     fn get_merge_type_vector_c(new_merge_type: *mut c_int);
 
+    fn get_merge_type_range_c(first: size_t, length: size_t, new_merge_type: *mut c_int);
+
 //-----------------------------------------------------------------------------------\\
 
-    fn set_label_vector_c(new_label: *const wchar_t);
+    fn set_label_vector_c(new_label: *const c_char);
 
     fn get_label_vector_length_c() -> size_t;
 
-    fn get_label_vector_c(new_label: *mut wchar_t);
+    fn get_label_vector_c(new_label: *mut c_char);
 
 //-----------------------------------------------------------------------------------\\
 }
@@ -69,48 +77,60 @@ thread_local! {
     static LABEL_INITIALSIED: RefCell<bool> = RefCell::new(false);
 }
 
-//-----------------------------------------------------------------------------------\\
+static GLOBAL_VECTORS_INIT: Once = ONCE_INIT;
+static mut GLOBAL_VECTORS: Option<Mutex<()>> = None;
 
-pub fn load_structure_from_file(path: &str) -> bool {
-    let success;
-    let mut path = path.to_owned();
-    path.push(char::from(0 as u8));
+///the C++ side keeps one global vector per stream, so only one thread may save or load an archive at a time
+///the guard has to be held from the first set or load until the last get or save
+pub fn lock_global_vectors() -> MutexGuard<'static, ()> {
     unsafe {
-        success = load_structure_from_file_c(path.as_ptr() as *const i32);
+        GLOBAL_VECTORS_INIT.call_once(|| GLOBAL_VECTORS = Some(Mutex::new(())));
+        //a panic while saving leaves the vectors in a state that the next set or load replaces
+        match GLOBAL_VECTORS.as_ref().unwrap().lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
     }
+}
+
+//-----------------------------------------------------------------------------------\\
+
+pub fn load_structure_from_file(path: &str) -> bool {
+    let success = match CString::new(path) {
+        Ok(path) => unsafe { load_structure_from_file_c(path.as_ptr()) },
+        //a path with a nul byte can not be passed to C
+        Err(_) => false,
+    };
     STRUCTURE_INITIALSIED.with(|elem| *elem.borrow_mut() = success);
     success
 }
 
 pub fn load_pointer_from_file(path: &str) -> bool {
-    let success;
-    let mut path = path.to_owned();
-    path.push(char::from(0 as u8));
-    unsafe {
-        success = load_pointer_from_file_c(path.as_ptr() as *const i32);
-    }
+    let success = match CString::new(path) {
+        Ok(path) => unsafe { load_pointer_from_file_c(path.as_ptr()) },
+        //a path with a nul byte can not be passed to C
+        Err(_) => false,
+    };
     POINTER_INITIALSIED.with(|elem| *elem.borrow_mut() = success);
     success
 }
 
 pub fn load_merge_type_from_file(path: &str) -> bool {
-    let success;
-    let mut path = path.to_owned();
-    path.push(char::from(0 as u8));
-    unsafe {
-        success = load_merge_type_from_file_c(path.as_ptr() as *const i32);
-    }
+    let success = match CString::new(path) {
+        Ok(path) => unsafe { load_merge_type_from_file_c(path.as_ptr()) },
+        //a path with a nul byte can not be passed to C
+        Err(_) => false,
+    };
     MERGE_TYPE_INITIALSIED.with(|elem| *elem.borrow_mut() = success);
     success
 }
 
 pub fn load_label_from_file(path: &str) -> bool {
-    let success;
-    let mut path = path.to_owned();
-    path.push(char::from(0 as u8));
-    unsafe {
-        success = load_label_from_file_c(path.as_ptr() as *const i32);
-    }
+    let success = match CString::new(path) {
+        Ok(path) => unsafe { load_label_from_file_c(path.as_ptr()) },
+        //a path with a nul byte can not be passed to C
+        Err(_) => false,
+    };
     LABEL_INITIALSIED.with(|elem| *elem.borrow_mut() = success);
     success
 }
@@ -119,37 +139,33 @@ pub fn load_label_from_file(path: &str) -> bool {
 
 pub fn save_structure_to_file(path: &str) -> bool {
     assert!(STRUCTURE_INITIALSIED.with(|elem| *elem.borrow()));
-    let mut path = path.to_owned();
-    path.push(char::from(0 as u8));
-    unsafe {
-        save_structure_to_file_c(path.as_ptr() as *const i32)
+    match CString::new(path) {
+        Ok(path) => unsafe { save_structure_to_file_c(path.as_ptr()) },
+        Err(_) => false,
     }
 }
 
 pub fn save_pointer_to_file(path: &str) -> bool {
     assert!(POINTER_INITIALSIED.with(|elem| *elem.borrow()));
-    let mut path = path.to_owned();
-    path.push(char::from(0 as u8));
-    unsafe {
-        save_pointer_to_file_c(path.as_ptr() as *const i32)
+    match CString::new(path) {
+        Ok(path) => unsafe { save_pointer_to_file_c(path.as_ptr()) },
+        Err(_) => false,
     }
 }
 
 pub fn save_merge_type_to_file(path: &str) -> bool {
     assert!(MERGE_TYPE_INITIALSIED.with(|elem| *elem.borrow()));
-    let mut path = path.to_owned();
-    path.push(char::from(0 as u8));
-    unsafe {
-        save_merge_type_to_file_c(path.as_ptr() as *const i32)
+    match CString::new(path) {
+        Ok(path) => unsafe { save_merge_type_to_file_c(path.as_ptr()) },
+        Err(_) => false,
     }
 }
 
 pub fn save_label_to_file(path: &str) -> bool {
     assert!(LABEL_INITIALSIED.with(|elem| *elem.borrow()));
-    let mut path = path.to_owned();
-    path.push(char::from(0 as u8));
-    unsafe {
-        save_label_to_file_c(path.as_ptr() as *const i32)
+    match CString::new(path) {
+        Ok(path) => unsafe { save_label_to_file_c(path.as_ptr()) },
+        Err(_) => false,
     }
 }
 
@@ -177,6 +193,22 @@ pub fn get_structure_vector() -> Vec<bool> {
     structure
 }
 
+pub fn get_structure_vector_length() -> usize {
+    assert!(STRUCTURE_INITIALSIED.with(|elem| *elem.borrow()));
+    unsafe {
+        get_structure_vector_length_c()
+    }
+}
+
+///copies the bits from first_word * 64 on into the words, the bit i is bit i % 64 of the word i / 64
+pub fn get_structure_words(first_word: usize, words: &mut [u64]) {
+    assert!(STRUCTURE_INITIALSIED.with(|elem| *elem.borrow()));
+    assert!((first_word + words.len()) * 64 < get_structure_vector_length() + 64);
+    unsafe {
+        get_structure_words_c(first_word, words.len(), words.as_mut_ptr());
+    }
+}
+
 //-----------------------------------------------------------------------------------\\
 
 pub fn set_pointer_vector(new_pointer: Vec<usize>) {
@@ -201,6 +233,21 @@ pub fn get_pointer_vector() -> Vec<usize> {
     pointer
 }
 
+pub fn get_pointer_vector_length() -> usize {
+    assert!(POINTER_INITIALSIED.with(|elem| *elem.borrow()));
+    unsafe {
+        get_pointer_vector_length_c()
+    }
+}
+
+///copies the pointers from first on into pointer
+pub fn get_pointer_range(first: usize, pointer: &mut [usize]) {
+    assert!(first + pointer.len() <= get_pointer_vector_length());
+    unsafe {
+        get_pointer_range_c(first, pointer.len(), pointer.as_mut_ptr());
+    }
+}
+
 //-----------------------------------------------------------------------------------\\
 
 pub fn set_merge_type_vector(new_merge_type: Vec<i32>) {
@@ -225,56 +272,147 @@ pub fn get_merge_type_vector() -> Vec<i32> {
     merge_type
 }
 
-//-----------------------------------------------------------------------------------\\
-
-pub fn set_label_vector_from_string(new_label: Vec<String>) {
-    let mut string = String::new();
-    for elem in new_label {
-        assert!(elem.find('\n').is_none());
-        string.push_str(&elem);
-        string.push('\n');
+pub fn get_merge_type_vector_length() -> usize {
+    assert!(MERGE_TYPE_INITIALSIED.with(|elem| *elem.borrow()));
+    unsafe {
+        get_merge_type_vector_length_c()
     }
-    LABEL_INITIALSIED.with(|elem| *elem.borrow_mut() = true);
-    let string_pointer = string.as_ptr() as *const i32;
+}
 
+///copies the merge types from first on into merge_type
+pub fn get_merge_type_range(first: usize, merge_type: &mut [i32]) {
+    assert!(first + merge_type.len() <= get_merge_type_vector_length());
     unsafe {
-        set_label_vector_c(string_pointer);
+        get_merge_type_range_c(first, merge_type.len(), merge_type.as_mut_ptr());
     }
 }
 
-pub fn set_label_vector_from_str(new_label: Vec<&str>) {
-    let mut string = String::new();
+//-----------------------------------------------------------------------------------\\
+
+///the labels are escaped and joined with '\n' so any byte sequence can be stored
+pub fn set_label_vector(new_label: Vec<Vec<u8>>) {
+    let mut bytes = Vec::new();
     for elem in new_label {
-        assert!(elem.find('\n').is_none());
-        string.push_str(&elem);
-        string.push('\n');
+        escape_label(&elem, &mut bytes);
+        bytes.push(b'\n');
     }
+    //sdsl reads the labels as a null terminated string
+    bytes.push(0);
     LABEL_INITIALSIED.with(|elem| *elem.borrow_mut() = true);
-    let string_pointer = string.as_ptr() as *const i32;
 
     unsafe {
-        set_label_vector_c(string_pointer);
+        set_label_vector_c(bytes.as_ptr() as *const c_char);
     }
 }
 
-pub fn get_label_vector() -> Vec<String> {
+///the bytes of the label vector, the joined labels and the terminator sdsl appends
+pub fn get_label_bytes() -> Vec<u8> {
     assert!(LABEL_INITIALSIED.with(|elem| *elem.borrow()));
     let length;
     unsafe {
         length = get_label_vector_length_c();
     }
-    let mut string = String::new();
-    for _index in 0..length {
-        string.push('\n');
-    }
+    let mut bytes = vec![0u8; length as usize];
     unsafe {
-        get_label_vector_c(string.as_ptr() as *mut i32);
+        get_label_vector_c(bytes.as_mut_ptr() as *mut c_char);
+    }
+    bytes
+}
+
+///splits the bytes of a label vector into the labels
+///labels of version 1 archives are not escaped
+pub fn labels_from_bytes(mut bytes: Vec<u8>, escaped: bool) -> Vec<Vec<u8>> {
+    //remove the terminating symbol sdsl appends
+    if bytes.last() == Some(&0) {
+        bytes.pop();
+    }
+
+    if escaped {
+        split_labels(bytes).into_iter().map(|elem| unescape_label(&elem)).collect()
+    } else {
+        split_labels(bytes)
     }
+}
 
-    let mut to_return: Vec<String> = string.split('\n').map(|elem| elem.to_owned()).collect();
+fn split_labels(bytes: Vec<u8>) -> Vec<Vec<u8>> {
+    let mut to_return: Vec<Vec<u8>> = bytes.split(|byte| *byte == b'\n').map(|elem| elem.to_vec()).collect();
+    //the last label is terminated by '\n' too
     to_return.pop();
     to_return
+}
 
+///'\\' is the escape symbol, '\n' is the separator and '\0' the terminator of sdsl
+fn escape_label(label: &[u8], output: &mut Vec<u8>) {
+    for byte in label {
+        match *byte {
+            b'\\' => output.extend_from_slice(b"\\\\"),
+            b'\n' => output.extend_from_slice(b"\\n"),
+            0 => output.extend_from_slice(b"\\0"),
+            byte => output.push(byte),
+        }
+    }
 }
 
-//-----------------------------------------------------------------------------------\\
\ No newline at end of file
+fn unescape_label(escaped: &[u8]) -> Vec<u8> {
+    let mut label = Vec::with_capacity(escaped.len());
+    let mut iter = escaped.iter();
+    while let Some(byte) = iter.next() {
+        if *byte == b'\\' {
+            match iter.next().cloned() {
+                Some(b'n') => label.push(b'\n'),
+                Some(b'0') => label.push(0),
+                Some(other) => label.push(other),
+                None => (),
+            }
+        } else {
+            label.push(*byte);
+        }
+    }
+    label
+}
+
+//-----------------------------------------------------------------------------------\\
+
+
+#[cfg(test)]
+mod tests {
+    use super::{escape_label, unescape_label, split_labels, load_structure_from_file, load_label_from_file};
+
+    fn join(labels: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for label in labels {
+            escape_label(label, &mut bytes);
+            bytes.push(b'\n');
+        }
+        bytes
+    }
+
+    #[test]
+    fn every_byte_round_trips() {
+        let labels = vec![
+            (0..256).map(|byte| byte as u8).collect::<Vec<u8>>(),
+            Vec::new(),
+            b"\\".to_vec(),
+            b"\\n\n\\0\0".to_vec(),
+            b"trailing\\".to_vec(),
+            vec![0xff, 0xfe, 0xc3],
+        ];
+
+        let bytes = join(&labels);
+        assert!(!bytes.contains(&0));
+        let decoded: Vec<Vec<u8>> = split_labels(bytes).into_iter().map(|label| unescape_label(&label)).collect();
+        assert_eq!(decoded, labels);
+    }
+
+    #[test]
+    fn labels_without_special_bytes_are_not_changed() {
+        let labels = vec![b"item".to_vec(), vec![0xe9, 0x80]];
+        assert_eq!(split_labels(join(&labels)), labels);
+    }
+
+    #[test]
+    fn paths_with_nul_bytes_are_not_loaded() {
+        assert!(!load_structure_from_file("structure\0.sdsl"));
+        assert!(!load_label_from_file("label\0.sdsl"));
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::{HashMap, VecDeque};
+
+use structs::MergeType;
+use io_tree::IO_Tree;
+use expand::{Expand, Expansion};
+use validation::DecodeError;
+
+
+
+///the id of the empty child list
+const EMPTY_LIST: usize = 0;
+
+#[derive(Clone, Debug)]
+struct SharedNode {
+    ///the index in the label vector
+    label: usize,
+    ///the position of the first child in the children vector
+    first_child: usize,
+    number_of_children: usize,
+}
+
+///The decompressed tree as a minimal DAG
+///
+///Equal subtrees are stored once, so two nodes are equal subtrees exactly if they have the same id.
+///A node is only stored after all of its children, the root is the last node.
+///The memory needed is proportional to the number of distinct subtrees and not to the size of the tree.
+pub struct SharedTree<L> {
+    nodes: Vec<SharedNode>,
+    ///the child lists of all nodes one after another
+    children: Vec<usize>,
+    label_vector: Vec<L>,
+}
+
+impl<L> SharedTree<L> {
+    pub fn root(&self) -> usize {
+        self.nodes.len() - 1
+    }
+
+    ///the number of distinct subtrees
+    pub fn number_of_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn label(&self, node: usize) -> &L {
+        &self.label_vector[self.nodes[node].label]
+    }
+
+    pub fn children(&self, node: usize) -> &[usize] {
+        let SharedNode { first_child, number_of_children, .. } = self.nodes[node];
+        &self.children[first_child..first_child + number_of_children]
+    }
+
+    ///the number of nodes of the subtree if every shared subtree is copied
+    ///saturating so trees with a lot of sharing do not overflow
+    pub fn expanded_size(&self, node: usize) -> usize {
+        //children are stored before their parents so one pass in order is enough
+        let mut sizes: Vec<usize> = Vec::with_capacity(node + 1);
+        for index in 0..node + 1 {
+            let size = self.children(index).iter().fold(1usize, |size, child| size.saturating_add(sizes[*child]));
+            sizes.push(size);
+        }
+        sizes[node]
+    }
+}
+
+impl<L: Clone> SharedTree<L> {
+    ///copies every shared subtree, this needs memory proportional to the size of the tree
+    #[allow(non_snake_case)]
+    pub fn to_IO_tree(&self) -> IO_Tree<L> {
+        //(node, number of converted children, converted children)
+        let root = self.root();
+        let mut stack = vec![(root, 0, VecDeque::with_capacity(self.children(root).len()))];
+        loop {
+            let (node, next_child) = {
+                let (node, next_child, _) = stack.last().unwrap();
+                (*node, *next_child)
+            };
+
+            if let Some(child) = self.children(node).get(next_child).cloned() {
+                stack.last_mut().unwrap().1 += 1;
+                stack.push((child, 0, VecDeque::with_capacity(self.children(child).len())));
+            } else {
+                //all children are converted so the node is finished
+                let (_, _, children) = stack.pop().unwrap();
+                let tree = IO_Tree { label: self.label(node).clone(), children };
+                match stack.last_mut() {
+                    Some((_, _, siblings)) => siblings.push_back(tree),
+                    None => return tree,
+                }
+            }
+        }
+    }
+}
+
+enum Step {
+    ///expand the id and hang the list below its bottom boundary node
+    Expand(usize, usize),
+    ///the second child of an AB cluster is expanded, hang it below the first child
+    BelowFirst(usize),
+    ///both children of a CE or DE cluster are expanded, put them next to each other
+    Concatenate,
+    ///the expansion of the id with the list below is finished, remember it
+    Finish(usize, usize),
+}
+
+///Hash conses the nodes and the sibling lists while the TopDAG is expanded
+struct SharedTreeBuilder<L> {
+    tree: SharedTree<L>,
+
+    ///(label, child list) to node
+    node_ids: HashMap<(usize, usize), usize>,
+    ///every distinct list of siblings, the children of a node and the partial results of the clusters
+    lists: Vec<Vec<usize>>,
+    list_ids: HashMap<Vec<usize>, usize>,
+    ///id of the TopDAG to index in the label vector
+    label_ids: HashMap<usize, usize>,
+    ///(id, list below) to the list of siblings the id expands to
+    expansions: HashMap<(usize, usize), usize>,
+}
+
+impl<L> SharedTreeBuilder<L> {
+    fn list(&mut self, list: Vec<usize>) -> usize {
+        if let Some(list_id) = self.list_ids.get(&list) {
+            return *list_id;
+        }
+        let list_id = self.lists.len();
+        self.lists.push(list.clone());
+        self.list_ids.insert(list, list_id);
+        list_id
+    }
+
+    fn node(&mut self, label: usize, children: usize) -> usize {
+        if let Some(node) = self.node_ids.get(&(label, children)) {
+            return *node;
+        }
+        let node = self.tree.nodes.len();
+        self.tree.nodes.push(SharedNode {
+            label,
+            //the position is set when the child lists are copied to the tree
+            first_child: children,
+            number_of_children: self.lists[children].len(),
+        });
+        self.node_ids.insert((label, children), node);
+        node
+    }
+
+    ///copies the child lists of the nodes to the tree, the partial results are dropped
+    fn finish(mut self) -> SharedTree<L> {
+        let mut positions: Vec<Option<usize>> = vec![None; self.lists.len()];
+        for node in &mut self.tree.nodes {
+            let list = node.first_child;
+            node.first_child = match positions[list] {
+                Some(position) => position,
+                None => {
+                    let position = self.tree.children.len();
+                    self.tree.children.extend_from_slice(&self.lists[list]);
+                    positions[list] = Some(position);
+                    position
+                },
+            };
+        }
+        self.tree
+    }
+}
+
+///expands the cluster to a minimal DAG
+///every id is expanded at most once for every distinct list of subtrees below its bottom boundary node
+///uses an explicit stack so the depth of the tree is not limited by the call stack
+pub fn share_tree<E: Expand>(source: &E, root: usize) -> Result<SharedTree<E::Label>, DecodeError> {
+    let mut builder = SharedTreeBuilder {
+        tree: SharedTree {
+            nodes: Vec::new(),
+            children: Vec::new(),
+            label_vector: Vec::new(),
+        },
+
+        node_ids: HashMap::new(),
+        lists: Vec::new(),
+        list_ids: HashMap::new(),
+        label_ids: HashMap::new(),
+        expansions: HashMap::new(),
+    };
+    let empty_list = builder.list(Vec::new());
+    assert!(empty_list == EMPTY_LIST);
+
+    let mut steps = vec![Step::Expand(root, EMPTY_LIST)];
+    let mut results: Vec<usize> = Vec::new();
+
+    while let Some(step) = steps.pop() {
+        match step {
+            Step::Expand(id, below) => {
+                if let Some(list) = builder.expansions.get(&(id, below)) {
+                    results.push(*list);
+                    continue;
+                }
+
+                match source.expansion(id)? {
+                    Expansion::Label(label) => {
+                        let label_id = match builder.label_ids.get(&id) {
+                            Some(label_id) => *label_id,
+                            None => {
+                                let label_id = builder.tree.label_vector.len();
+                                builder.tree.label_vector.push(label);
+                                builder.label_ids.insert(id, label_id);
+                                label_id
+                            },
+                        };
+                        let node = builder.node(label_id, below);
+                        let list = builder.list(vec![node]);
+                        builder.expansions.insert((id, below), list);
+                        results.push(list);
+                    },
+
+                    Expansion::Cluster(MergeType::AB, first_child, second_child) => {
+                        steps.push(Step::Finish(id, below));
+                        steps.push(Step::BelowFirst(first_child));
+                        steps.push(Step::Expand(second_child, below));
+                    },
+
+                    Expansion::Cluster(MergeType::CE, first_child, second_child) => {
+                        steps.push(Step::Finish(id, below));
+                        steps.push(Step::Concatenate);
+                        steps.push(Step::Expand(second_child, EMPTY_LIST));
+                        steps.push(Step::Expand(first_child, below));
+                    },
+
+                    Expansion::Cluster(MergeType::DE, first_child, second_child) => {
+                        steps.push(Step::Finish(id, below));
+                        steps.push(Step::Concatenate);
+                        steps.push(Step::Expand(second_child, below));
+                        steps.push(Step::Expand(first_child, EMPTY_LIST));
+                    },
+                }
+            },
+
+            Step::BelowFirst(first_child) => {
+                let below = results.pop().unwrap();
+                steps.push(Step::Expand(first_child, below));
+            },
+
+            Step::Concatenate => {
+                let second = results.pop().unwrap();
+                let first = results.pop().unwrap();
+                let mut list = builder.lists[first].clone();
+                list.extend_from_slice(&builder.lists[second]);
+                let list = builder.list(list);
+                results.push(list);
+            },
+
+            Step::Finish(id, below) => {
+                builder.expansions.insert((id, below), *results.last().unwrap());
+            },
+        }
+    }
+
+    let root_list = results.pop().unwrap();
+    if !results.is_empty() || builder.lists[root_list].len() != 1 { return Err(DecodeError::InvalidRoot) }
+    //the root is the last node because it is inserted after all other nodes
+    assert!(builder.lists[root_list][0] + 1 == builder.tree.nodes.len());
+    Ok(builder.finish())
+}
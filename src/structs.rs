@@ -29,21 +29,29 @@ pub struct Cluster {
     pub second_child: usize,
 }
 
+///How the two children of a cluster are joined
 #[derive(Eq, PartialEq, Hash, Clone, Debug)]
 pub enum MergeType {
+    ///vertical merge, the second child hangs below the bottom boundary node of the first child
     AB,
 
+    ///horizontal merge, the first child has a bottom boundary node
     CE,
 
+    ///horizontal merge, the second child has a bottom boundary node or none of them
     DE,
 }
 
+///The integer mapping is part of the archive format
+///changing it needs a new format version
 impl MergeType {
-    pub fn from_i32(integer: i32) -> Self {
+    ///returns None for integers outside of the mapping
+    pub fn from_i32(integer: i32) -> Option<Self> {
         match integer {
-            0 => MergeType::AB,
-            1 => MergeType::CE,
-            _ => MergeType::DE,
+            0 => Some(MergeType::AB),
+            1 => Some(MergeType::CE),
+            2 => Some(MergeType::DE),
+            _ => None,
         }
     }
 
@@ -73,6 +81,8 @@ pub struct Node {
     pub first_child: usize,
     ///The position of the last child in the edges array (exclusive)
     pub last_child: usize,
+    ///The end of the space reserved for children in the edges array (exclusive)
+    pub reserved_end: usize,
 }
 
 impl Node {
@@ -82,6 +92,7 @@ impl Node {
             data: Data::Label(label),
             first_child: 0,
             last_child: 0,
+            reserved_end: 0,
         }
     }
 }
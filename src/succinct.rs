@@ -0,0 +1,269 @@
+use std::slice;
+
+
+
+///number of words between two rank samples
+const WORDS_PER_SUPERBLOCK: usize = 8;
+
+///A bit vector with constant time rank
+///S is the storage of the words so the vector can live in owned memory or in a mapped file
+#[derive(Clone, Debug)]
+pub struct BitVector<S: AsRef<[u64]> = Vec<u64>> {
+    length: usize,
+    words: S,
+    ///number of ones before every superblock
+    superblock_ranks: S,
+}
+
+impl BitVector {
+    pub fn new(bits: &[bool]) -> BitVector {
+        let mut words = vec![0u64; (bits.len() + 63) / 64];
+        for (index, bit) in bits.iter().enumerate() {
+            if *bit {
+                words[index / 64] |= 1 << (index % 64);
+            }
+        }
+        BitVector::from_words(bits.len(), words)
+    }
+
+    ///the bit i is bit i % 64 of the word i / 64, the bits behind length have to be zero
+    pub fn from_words(length: usize, words: Vec<u64>) -> BitVector {
+        assert!(words.len() == (length + 63) / 64);
+
+        //one sample more than superblocks so rank works at the end of the vector
+        let mut superblock_ranks = Vec::with_capacity(words.len() / WORDS_PER_SUPERBLOCK + 1);
+        let mut rank = 0;
+        for index in 0..words.len() + 1 {
+            if index % WORDS_PER_SUPERBLOCK == 0 {
+                superblock_ranks.push(rank);
+            }
+            if index < words.len() {
+                rank += words[index].count_ones() as u64;
+            }
+        }
+
+        BitVector { length, words, superblock_ranks }
+    }
+}
+
+impl<S: AsRef<[u64]>> BitVector<S> {
+    ///the parts have to be taken from an other BitVector
+    pub fn from_parts(length: usize, words: S, superblock_ranks: S) -> Option<BitVector<S>> {
+        let number_of_words = (length + 63) / 64;
+        if words.as_ref().len() != number_of_words || superblock_ranks.as_ref().len() != number_of_words / WORDS_PER_SUPERBLOCK + 1 {
+            return None;
+        }
+        Some(BitVector { length, words, superblock_ranks })
+    }
+
+    pub fn parts(&self) -> (usize, &[u64], &[u64]) {
+        (self.length, self.words.as_ref(), self.superblock_ranks.as_ref())
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn get(&self, index: usize) -> bool {
+        assert!(index < self.length);
+        self.words.as_ref()[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    ///number of ones in [0, index)
+    pub fn rank1(&self, index: usize) -> usize {
+        assert!(index <= self.length);
+        let words = self.words.as_ref();
+        let word_index = index / 64;
+        let superblock = word_index / WORDS_PER_SUPERBLOCK;
+
+        let mut rank = self.superblock_ranks.as_ref()[superblock] as usize;
+        for word in &words[superblock * WORDS_PER_SUPERBLOCK..word_index] {
+            rank += word.count_ones() as usize;
+        }
+        if index % 64 != 0 {
+            rank += (words[word_index] & ((1 << (index % 64)) - 1)).count_ones() as usize;
+        }
+        rank
+    }
+
+    ///true if the rank samples match the words
+    pub fn check(&self) -> bool {
+        let words = self.words.as_ref();
+        let superblock_ranks = self.superblock_ranks.as_ref();
+        let mut rank = 0;
+        for index in 0..words.len() + 1 {
+            if index % WORDS_PER_SUPERBLOCK == 0 && superblock_ranks[index / WORDS_PER_SUPERBLOCK] != rank {
+                return false;
+            }
+            if index < words.len() {
+                rank += words[index].count_ones() as u64;
+            }
+        }
+        //bits behind the length have to be zero
+        self.length % 64 == 0 || words[self.length / 64] >> (self.length % 64) == 0
+    }
+
+    ///number of zeros in [0, index)
+    pub fn rank0(&self, index: usize) -> usize {
+        index - self.rank1(index)
+    }
+
+    pub fn size_in_bits(&self) -> usize {
+        (self.words.as_ref().len() + self.superblock_ranks.as_ref().len()) * 64
+    }
+}
+
+///A vector of integers with a fixed bit width
+#[derive(Clone, Debug)]
+pub struct IntVector<S: AsRef<[u64]> = Vec<u64>> {
+    length: usize,
+    width: usize,
+    words: S,
+}
+
+impl IntVector {
+    ///uses the smallest width that fits the largest value
+    pub fn new(values: &[usize]) -> IntVector {
+        let max = values.iter().cloned().max().unwrap_or(0);
+        let width = (64 - (max as u64).leading_zeros() as usize).max(1);
+        IntVector::with_width(values, width)
+    }
+
+    pub fn with_width(values: &[usize], width: usize) -> IntVector {
+        let mut int_vector = IntVector::zeros(values.len(), width);
+        for (index, value) in values.iter().enumerate() {
+            int_vector.set(index, *value);
+        }
+        int_vector
+    }
+
+    ///length values of zero, they are filled with set
+    pub fn zeros(length: usize, width: usize) -> IntVector {
+        assert!(width >= 1 && width <= 64);
+        IntVector { length, width, words: vec![0u64; (length * width + 63) / 64] }
+    }
+
+    ///the value has to fit into the width, the old value has to be zero
+    pub fn set(&mut self, index: usize, value: usize) {
+        assert!(index < self.length);
+        let value = value as u64;
+        let width = self.width;
+        assert!(width == 64 || value >> width == 0);
+        let bit = index * width;
+        self.words[bit / 64] |= value << (bit % 64);
+        if bit % 64 + width > 64 {
+            self.words[bit / 64 + 1] |= value >> (64 - bit % 64);
+        }
+    }
+}
+
+impl<S: AsRef<[u64]>> IntVector<S> {
+    ///the parts have to be taken from an other IntVector
+    pub fn from_parts(length: usize, width: usize, words: S) -> Option<IntVector<S>> {
+        if width < 1 || width > 64 || words.as_ref().len() != (length.checked_mul(width)? + 63) / 64 {
+            return None;
+        }
+        Some(IntVector { length, width, words })
+    }
+
+    pub fn parts(&self) -> (usize, usize, &[u64]) {
+        (self.length, self.width, self.words.as_ref())
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn get(&self, index: usize) -> usize {
+        assert!(index < self.length);
+        let words = self.words.as_ref();
+        let bit = index * self.width;
+        let mask = if self.width == 64 { !0 } else { (1 << self.width) - 1 };
+
+        let mut value = words[bit / 64] >> (bit % 64);
+        if bit % 64 + self.width > 64 {
+            value |= words[bit / 64 + 1] << (64 - bit % 64);
+        }
+        (value & mask) as usize
+    }
+
+    pub fn size_in_bits(&self) -> usize {
+        self.words.as_ref().len() * 64
+    }
+}
+
+///The labels are stored one after another, offsets points to the start of every label
+#[derive(Clone, Debug)]
+pub struct LabelStore<S: AsRef<[u64]> = Vec<u64>> {
+    offsets: IntVector<S>,
+    bytes: S,
+}
+
+impl LabelStore {
+    pub fn new(labels: &[Vec<u8>]) -> LabelStore {
+        let mut offsets = Vec::with_capacity(labels.len() + 1);
+        let mut bytes = Vec::new();
+        for label in labels {
+            offsets.push(bytes.len());
+            bytes.extend_from_slice(label);
+        }
+        offsets.push(bytes.len());
+
+        let mut words = vec![0u64; (bytes.len() + 7) / 8];
+        words_as_bytes_mut(&mut words)[..bytes.len()].copy_from_slice(&bytes);
+
+        LabelStore { offsets: IntVector::new(&offsets), bytes: words }
+    }
+}
+
+impl<S: AsRef<[u64]>> LabelStore<S> {
+    ///the parts have to be taken from an other LabelStore
+    ///the offsets are not read here, check does that
+    pub fn from_parts(offsets: IntVector<S>, bytes: S) -> Option<LabelStore<S>> {
+        if offsets.len() == 0 { return None }
+        Some(LabelStore { offsets, bytes })
+    }
+
+    ///true if the offsets are ascending and inside of the bytes
+    pub fn check(&self) -> bool {
+        let number_of_bytes = self.bytes.as_ref().len() * 8;
+        let mut previous = 0;
+        for index in 0..self.offsets.len() {
+            let offset = self.offsets.get(index);
+            if offset < previous || offset > number_of_bytes { return false }
+            previous = offset;
+        }
+        true
+    }
+
+    pub fn parts(&self) -> (&IntVector<S>, &[u64]) {
+        (&self.offsets, self.bytes.as_ref())
+    }
+
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn get(&self, id: usize) -> Option<&[u8]> {
+        if id >= self.len() { return None }
+        let bytes = words_as_bytes(self.bytes.as_ref());
+        bytes.get(self.offsets.get(id)..self.offsets.get(id + 1))
+    }
+
+    pub fn size_in_bits(&self) -> usize {
+        self.offsets.size_in_bits() + self.bytes.as_ref().len() * 64
+    }
+}
+
+///views the words as bytes in memory order
+pub fn words_as_bytes(words: &[u64]) -> &[u8] {
+    unsafe {
+        slice::from_raw_parts(words.as_ptr() as *const u8, words.len() * 8)
+    }
+}
+
+fn words_as_bytes_mut(words: &mut [u64]) -> &mut [u8] {
+    unsafe {
+        slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, words.len() * 8)
+    }
+}
@@ -0,0 +1,392 @@
+use std::fmt::{Debug, Formatter, Result, Write};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use structs::{Cluster, MergeType};
+use io_tree::{IO_Tree, GenResult};
+use flags_and_statistic::{Statistic, TopDagShape, Flags, LabelEncoding, PointerEncoding, SizeReport};
+use uninitialized::Uninitialized;
+use format::{Meta, Streams, open_archive, load_streams, save_streams};
+use validation::{DecodeLimits, DecodeError};
+use compressed::CompressedTopDag;
+use label::{Label, LabelBytes};
+use label_encoding::LabelDictionary;
+use expand::{Expand, Expansion, expand_tree};
+use shared_tree::{SharedTree, share_tree};
+use verify::Mismatch;
+use TopTreeBuilder;
+
+
+
+///The finished TopDAG
+///
+///It is never changed after it was built or loaded, so it is Send + Sync whenever the label is
+///and can be shared between threads with an Arc.
+///Ids below number_of_labels are labels, the id number_of_labels + i is the i-th cluster, the root is the last one.
+///A tree of a single node has no cluster, its root is the label 0.
+pub struct TopDag<L: Label = Vec<u8>> {
+    cluster_vector: Vec<Cluster>,
+    cluster_size: Vec<usize>,
+    label_vector: Vec<L>,
+
+    label_encoding: LabelEncoding,
+    pointer_encoding: PointerEncoding,
+
+    statistic: Statistic,
+}
+
+///A cluster of the TopDAG
+///the children are ids of the TopDAG, so they are either labels or clusters with a smaller id
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClusterInfo {
+    pub id: usize,
+    pub merge_type: MergeType,
+    pub first_child: usize,
+    pub second_child: usize,
+    ///the number of nodes the cluster expands to
+    pub size: usize,
+}
+
+impl TopDag {
+    ///like verify_against but streams the xml, so the input tree is never in memory
+    pub fn verify_against_xml(&self, path: &str) -> GenResult<()> {
+        self.get_shared_tree().verify_against_xml(path)
+    }
+
+    ///like new_fom_file_verified but streams the xml, so the input tree is never in memory
+    pub fn new_fom_file_verified_xml(path: &str, xml_path: &str) -> GenResult<TopDag> {
+        let top_dag = TopDag::new_fom_file(path)?;
+        top_dag.verify_against_xml(xml_path)?;
+        Ok(top_dag)
+    }
+}
+
+///Loading and saving archives needs labels that can be converted to bytes
+impl<L: LabelBytes> TopDag<L> {
+    pub fn new_fom_file(path: &str) -> GenResult<TopDag<L>> {
+        TopDag::new_fom_file_with_limits(path, &DecodeLimits::default())
+    }
+
+    ///loads an archive and rejects it if it is corrupt or exceeds the limits
+    pub fn new_fom_file_with_limits(path: &str, limits: &DecodeLimits) -> GenResult<TopDag<L>> {
+        let (meta, streams) = open_archive(path, |directory| load_streams(directory, limits))?;
+        let Streams { structure, pointer, merge_type, label } = streams;
+
+        //the TopDAG owns its labels, so a front coded dictionary is decoded here
+        let mut top_dag = TopDag::from_streams(structure, pointer, merge_type, label.into_vec(), limits)?;
+        //saving the TopDAG again keeps the encodings of the archive
+        top_dag.label_encoding = meta.label_encoding;
+        top_dag.pointer_encoding = meta.pointer_encoding;
+        Ok(top_dag)
+    }
+
+    ///loads an archive and checks that it decompresses to the tree, the error is the first Mismatch
+    pub fn new_fom_file_verified(path: &str, tree: &IO_Tree<L>) -> GenResult<TopDag<L>> {
+        let top_dag = TopDag::new_fom_file(path)?;
+        top_dag.verify_against(tree)?;
+        Ok(top_dag)
+    }
+
+    ///builds the TopDAG from the decoded streams of an archive
+    ///the streams are fully validated so untrusted input returns an error instead of panicking
+    pub fn from_streams(structure: Vec<bool>, pointer: Vec<usize>, merge_type: Vec<i32>, label: Vec<Vec<u8>>, limits: &DecodeLimits) -> GenResult<TopDag<L>> {
+        let mut labels = Vec::with_capacity(label.len());
+        for (id, bytes) in label.iter().enumerate() {
+            labels.push(L::from_bytes(bytes).ok_or(DecodeError::InvalidLabel(id))?);
+        }
+
+        //the builder finds clusters that are stored twice
+        let mut top_tree_builder = TopTreeBuilder::empty(Flags::default());
+        top_tree_builder.detraverse(structure, pointer, merge_type, labels, limits)?;
+
+        Ok(TopDag::from_builder(top_tree_builder))
+    }
+
+    ///saves with the encodings of the flags, or of the archive the TopDAG was loaded from
+    ///returns the encoded size of every stream
+    pub fn save_to_file(&self, path: &str) -> SizeReport {
+        self.save_to_file_with_encodings(path, self.label_encoding, self.pointer_encoding)
+    }
+
+    ///returns the encoded size of every stream
+    pub fn save_to_file_with_encodings(&self, path: &str, label_encoding: LabelEncoding, pointer_encoding: PointerEncoding) -> SizeReport {
+        let (structure, pointer, merge_type) = self.traverse();
+        let label = self.label_bytes();
+
+        let meta = Meta::current(label_encoding, pointer_encoding);
+        save_streams(path, Streams { structure, pointer, merge_type, label: LabelDictionary::Plain(label) }, &meta, self.number_of_nodes())
+    }
+
+    ///the read only view on the encoded streams
+    pub fn to_compressed(&self) -> CompressedTopDag {
+        let (structure, pointer, merge_type) = self.traverse();
+        let label = self.label_bytes();
+
+        CompressedTopDag::from_streams(&Streams { structure, pointer, merge_type, label: LabelDictionary::Plain(label) }, &DecodeLimits::unlimited())
+            .expect("Error: traverse produced invalid streams")
+    }
+
+    ///saves the TopDAG in the single file container that CompressedTopDag::open_mmap loads
+    pub fn save_to_container(&self, path: &str) -> GenResult<()> {
+        self.to_compressed().save_to_container(path)
+    }
+
+    fn label_bytes(&self) -> Vec<Vec<u8>> {
+        self.label_vector.iter().map(|label| label.to_bytes()).collect()
+    }
+}
+
+impl<L: Label> TopDag<L> {
+    ///takes the clusters and labels of a builder that finished its merge rounds
+    pub(crate) fn from_builder(top_tree_builder: TopTreeBuilder<L>) -> TopDag<L> {
+        let TopTreeBuilder { cluster_vector, cluster_size, label_vector, flags, statistic, .. } = top_tree_builder;
+
+        let mut top_dag = TopDag {
+            cluster_vector,
+            cluster_size,
+            label_vector,
+
+            label_encoding: flags.label_encoding,
+            pointer_encoding: flags.pointer_encoding,
+
+            statistic,
+        };
+        top_dag.statistic.shape = top_dag.shape();
+        top_dag
+    }
+
+    ///the id of the root cluster, or the label 0 if the tree has a single node
+    pub fn root(&self) -> usize {
+        match self.cluster_vector.len() {
+            0 => 0,
+            number_of_clusters => number_of_clusters - 1 + self.label_vector.len(),
+        }
+    }
+
+    pub fn number_of_clusters(&self) -> usize {
+        self.cluster_vector.len()
+    }
+
+    pub fn number_of_labels(&self) -> usize {
+        self.label_vector.len()
+    }
+
+    ///all distinct labels, the index is the id of the label
+    pub fn labels(&self) -> &[L] {
+        &self.label_vector
+    }
+
+    ///None if the id is not a label
+    pub fn label(&self, id: usize) -> Option<&L> {
+        self.label_vector.get(id)
+    }
+
+    pub fn is_label(&self, id: usize) -> bool {
+        id < self.label_vector.len()
+    }
+
+    ///None if the id is not a cluster
+    pub fn cluster(&self, id: usize) -> Option<ClusterInfo> {
+        if id < self.label_vector.len() { return None }
+        let index = id - self.label_vector.len();
+        let Cluster { merge_type, first_child, second_child } = self.cluster_vector.get(index)?;
+
+        Some(ClusterInfo {
+            id,
+            merge_type: merge_type.clone(),
+            first_child: *first_child,
+            second_child: *second_child,
+            size: (self.cluster_size[index] + 1) / 2,
+        })
+    }
+
+    ///all clusters in the order of their ids, the root is the last one
+    pub fn clusters<'a>(&'a self) -> impl Iterator<Item = ClusterInfo> + 'a {
+        let number_of_labels = self.label_vector.len();
+        (0..self.cluster_vector.len()).map(move |index| self.cluster(index + number_of_labels).unwrap())
+    }
+
+    ///the number of nodes the label or cluster expands to
+    ///None if the id is neither a label nor a cluster
+    pub fn size(&self, id: usize) -> Option<usize> {
+        if id < self.label_vector.len() {
+            Some(1)
+        } else {
+            //a cluster of size s expands to (s + 1) / 2 nodes
+            self.cluster_size.get(id - self.label_vector.len()).map(|size| (size + 1) / 2)
+        }
+    }
+
+    ///the number of nodes of the decompressed tree
+    pub fn number_of_nodes(&self) -> usize {
+        match self.cluster_size.last() {
+            Some(size) => (size + 1) / 2,
+            //the single node is the label
+            None => self.label_vector.len().min(1),
+        }
+    }
+
+    ///height, largest cluster and how often the clusters are shared
+    pub fn shape(&self) -> TopDagShape {
+        let number_of_labels = self.label_vector.len();
+        let mut heights: Vec<usize> = Vec::with_capacity(self.cluster_vector.len());
+        let mut parents: Vec<usize> = vec![0; self.cluster_vector.len()];
+
+        //children have smaller ids than their parents so one pass in order is enough
+        for cluster in &self.cluster_vector {
+            let mut height = 0;
+            for child in &[cluster.first_child, cluster.second_child] {
+                if *child >= number_of_labels {
+                    height = height.max(heights[child - number_of_labels]);
+                    parents[child - number_of_labels] += 1;
+                }
+            }
+            heights.push(height + 1);
+        }
+
+        let mut sharing_histogram = BTreeMap::new();
+        for number_of_parents in &parents {
+            *sharing_histogram.entry(*number_of_parents).or_insert(0) += 1;
+        }
+
+        let below_root = &self.cluster_size[..self.cluster_size.len().saturating_sub(1)];
+        TopDagShape {
+            height: heights.last().cloned().unwrap_or(0),
+            max_cluster_size: below_root.iter().max().map_or(0, |size| (size + 1) / 2),
+            sharing_histogram,
+            number_of_nodes: self.number_of_nodes(),
+        }
+    }
+
+    ///the statistic of the build, time_for_decompression and size_report are left to the caller
+    pub fn get_statistic(&self) -> &Statistic {
+        &self.statistic
+    }
+
+    #[allow(non_snake_case)]
+    pub fn get_IO_tree(&self) -> IO_Tree<L> {
+        expand_tree(self, self.root()).expect("Error: The clusters of a TopDag always expand")
+    }
+
+    ///the decompressed tree and the time it took, the time is only measured with the feature performance_test
+    #[allow(non_snake_case)]
+    pub fn get_IO_tree_timed(&self) -> (IO_Tree<L>, Duration) {
+        let mut time_for_decompression = Duration::default();
+        let tree = measure_performance!(self.get_IO_tree(), time_for_decompression);
+        (tree, time_for_decompression)
+    }
+
+    ///the decompressed tree with every distinct subtree stored once
+    pub fn get_shared_tree(&self) -> SharedTree<L> {
+        share_tree(self, self.root()).expect("Error: The clusters of a TopDag always expand")
+    }
+
+    ///checks that the TopDAG decompresses to the tree, the shared subtrees are not expanded
+    ///returns the first node in preorder that differs
+    pub fn verify_against(&self, tree: &IO_Tree<L>) -> std::result::Result<(), Mismatch> {
+        self.get_shared_tree().verify_against(tree)
+    }
+
+    ///a tree of a single node has an empty structure and the pointer to its label
+    fn traverse(&self) -> (Vec<bool>, Vec<usize>, Vec<i32>) {
+        if self.cluster_vector.is_empty() {
+            return (Vec::new(), vec![0], Vec::new());
+        }
+
+        let mut structure = Vec::new();
+        let mut merge_types = Vec::new();
+
+        let mut cluster_pointer: Vec<Uninitialized<usize>> = vec![Uninitialized::new(); self.cluster_vector.len()];
+        let mut pointer: Vec<Uninitialized<usize>> = vec![Uninitialized::new(); self.cluster_vector.len()*2];
+
+        //(current index, first occurrence)
+        let mut work_stack = vec![(self.cluster_vector.len() - 1, true, 0)];
+        let mut current_traverse_index: usize = 0;
+
+        //current_index is the true index in the cluster vector
+        while let Some((current_index, first_occurrence, own_traverse_index)) = work_stack.pop() {
+            let Cluster{ merge_type, first_child, second_child } = &self.cluster_vector[current_index];
+
+            if first_occurrence {
+                //increase the traverse index one time for each true cluster
+                current_traverse_index += 1;
+
+                //push us on the stack again
+                work_stack.push((current_index, false, own_traverse_index));
+
+                //push the merge type
+                merge_types.push(merge_type.get_i32());
+
+                //first child
+                if *first_child < self.label_vector.len() {
+                    //it is a leaf
+                    structure.push(false);
+                    pointer[own_traverse_index*2].set_value(*first_child);
+                } else if cluster_pointer[first_child - self.label_vector.len()].is_initialized() {
+                    //we already have this cluster
+                    structure.push(false);
+                    pointer[own_traverse_index*2].set_value(*cluster_pointer[first_child - self.label_vector.len()]);
+                } else {
+                    //it is a true cluster
+                    structure.push(true);
+                    work_stack.push((first_child - self.label_vector.len(), true, current_traverse_index));
+                }
+
+                //we need to push a dummy value
+                structure.push(false);
+            } else {
+                //second child
+                if *second_child < self.label_vector.len() {
+                    //it is a leaf
+                    structure[own_traverse_index*2 + 1] = false; //should already be false
+                    pointer[own_traverse_index*2 + 1].set_value(*second_child);
+                } else if cluster_pointer[second_child - self.label_vector.len()].is_initialized() {
+                    //we already have this cluster
+                    structure[own_traverse_index*2 + 1] = false; //should already be false
+                    pointer[own_traverse_index*2 + 1].set_value(*cluster_pointer[second_child - self.label_vector.len()]);
+                } else {
+                    //it is a true cluster
+                    structure[own_traverse_index*2 + 1] = true;
+                    work_stack.push((second_child - self.label_vector.len(), true, current_traverse_index));
+                }
+
+                //we have finished this cluster so we mark it as finished
+                //we set the pointer to the number of clusters we already have plus the offset for the labels
+                cluster_pointer[current_index].set_value(own_traverse_index + self.label_vector.len());
+            }
+        }
+        let pointer = pointer.iter().filter_map(|elem| elem.clone().try_into_inner()).collect();
+        (structure, pointer, merge_types)
+    }
+}
+
+impl<L: Label> Expand for TopDag<L> {
+    type Label = L;
+
+    ///a TopDag was built or validated completely, so every id expands
+    fn expansion(&self, id: usize) -> std::result::Result<Expansion<L>, DecodeError> {
+        if id < self.label_vector.len() {
+            Ok(Expansion::Label(self.label_vector[id].clone()))
+        } else {
+            let cluster = &self.cluster_vector[id - self.label_vector.len()];
+            Ok(Expansion::Cluster(cluster.merge_type.clone(), cluster.first_child, cluster.second_child))
+        }
+    }
+}
+
+impl<L: Label + Debug> Debug for TopDag<L> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let mut output = String::new();
+        write!(output, "Label Vector: \n")?;
+        for (id, label) in self.label_vector.iter().enumerate() {
+            write!(output, "({:?}, {}) \n", label, id)?;
+        }
+
+        write!(output, "\nCluster Vector: \n")?;
+        for (index, cluster) in self.cluster_vector.iter().enumerate() {
+            write!(output, "({:?}, {}) size: {} \n", cluster, index + self.label_vector.len(), self.cluster_size[index])?;
+        }
+
+        write!(f, "TopDAG: \n{}", output)
+    }
+}
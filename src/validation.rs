@@ -0,0 +1,182 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::Read;
+use std::fmt::{Formatter, Result, Display};
+
+
+
+///Upper bounds for data read from a compressed archive
+///An archive exceeding one of these is rejected before anything is expanded
+#[derive(Clone, Debug)]
+pub struct DecodeLimits {
+    ///maximal number of clusters in the TopDAG
+    pub max_clusters: usize,
+    ///maximal number of distinct labels
+    pub max_labels: usize,
+    ///maximal number of bytes of all labels together
+    pub max_label_bytes: usize,
+    ///maximal number of nodes in the decompressed tree
+    ///the default keeps an IO_Tree of at least 56 bytes per node below 4 GiB
+    pub max_decompressed_nodes: usize,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        DecodeLimits {
+            max_clusters: power_of_two_or_max(32),
+            max_labels: power_of_two_or_max(32),
+            max_label_bytes: power_of_two_or_max(34),
+            max_decompressed_nodes: 1 << 26,
+        }
+    }
+}
+
+impl DecodeLimits {
+    ///no limit at all, only for streams this crate has just written itself
+    pub fn unlimited() -> DecodeLimits {
+        DecodeLimits {
+            max_clusters: usize::max_value(),
+            max_labels: usize::max_value(),
+            max_label_bytes: usize::max_value(),
+            max_decompressed_nodes: usize::max_value(),
+        }
+    }
+}
+
+///2^exponent, or usize::max_value() on targets where that does not fit into usize
+fn power_of_two_or_max(exponent: u32) -> usize {
+    1usize.checked_shl(exponent).unwrap_or(usize::max_value())
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    ///a section of the archive could not be read
+    CannotLoad(&'static str),
+    ///the archive was written by a newer version of this crate
+    UnsupportedVersion(u32),
+    ///the meta section can not be parsed
+    InvalidMeta,
+    ///a section declares more elements than the limits allow
+    SectionTooLarge(&'static str),
+    ///the archive contains no node
+    Empty,
+    ///the structure, pointer and merge type streams do not fit together
+    LengthMismatch,
+    ///a merge type outside of the known mapping
+    UnknownMergeType(i32),
+    ///a pointer that references neither a label nor an already decoded cluster
+    InvalidPointer(usize),
+    ///the same cluster is stored twice
+    DuplicateCluster,
+    ///the root does not expand to a single tree
+    InvalidRoot,
+    ///the decompressed tree would exceed max_decompressed_nodes
+    TooLarge,
+    ///the label with this id can not be converted to the label type
+    InvalidLabel(usize),
+}
+
+impl Error for DecodeError {
+    fn description(&self) -> &str {
+        "Corrupt archive"
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        None
+    }
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        use self::DecodeError::*;
+        match self {
+            CannotLoad(section) => write!(f, "Cannot load the {} section", section),
+            UnsupportedVersion(version) => write!(f, "Format version {} is newer than the supported version", version),
+            InvalidMeta => write!(f, "The meta section is corrupt"),
+            SectionTooLarge(section) => write!(f, "The {} section exceeds the decode limits", section),
+            Empty => write!(f, "The archive contains no node"),
+            LengthMismatch => write!(f, "The lengths of the structure, pointer and merge type streams do not match"),
+            UnknownMergeType(merge_type) => write!(f, "Unknown merge type {}", merge_type),
+            InvalidPointer(pointer) => write!(f, "Pointer {} references no label or decoded cluster", pointer),
+            DuplicateCluster => write!(f, "A cluster is stored twice"),
+            InvalidRoot => write!(f, "The root cluster does not expand to a single tree"),
+            TooLarge => write!(f, "The decompressed tree exceeds the decode limits"),
+            InvalidLabel(id) => write!(f, "Label {} does not match the label type", id),
+        }
+    }
+}
+
+///checks that the streams can describe a TopDAG before any of them is indexed
+pub fn check_stream_lengths(structure: &[bool], pointer: &[usize], merge_types: &[i32], number_of_labels: usize, limits: &DecodeLimits) -> std::result::Result<(), DecodeError> {
+    if number_of_labels == 0 {
+        return Err(DecodeError::Empty);
+    }
+    if merge_types.is_empty() {
+        return check_single_node(structure.len(), pointer, number_of_labels);
+    }
+    if merge_types.len() > limits.max_clusters {
+        return Err(DecodeError::SectionTooLarge("merge_type"));
+    }
+    if number_of_labels > limits.max_labels {
+        return Err(DecodeError::SectionTooLarge("label"));
+    }
+
+    //every cluster has two children
+    if structure.len() != merge_types.len() * 2 {
+        return Err(DecodeError::LengthMismatch);
+    }
+
+    //every true cluster except the root is referenced by exactly one set bit, every other child by a pointer
+    let number_of_ones = structure.iter().filter(|bit| **bit).count();
+    if number_of_ones + 1 != merge_types.len() || structure.len() - number_of_ones != pointer.len() {
+        return Err(DecodeError::LengthMismatch);
+    }
+
+    Ok(())
+}
+
+///a tree of a single node has no cluster, the only pointer is its label and the root
+pub fn check_single_node(structure_length: usize, pointer: &[usize], number_of_labels: usize) -> std::result::Result<(), DecodeError> {
+    if structure_length != 0 || pointer.len() != 1 {
+        return Err(DecodeError::LengthMismatch);
+    }
+    if pointer[0] != 0 || number_of_labels != 1 {
+        return Err(DecodeError::InvalidRoot);
+    }
+    Ok(())
+}
+
+///reads the length sdsl stores at the start of a serialized vector and compares it with the limit
+///prevents sdsl from allocating the declared size of a forged file
+pub fn check_declared_length(path: &str, section: &'static str, max_length: usize) -> std::result::Result<(), DecodeError> {
+    let mut file = File::open(path).map_err(|_| DecodeError::CannotLoad(section))?;
+    let mut buffer = [0u8; 8];
+    file.read_exact(&mut buffer).map_err(|_| DecodeError::CannotLoad(section))?;
+
+    let mut declared_length: u64 = 0;
+    for (index, byte) in buffer.iter().enumerate() {
+        declared_length |= (*byte as u64) << (8 * index);
+    }
+
+    if declared_length > max_length as u64 {
+        Err(DecodeError::SectionTooLarge(section))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::power_of_two_or_max;
+
+    #[test]
+    fn limits_saturate_instead_of_overflowing() {
+        assert_eq!(power_of_two_or_max(0), 1);
+        assert_eq!(power_of_two_or_max(10), 1024);
+        assert_eq!(power_of_two_or_max(31), 1 << 31);
+        //the defaults use exponents up to 40, on 32 bit targets they become usize::max_value()
+        let bits = (0usize.count_zeros()) as u32;
+        assert_eq!(power_of_two_or_max(bits - 1), 1 << (bits - 1));
+        assert_eq!(power_of_two_or_max(bits), usize::max_value());
+    }
+}
@@ -0,0 +1,182 @@
+use std::error::Error;
+use std::fmt::{Formatter, Result, Display};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use quick_xml::Reader;
+use quick_xml::events::Event;
+
+use io_tree::{IO_Tree, ParseError, GenResult};
+use shared_tree::SharedTree;
+
+
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MismatchKind {
+    ///the node has a different label
+    Label,
+    ///the input has no node at the path, its parent has fewer children
+    MissingNode,
+    ///the decompressed tree has no node at the path, the parent in the input has more children
+    ExtraNode,
+}
+
+///The first node in preorder where the input differs from the decompressed tree
+#[derive(Clone, Debug)]
+pub struct Mismatch {
+    ///the child indices from the root to the node, the root has the empty path
+    pub path: Vec<usize>,
+    pub kind: MismatchKind,
+}
+
+impl Error for Mismatch {
+    fn description(&self) -> &str {
+        "Decompressed tree differs from the input"
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        None
+    }
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        use self::MismatchKind::*;
+        let kind = match self.kind {
+            Label => "the label differs",
+            MissingNode => "the input has no node there",
+            ExtraNode => "the decompressed tree has no node there",
+        };
+        write!(f, "The trees differ at the node with path {:?}: {}", self.path, kind)
+    }
+}
+
+///Walks the decompressed tree in preorder while the nodes of the input are opened and closed
+struct Walker<'a, L: 'a> {
+    tree: &'a SharedTree<L>,
+    ///(node, number of children that are opened already)
+    stack: Vec<(usize, usize)>,
+    ///the child indices of the nodes on the stack without the root
+    path: Vec<usize>,
+}
+
+impl<'a, L: 'a> Walker<'a, L> {
+    fn new(tree: &'a SharedTree<L>) -> Walker<'a, L> {
+        Walker { tree, stack: Vec::new(), path: Vec::new() }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    ///the next node of the input starts, equal compares its label with the label of the decompressed node
+    fn open<F: Fn(&L) -> bool>(&mut self, equal: F) -> std::result::Result<(), Mismatch> {
+        let node = match self.stack.last_mut() {
+            None => self.tree.root(),
+            Some((parent, opened)) => {
+                let index = *opened;
+                let node = match self.tree.children(*parent).get(index) {
+                    Some(node) => *node,
+                    None => return Err(mismatch(&self.path, Some(index), MismatchKind::ExtraNode)),
+                };
+                *opened += 1;
+                self.path.push(index);
+                node
+            },
+        };
+
+        self.stack.push((node, 0));
+        if !equal(self.tree.label(node)) {
+            return Err(mismatch(&self.path, None, MismatchKind::Label));
+        }
+        Ok(())
+    }
+
+    ///the current node of the input ends, all children of the decompressed node have to be opened
+    fn close(&mut self) -> std::result::Result<(), Mismatch> {
+        let (node, opened) = self.stack.pop().expect("Error: close without open");
+        if opened < self.tree.children(node).len() {
+            return Err(mismatch(&self.path, Some(opened), MismatchKind::MissingNode));
+        }
+        self.path.pop();
+        Ok(())
+    }
+}
+
+fn mismatch(path: &[usize], child: Option<usize>, kind: MismatchKind) -> Mismatch {
+    let mut path = path.to_vec();
+    path.extend(child);
+    Mismatch { path, kind }
+}
+
+impl<L: PartialEq> SharedTree<L> {
+    ///compares the decompressed tree with the input without expanding the shared subtrees
+    pub fn verify_against(&self, tree: &IO_Tree<L>) -> std::result::Result<(), Mismatch> {
+        let mut walker = Walker::new(self);
+        walker.open(|label| *label == tree.label)?;
+
+        //(node of the input, number of opened children)
+        let mut stack = vec![(tree, 0)];
+        while let Some((node, opened)) = stack.pop() {
+            match node.children.get(opened) {
+                Some(child) => {
+                    stack.push((node, opened + 1));
+                    walker.open(|label| *label == child.label)?;
+                    stack.push((child, 0));
+                },
+
+                None => walker.close()?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SharedTree<Vec<u8>> {
+    ///compares the decompressed tree with the xml while it is read, the input is never stored completely
+    pub fn verify_against_xml(&self, path: &str) -> GenResult<()> {
+        let file = File::open(path)?;
+        self.verify_against_xml_reader(BufReader::new(file))
+    }
+
+    ///like verify_against_xml but reads from any buffered source, for example stdin
+    pub fn verify_against_xml_reader<R: BufRead>(&self, source: R) -> GenResult<()> {
+        let mut reader = Reader::from_reader(source);
+        let mut buf = Vec::new();
+
+        let mut walker = Walker::new(self);
+        let mut has_root = false;
+
+        'filereader: loop {
+            match reader.read_event(&mut buf) {
+                Ok(Event::Start(ref elem)) => {
+                    //only the root has no parent
+                    if walker.is_finished() && has_root {return Err(Box::new(ParseError::CannotParse));}
+                    has_root = true;
+
+                    walker.open(|label| &label[..] == elem.name())?;
+                },
+
+                Ok(Event::End(_)) => {
+                    if walker.is_finished() {return Err(Box::new(ParseError::CannotParse));}
+                    walker.close()?;
+                },
+
+                Ok(Event::Eof) => break 'filereader,
+
+                Err(_) => {
+                    return Err(Box::new(ParseError::CannotParse))
+                },
+
+                _ => (), //the same events as in IO_Tree::new_from_xml are ignored
+            }
+
+            buf.clear();
+        }
+
+        if !has_root || !walker.is_finished() {
+            return Err(Box::new(ParseError::CannotParse));
+        }
+        Ok(())
+    }
+}
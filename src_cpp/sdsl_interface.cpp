@@ -4,6 +4,8 @@
 #include <sdsl/wavelet_trees.hpp>
 #include <sdsl/bit_vectors.hpp>
 #include <cstddef>
+#include <cstdint>
+#include <algorithm>
 
 using namespace sdsl;
 
@@ -69,6 +71,15 @@ void get_structure_vector_c(bool* new_structure) {
     }
 }
 
+//the bit i is bit i % 64 of the word i / 64, the bits behind the end are zero
+void get_structure_words_c(size_t first_word, size_t number_of_words, uint64_t* words) {
+    size_t length = get_structure_vector_length_c();
+    for (size_t i = 0; i < number_of_words; i++) {
+        size_t index = (first_word + i) * 64;
+        words[i] = structure.get_int(index, std::min<size_t>(64, length - index));
+    }
+}
+
 //-----------------------------------------------------------------------------------\\
 
 void set_pointer_vector_c(size_t length, const size_t* new_pointer) {
@@ -90,6 +101,12 @@ void get_pointer_vector_c(size_t* new_pointer) {
     }
 }
 
+void get_pointer_range_c(size_t first, size_t length, size_t* new_pointer) {
+    for (size_t i = 0; i < length; i++) {
+        new_pointer[i] = pointer[first + i];
+    }
+}
+
 //-----------------------------------------------------------------------------------\\
 
 void set_merge_type_vector_c(size_t length, const int* new_merge_type) {
@@ -111,6 +128,12 @@ void get_merge_type_vector_c(int* new_merge_type) {
     }
 }
 
+void get_merge_type_range_c(size_t first, size_t length, int* new_merge_type) {
+    for (size_t i = 0; i < length; i++) {
+        new_merge_type[i] = merge_type[first + i];
+    }
+}
+
 //-----------------------------------------------------------------------------------\\
 
 void set_label_vector_c(const char* new_label) {
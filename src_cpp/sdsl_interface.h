@@ -2,6 +2,7 @@
 extern "C" {
 #endif
 #include <cstddef>
+#include <cstdint>
 
 //-----------------------------------------------------------------------------------\\
 
@@ -31,6 +32,8 @@ size_t get_structure_vector_length_c();
 
 void get_structure_vector_c(bool* new_structure);
 
+void get_structure_words_c(size_t first_word, size_t number_of_words, uint64_t* words);
+
 //-----------------------------------------------------------------------------------\\
 
 void set_pointer_vector_c(size_t length, const size_t* new_pointer);
@@ -39,6 +42,8 @@ size_t get_pointer_vector_length_c();
 
 void get_pointer_vector_c(size_t* new_pointer);
 
+void get_pointer_range_c(size_t first, size_t length, size_t* new_pointer);
+
 //-----------------------------------------------------------------------------------\\
 
 void set_merge_type_vector_c(size_t length, const int* new_merge_type);
@@ -47,6 +52,8 @@ size_t get_merge_type_vector_length_c();
 
 void get_merge_type_vector_c(int* new_merge_type);
 
+void get_merge_type_range_c(size_t first, size_t length, int* new_merge_type);
+
 //-----------------------------------------------------------------------------------\\
 
 void set_label_vector_c(const char* new_label);
@@ -0,0 +1,140 @@
+extern crate top_tree_compression;
+
+mod common;
+
+use std::collections::VecDeque;
+use std::env;
+use std::fs;
+
+use top_tree_compression::TopTreeBuilder;
+use top_tree_compression::arena_tree::ArenaTree;
+use top_tree_compression::io_tree::IO_Tree;
+use top_tree_compression::push_builder::BuildError;
+use top_tree_compression::top_dag::ClusterInfo;
+use common::{Random, random_tree};
+
+
+
+///deep enough that a recursive conversion would overflow the stack
+fn chain(length: usize) -> IO_Tree {
+    let mut tree = IO_Tree { label: b"end".to_vec(), children: VecDeque::new() };
+    for index in 0..length {
+        tree = IO_Tree { label: format!("{}", index % 2).into_bytes(), children: vec![tree].into_iter().collect() };
+    }
+    tree
+}
+
+fn preorder(tree: &IO_Tree) -> Vec<&IO_Tree> {
+    let mut nodes = Vec::new();
+    let mut stack = vec![tree];
+    while let Some(node) = stack.pop() {
+        nodes.push(node);
+        stack.extend(node.children.iter().rev());
+    }
+    nodes
+}
+
+#[test]
+fn conversion_round_trips() {
+    let mut random = Random(17);
+    let mut trees: Vec<IO_Tree> = (0..20).map(|_| random_tree(&mut random, 6, 6, 5)).collect();
+    trees.push(chain(200_000));
+    trees.push(IO_Tree { label: b"single".to_vec(), children: VecDeque::new() });
+
+    for tree in &trees {
+        let arena_tree = ArenaTree::from_IO_tree(tree);
+        assert!(arena_tree.to_IO_tree() == *tree);
+        assert_eq!(arena_tree.count(), tree.count());
+    }
+}
+
+#[test]
+fn nodes_are_stored_in_preorder_with_interned_labels() {
+    let tree = random_tree(&mut Random(23), 5, 6, 5);
+    let arena_tree = ArenaTree::from_IO_tree(&tree);
+    let nodes = preorder(&tree);
+
+    assert_eq!(arena_tree.root(), Some(0));
+    assert_eq!(arena_tree.number_of_nodes(), nodes.len());
+    for (index, node) in nodes.iter().enumerate() {
+        assert_eq!(*arena_tree.label(index), node.label);
+        assert_eq!(arena_tree.labels()[arena_tree.label_id(index)], node.label);
+        assert_eq!(arena_tree.number_of_children(index), node.children.len());
+
+        let children: Vec<usize> = arena_tree.children(index).collect();
+        assert_eq!(children.len(), node.children.len());
+        assert_eq!(arena_tree.first_child(index), children.first().cloned());
+        for (child, child_tree) in children.iter().zip(node.children.iter()) {
+            assert_eq!(*arena_tree.label(*child), child_tree.label);
+        }
+        for pair in children.windows(2) {
+            assert_eq!(arena_tree.next_sibling(pair[0]), Some(pair[1]));
+        }
+        if let Some(last) = children.last() {
+            assert_eq!(arena_tree.next_sibling(*last), None);
+        }
+    }
+
+    //every distinct label is stored once
+    let mut labels = arena_tree.labels().to_vec();
+    labels.sort();
+    labels.dedup();
+    assert_eq!(labels.len(), arena_tree.labels().len());
+}
+
+#[test]
+fn xml_is_parsed_like_the_io_tree() {
+    let mut xml = Vec::new();
+    random_tree(&mut Random(29), 5, 6, 5).write_xml(&mut xml).unwrap();
+    let path = env::temp_dir().join("top_tree_arena_tree.xml").to_str().unwrap().to_owned();
+    fs::write(&path, &xml).unwrap();
+
+    let io_tree = IO_Tree::new_from_xml(&path).unwrap();
+    assert!(ArenaTree::new_from_xml(&path).unwrap().to_IO_tree() == io_tree);
+    assert!(ArenaTree::from_xml_reader(&xml[..]).unwrap().to_IO_tree() == io_tree);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn pushed_nodes_become_the_last_child() {
+    let mut arena_tree: ArenaTree = ArenaTree::new();
+    assert!(arena_tree.is_empty());
+    assert_eq!(arena_tree.root(), None);
+
+    let root = arena_tree.push(None, b"a".to_vec());
+    let first = arena_tree.push(Some(root), b"b".to_vec());
+    let second = arena_tree.push(Some(root), b"a".to_vec());
+    arena_tree.push(Some(first), b"c".to_vec());
+
+    assert_eq!(arena_tree.children(root).collect::<Vec<_>>(), vec![first, second]);
+    assert_eq!(arena_tree.label_id(second), arena_tree.label_id(root));
+    assert_eq!(arena_tree.labels().len(), 3);
+    assert_eq!(arena_tree.count(), (2, 2));
+}
+
+#[test]
+#[should_panic(expected = "already has a root")]
+fn a_second_root_is_rejected() {
+    let mut arena_tree: ArenaTree = ArenaTree::new();
+    arena_tree.push(None, b"a".to_vec());
+    arena_tree.push(None, b"b".to_vec());
+}
+
+#[test]
+fn empty_trees_are_rejected() {
+    let arena_tree: ArenaTree = ArenaTree::new();
+    match TopTreeBuilder::new_from_arena_tree(&arena_tree, None) {
+        Err(BuildError::Empty) => {},
+        other => panic!("expected BuildError::Empty, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn builders_from_both_trees_are_the_same() {
+    let tree = random_tree(&mut Random(31), 6, 6, 5);
+    let from_arena = TopTreeBuilder::new_from_arena_tree(&ArenaTree::from_IO_tree(&tree), None).unwrap().build();
+    let from_io_tree = TopTreeBuilder::new_from_IO_tree(tree, None).unwrap().build();
+
+    assert_eq!(from_arena.labels(), from_io_tree.labels());
+    assert_eq!(from_arena.clusters().collect::<Vec<ClusterInfo>>(), from_io_tree.clusters().collect::<Vec<ClusterInfo>>());
+}
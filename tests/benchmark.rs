@@ -0,0 +1,138 @@
+extern crate top_tree_compression;
+
+mod common;
+
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use top_tree_compression::TopTreeBuilder;
+use top_tree_compression::flags_and_statistic::{Flags, MergeRule};
+use common::{run, run_ok, temp_path};
+
+
+
+const CSV_HEADER: &str = "file,merge_rule,slowing_down,build_seconds,top_dag_size,encoded_size,number_of_merge_rounds";
+
+const RULES: [(&str, MergeRule); 3] = [
+    ("simplified", MergeRule::SimplifiedStandardRules),
+    ("fast-advanced", MergeRule::FastAdvancedRules),
+    ("slow-advanced", MergeRule::SlowAdvancedRules),
+];
+
+fn xml(children: usize, modulus: usize) -> String {
+    let mut xml = String::from("<root>");
+    for index in 0..children {
+        xml.push_str(&format!("<item><name>{}</name><value></value></item>", index % modulus));
+    }
+    xml.push_str("</root>");
+    xml
+}
+
+///two documents and a file that is no xml
+fn corpus(name: &str) -> String {
+    let directory = PathBuf::from(temp_path(name));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(directory.join("subdirectory")).unwrap();
+    fs::write(directory.join("a.xml"), xml(100, 3)).unwrap();
+    fs::write(directory.join("b.xml"), xml(300, 17)).unwrap();
+    fs::write(directory.join("broken.xml"), "<root><item></root>").unwrap();
+    directory.to_str().unwrap().to_owned()
+}
+
+///TopDAG size, encoded size and rounds the library reports for the file
+fn expected(path: &str, merge_rule: MergeRule, slowing_down: f64) -> (usize, usize, usize) {
+    let flags = Flags { merge_rule, slowing_down, ..Flags::default() };
+    let top_dag = TopTreeBuilder::from_xml_reader(Cursor::new(fs::read(path).unwrap()), Some(flags)).unwrap().build();
+    let mut container = Vec::new();
+    top_dag.to_compressed().write_container(&mut container).unwrap();
+    (top_dag.number_of_labels() + top_dag.number_of_clusters(), container.len(), top_dag.get_statistic().number_of_merge_rounds)
+}
+
+#[test]
+fn csv_has_a_row_for_every_file_and_configuration() {
+    let directory = corpus("csv");
+    let output = run(&["benchmark", &directory, "--csv"], b"");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("broken.xml"));
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut lines = stdout.lines();
+    assert_eq!(lines.next(), Some(CSV_HEADER));
+    let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+    assert_eq!(rows.len(), 2 * RULES.len());
+
+    let slowing_down = Flags::default().slowing_down;
+    for (index, row) in rows.iter().enumerate() {
+        //sorted by file, then in the order of the merge rules
+        let file = format!("{}/{}", directory, if index < RULES.len() { "a.xml" } else { "b.xml" });
+        let (name, merge_rule) = RULES[index % RULES.len()];
+        assert_eq!(row[0], file);
+        assert_eq!(row[1], name);
+        assert_eq!(row[2].parse::<f64>().unwrap(), slowing_down);
+        assert!(row[3].parse::<f64>().unwrap() >= 0.0);
+
+        let (top_dag_size, encoded_size, rounds) = expected(&file, merge_rule, slowing_down);
+        assert_eq!(row[4].parse::<usize>().unwrap(), top_dag_size);
+        assert_eq!(row[5].parse::<usize>().unwrap(), encoded_size);
+        assert_eq!(row[6].parse::<usize>().unwrap(), rounds);
+    }
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn lists_select_the_configurations() {
+    let directory = corpus("lists");
+    let stdout = String::from_utf8(run_ok(&["benchmark", &directory, "--csv", "--merge-rule", "simplified,slow-advanced", "--slowing-down", "2,8"], b"")).unwrap();
+    let rows: Vec<Vec<&str>> = stdout.lines().skip(1).map(|line| line.split(',').collect()).collect();
+    assert_eq!(rows.len(), 2 * 2 * 2);
+
+    let configurations: Vec<(&str, &str)> = rows[..4].iter().map(|row| (row[1], row[2])).collect();
+    assert_eq!(configurations, vec![("simplified", "2"), ("simplified", "8"), ("slow-advanced", "2"), ("slow-advanced", "8")]);
+
+    let file = format!("{}/b.xml", directory);
+    let (top_dag_size, encoded_size, rounds) = expected(&file, MergeRule::SlowAdvancedRules, 8.0);
+    assert_eq!(rows[7][4].parse::<usize>().unwrap(), top_dag_size);
+    assert_eq!(rows[7][5].parse::<usize>().unwrap(), encoded_size);
+    assert_eq!(rows[7][6].parse::<usize>().unwrap(), rounds);
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn table_ends_with_the_totals_sorted_by_size() {
+    let directory = corpus("table");
+    let stdout = String::from_utf8(run_ok(&["benchmark", &directory], b"")).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+
+    assert!(lines[0].starts_with("file"));
+    assert_eq!(lines[1..].iter().position(|line| line.is_empty()), Some(2 * RULES.len()));
+
+    //the header of the totals follows the blank line
+    assert!(lines[2 * RULES.len() + 2].starts_with("total"));
+    let totals = &lines[2 * RULES.len() + 3..];
+    assert_eq!(totals.len(), RULES.len());
+    let encoded_sizes: Vec<usize> = totals.iter().map(|line| line.split_whitespace().last().unwrap().parse().unwrap()).collect();
+    let mut sorted = encoded_sizes.clone();
+    sorted.sort();
+    assert_eq!(encoded_sizes, sorted);
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn wrong_arguments_are_usage_errors() {
+    assert!(!run(&["benchmark", &temp_path("missing")], b"").status.success());
+
+    let output = run(&["benchmark", "directory", "--merge-rule", "simplified,unknown"], b"");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--merge-rule needs"));
+
+    //a single factor that never ends the build rejects the whole list
+    let output = run(&["benchmark", "directory", "--slowing-down", "2,1"], b"");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--slowing-down needs"));
+
+    //lists are only for the benchmark
+    let output = run(&["stats", "-", "--slowing-down", "2,4"], b"");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Only benchmark"));
+}
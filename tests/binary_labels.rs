@@ -0,0 +1,115 @@
+extern crate top_tree_compression;
+
+mod common;
+
+use std::fs;
+
+use top_tree_compression::TopTreeBuilder;
+use top_tree_compression::compressed::CompressedTopDag;
+use top_tree_compression::flags_and_statistic::{LabelEncoding, PointerEncoding};
+use top_tree_compression::io_tree::IO_Tree;
+use top_tree_compression::push_builder::PushBuilder;
+use top_tree_compression::top_dag::TopDag;
+use top_tree_compression::validation::DecodeLimits;
+use common::temp_path;
+
+
+
+///labels with the separator, the escape symbol, the terminator of sdsl and bytes that are not utf-8
+fn binary_labels() -> Vec<Vec<u8>> {
+    vec![
+        (0..256).map(|byte| byte as u8).collect(),
+        Vec::new(),
+        b"line\nbreak".to_vec(),
+        b"\\n".to_vec(),
+        b"\\".to_vec(),
+        b"null\0byte".to_vec(),
+        vec![0xff, 0xfe, 0xc3, 0x28],
+        "gr\u{fc}\u{df}e".as_bytes().to_vec(),
+    ]
+}
+
+///every label is a leaf and again the label of an inner node, the same leaf appears twice
+fn binary_tree() -> TopDag {
+    let mut push_builder = PushBuilder::new(None);
+    push_builder.open(b"root".to_vec()).unwrap();
+    for label in binary_labels() {
+        push_builder.open(label.clone()).unwrap();
+        push_builder.leaf(label.clone()).unwrap();
+        push_builder.leaf(label).unwrap();
+        push_builder.close().unwrap();
+    }
+    push_builder.close().unwrap();
+    push_builder.finish().unwrap()
+}
+
+fn assert_binary_tree(tree: &IO_Tree) {
+    assert_eq!(tree.label, b"root".to_vec());
+    assert_eq!(tree.children.len(), binary_labels().len());
+    for (child, label) in tree.children.iter().zip(binary_labels()) {
+        assert_eq!(child.label, label);
+        assert_eq!(child.children.len(), 2);
+        assert!(child.children.iter().all(|leaf| leaf.label == label && leaf.children.is_empty()));
+    }
+}
+
+#[test]
+fn binary_labels_round_trip_through_every_archive_encoding() {
+    assert_binary_tree(&binary_tree().get_IO_tree());
+
+    for &label_encoding in &[LabelEncoding::WaveletTree, LabelEncoding::FrontCoding, LabelEncoding::Plain] {
+        let path = temp_path(&format!("{:?}", label_encoding));
+        binary_tree().save_to_file_with_encodings(&path, label_encoding, PointerEncoding::Vlc);
+
+        let loaded: TopDag = TopDag::new_fom_file(&path).unwrap();
+        assert_binary_tree(&loaded.get_IO_tree());
+        assert_binary_tree(&CompressedTopDag::new_fom_file(&path).unwrap().get_IO_tree().unwrap());
+        fs::remove_file(format!("{}.tar", path)).unwrap();
+    }
+}
+
+#[test]
+fn binary_labels_round_trip_through_the_container() {
+    let mut container = Vec::new();
+    binary_tree().to_compressed().write_container(&mut container).unwrap();
+
+    let loaded = CompressedTopDag::from_reader(&mut &container[..], &DecodeLimits::default()).unwrap();
+    assert_binary_tree(&loaded.get_IO_tree().unwrap());
+}
+
+#[test]
+fn xml_labels_keep_their_raw_bytes() {
+    //latin-1 element names are not valid utf-8 and must not be replaced
+    let xml = b"<r\xe9sum\xe9><n\xe4me></n\xe4me><n\xe4me></n\xe4me></r\xe9sum\xe9>".to_vec();
+    let path = temp_path("latin1.xml");
+    fs::write(&path, &xml).unwrap();
+
+    let tree = IO_Tree::new_from_xml(&path).unwrap();
+    assert_eq!(tree.label, b"r\xe9sum\xe9".to_vec());
+    assert_eq!(tree.children[0].label, b"n\xe4me".to_vec());
+
+    let top_dag = TopTreeBuilder::new_from_xml(&path, None).unwrap().build();
+    let mut output = Vec::new();
+    top_dag.get_IO_tree().write_xml(&mut output).unwrap();
+    assert_eq!(output, xml);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn labels_that_are_no_xml_names_are_not_written() {
+    for label in binary_labels() {
+        let tree = IO_Tree { label: b"root".to_vec(), children: vec![IO_Tree { label: label.clone(), children: Default::default() }].into_iter().collect() };
+        let result = tree.write_xml(&mut Vec::new());
+        if label == "gr\u{fc}\u{df}e".as_bytes() {
+            assert!(result.is_ok());
+        } else {
+            assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData, "{:?} was written", label);
+        }
+    }
+
+    //the name characters of xml, the first one is no digit, '-' or '.'
+    for &(label, valid) in &[(&b"ns:item-1.x_y"[..], true), (b"_1", true), (b"1a", false), (b"-a", false), (b".a", false), (b"a b", false), (b"a>", false), (b"a/", false)] {
+        let tree = IO_Tree { label: label.to_vec(), children: Default::default() };
+        assert_eq!(tree.write_xml(&mut Vec::new()).is_ok(), valid, "{:?}", label);
+    }
+}
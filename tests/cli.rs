@@ -0,0 +1,120 @@
+extern crate top_tree_compression;
+
+mod common;
+
+use std::fs;
+
+use top_tree_compression::flags_and_statistic::Statistic;
+use common::{run, run_ok, temp_path};
+
+
+
+const INPUT_XML: &str = "<a><b><c></c><d></d></b><e></e><b><c></c><d></d></b></a>";
+
+#[test]
+fn container_through_stdin_and_stdout() {
+    let container = run_ok(&["compress", "-", "-"], INPUT_XML.as_bytes());
+    assert!(!container.is_empty());
+    assert_eq!(run_ok(&["decompress", "-", "-"], &container), INPUT_XML.as_bytes());
+    assert_eq!(run_ok(&["decompress", "-", "-", "--format", "container"], &container), INPUT_XML.as_bytes());
+}
+
+#[test]
+fn max_nodes_limits_the_decompressed_tree() {
+    for format in &["container", "archive"] {
+        let archive = run_ok(&["compress", "-", "-", "--format", format], INPUT_XML.as_bytes());
+        let output = run(&["decompress", "-", "-", "--format", format, "--max-nodes", "3"], &archive);
+        assert!(!output.status.success());
+        assert!(output.stdout.is_empty());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("exceeds the decode limits"));
+
+        assert_eq!(run_ok(&["decompress", "-", "-", "--format", format, "--max-nodes", "1000"], &archive), INPUT_XML.as_bytes());
+    }
+    assert!(!run(&["decompress", "-", "-", "--max-nodes", "many"], b"").status.success());
+}
+
+#[test]
+fn container_through_files() {
+    let input = temp_path("container_input.xml");
+    let container = temp_path("container");
+    let output = temp_path("container_output.xml");
+    fs::write(&input, INPUT_XML).unwrap();
+
+    run_ok(&["compress", &input, &container, "--merge-rule", "slow-advanced"], b"");
+    run_ok(&["decompress", &container, &output], b"");
+    assert_eq!(fs::read(&output).unwrap(), INPUT_XML.as_bytes());
+
+    for path in &[input, container, output] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn archive_through_stdin_and_stdout() {
+    let archive = run_ok(&["compress", "-", "-", "--format", "archive"], INPUT_XML.as_bytes());
+    assert_eq!(run_ok(&["decompress", "-", "-", "--format", "archive"], &archive), INPUT_XML.as_bytes());
+
+    //the archive is not a container
+    assert!(!run(&["decompress", "-", "-"], &archive).status.success());
+}
+
+#[test]
+fn archive_through_files() {
+    let archive = temp_path("archive.tar");
+    run_ok(&["compress", "-", &archive, "--format", "archive"], INPUT_XML.as_bytes());
+    assert_eq!(run_ok(&["decompress", &archive, "-", "--format", "archive"], b""), INPUT_XML.as_bytes());
+
+    //the archive read from stdin keeps the name it was saved with
+    let bytes = fs::read(&archive).unwrap();
+    assert_eq!(run_ok(&["decompress", "-", "-", "--format", "archive"], &bytes), INPUT_XML.as_bytes());
+
+    fs::remove_file(&archive).unwrap();
+}
+
+#[test]
+fn archive_paths_end_with_tar() {
+    let output = run(&["compress", "-", &temp_path("archive_without_suffix"), "--format", "archive"], INPUT_XML.as_bytes());
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("has to end with .tar"));
+}
+
+#[test]
+fn garbage_on_stdin_is_rejected() {
+    for format in &["container", "archive"] {
+        let output = run(&["decompress", "-", "-", "--format", format], b"not an archive");
+        assert!(!output.status.success());
+        assert!(output.stdout.is_empty());
+    }
+}
+
+#[test]
+fn unknown_format_is_a_usage_error() {
+    let output = run(&["compress", "-", "-", "--format", "zip"], INPUT_XML.as_bytes());
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--format needs container or archive"));
+}
+
+#[test]
+fn slowing_downs_that_never_end_the_build_are_usage_errors() {
+    for slowing_down in &["1", "0.5", "0", "-2", "NaN", "inf", "four"] {
+        let output = run(&["compress", "-", "-", "--slowing-down", slowing_down], INPUT_XML.as_bytes());
+        assert!(!output.status.success(), "{} was accepted", slowing_down);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("--slowing-down needs"));
+    }
+    assert!(!run_ok(&["compress", "-", "-", "--slowing-down", "1.5"], INPUT_XML.as_bytes()).is_empty());
+}
+
+#[test]
+fn verify_and_stats_read_stdin() {
+    let verified = String::from_utf8(run_ok(&["verify", "-"], INPUT_XML.as_bytes())).unwrap();
+    assert!(verified.starts_with("Ok: "));
+
+    let csv = String::from_utf8(run_ok(&["stats", "-", "--csv"], INPUT_XML.as_bytes())).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some(Statistic::csv_header().as_str()));
+    assert_eq!(lines.next().unwrap().split(',').count(), Statistic::csv_header().split(',').count());
+    assert_eq!(lines.next(), None);
+
+    let output = run(&["verify", "-"], b"<a><b></a>");
+    assert!(!output.status.success());
+}
@@ -0,0 +1,91 @@
+//! helpers shared by the integration tests, every test crate uses only some of them
+#![allow(dead_code)]
+
+use std::collections::VecDeque;
+use std::env;
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+use top_tree_compression::io_tree::IO_Tree;
+use top_tree_compression::push_builder::PushBuilder;
+use top_tree_compression::top_dag::TopDag;
+
+
+
+///the binary is built next to the directory of the test executable
+pub fn binary() -> PathBuf {
+    let mut path = env::current_exe().unwrap();
+    path.pop();
+    if path.ends_with("deps") {
+        path.pop();
+    }
+    path.join("top_tree_compression")
+}
+
+pub fn run(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(binary())
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    //a usage error exits before stdin is read
+    if let Err(error) = child.stdin.take().unwrap().write_all(stdin) {
+        assert_eq!(error.kind(), ErrorKind::BrokenPipe);
+    }
+    child.wait_with_output().unwrap()
+}
+
+pub fn run_ok(args: &[&str], stdin: &[u8]) -> Vec<u8> {
+    let output = run(args, stdin);
+    assert!(output.status.success(), "{:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+    output.stdout
+}
+
+///a path in the temporary directory that starts with the name of the test crate,
+///so test crates that run at the same time never share a file
+pub fn temp_path(name: &str) -> String {
+    let executable = env::current_exe().unwrap();
+    let stem = executable.file_stem().unwrap().to_str().unwrap();
+    let test_crate = stem.split('-').next().unwrap();
+    env::temp_dir().join(format!("top_tree_{}_{}", test_crate, name)).to_str().unwrap().to_owned()
+}
+
+pub fn node(label: &str, children: Vec<IO_Tree>) -> IO_Tree {
+    IO_Tree { label: label.as_bytes().to_vec(), children: children.into_iter().collect::<VecDeque<_>>() }
+}
+
+///a list of items with one of 7 leafs each
+pub fn sample_tree(number_of_items: usize) -> TopDag {
+    let mut push_builder = PushBuilder::new(None);
+    push_builder.open(b"list".to_vec()).unwrap();
+    for index in 0..number_of_items {
+        push_builder.open(b"item".to_vec()).unwrap();
+        push_builder.leaf(format!("{}", index % 7).into_bytes()).unwrap();
+        push_builder.close().unwrap();
+    }
+    push_builder.close().unwrap();
+    push_builder.finish().unwrap()
+}
+
+///a linear congruential generator, so the random trees are the same in every run
+pub struct Random(pub u64);
+
+impl Random {
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (self.0 >> 33) % bound
+    }
+}
+
+///a tree of the given depth with labels `a`, `b`, … and less than `children_bound` children per inner node
+pub fn random_tree(random: &mut Random, depth: usize, number_of_labels: u64, children_bound: u64) -> IO_Tree {
+    let label = vec![b'a' + random.below(number_of_labels) as u8];
+    let number_of_children = if depth == 0 { 0 } else { random.below(children_bound) as usize };
+    IO_Tree {
+        label,
+        children: (0..number_of_children).map(|_| random_tree(random, depth - 1, number_of_labels, children_bound)).collect(),
+    }
+}
@@ -0,0 +1,172 @@
+extern crate top_tree_compression;
+
+mod common;
+
+use std::fs;
+
+use top_tree_compression::compressed::CompressedTopDag;
+use top_tree_compression::container::{Section, CONTAINER_VERSION};
+use top_tree_compression::io_tree::GenError;
+use top_tree_compression::validation::{DecodeLimits, DecodeError};
+use common::{sample_tree, temp_path};
+
+
+
+//the positions of the header words
+const VERSION: usize = 1;
+const BYTE_ORDER_MARK: usize = 2;
+const TOTAL_WORDS: usize = 3;
+const POINTER_OFFSET: usize = 19;
+const POINTER_WORDS: usize = 20;
+const MERGE_TYPE_OFFSET: usize = 23;
+const MERGE_TYPE_WORDS: usize = 24;
+
+fn container() -> Vec<u8> {
+    let mut container = Vec::new();
+    sample_tree(50).to_compressed().write_container(&mut container).unwrap();
+    container
+}
+
+fn word(container: &[u8], position: usize) -> u64 {
+    let mut word = [0u8; 8];
+    word.copy_from_slice(&container[position * 8..position * 8 + 8]);
+    u64::from_le_bytes(word)
+}
+
+fn set_word(container: &mut [u8], position: usize, value: u64) {
+    container[position * 8..position * 8 + 8].copy_from_slice(&value.to_le_bytes());
+}
+
+fn from_bytes(container: &[u8]) -> Result<CompressedTopDag<Section<Vec<u64>>>, GenError> {
+    CompressedTopDag::from_reader(&mut &container[..], &DecodeLimits::default())
+}
+
+///the container is rejected by the reader and by the mapping
+fn assert_rejected(name: &str, container: &[u8]) -> GenError {
+    let path = temp_path(name);
+    fs::write(&path, container).unwrap();
+    let mapped_error = CompressedTopDag::open_mmap(&path).err().expect("Error: The mapped container was accepted");
+    let limited_error = CompressedTopDag::open_mmap_with_limits(&path, &DecodeLimits::default()).err().expect("Error: The mapped container was accepted");
+    assert_eq!(limited_error.to_string(), mapped_error.to_string());
+    fs::remove_file(&path).unwrap();
+
+    let error = from_bytes(container).err().expect("Error: The container was accepted");
+    assert_eq!(error.to_string(), mapped_error.to_string());
+    error
+}
+
+fn assert_corrupt_container(error: GenError) {
+    match error.downcast_ref::<DecodeError>() {
+        Some(&DecodeError::CannotLoad("container")) => (),
+        other => panic!("Error: Expected a corrupt container, got {:?}", other),
+    }
+}
+
+#[test]
+fn mapped_containers_decompress_like_the_top_dag() {
+    let path = temp_path("round_trip");
+    sample_tree(50).to_compressed().save_to_container(&path).unwrap();
+    let tree = sample_tree(50).get_IO_tree();
+
+    let mapped = CompressedTopDag::open_mmap(&path).unwrap();
+    assert!(mapped.get_IO_tree().unwrap() == tree);
+    assert!(mapped.verify_against(&tree).is_ok());
+    assert_eq!(mapped.number_of_nodes(), sample_tree(50).number_of_nodes());
+    assert!(from_bytes(&container()).unwrap().get_IO_tree().unwrap() == tree);
+
+    //the mapping stays valid after the file is removed
+    fs::remove_file(&path).unwrap();
+    assert!(mapped.get_IO_tree().unwrap() == tree);
+}
+
+#[test]
+fn missing_and_misaligned_files_are_rejected() {
+    assert!(CompressedTopDag::open_mmap(&temp_path("missing")).is_err());
+
+    assert_corrupt_container(assert_rejected("empty", &[]));
+    let container = container();
+    assert_corrupt_container(assert_rejected("misaligned", &container[..container.len() - 3]));
+    assert_corrupt_container(assert_rejected("header_only", &container[..8 * 4]));
+}
+
+#[test]
+fn damaged_headers_are_rejected() {
+    let mut wrong_magic = container();
+    wrong_magic[0] ^= 0xff;
+    assert_corrupt_container(assert_rejected("magic", &wrong_magic));
+
+    let mut wrong_byte_order = container();
+    let byte_order_mark = word(&wrong_byte_order, BYTE_ORDER_MARK);
+    set_word(&mut wrong_byte_order, BYTE_ORDER_MARK, byte_order_mark.swap_bytes());
+    assert_corrupt_container(assert_rejected("byte_order", &wrong_byte_order));
+
+    //a truncated or extended container does not match the total length of the header
+    let container = container();
+    assert_corrupt_container(assert_rejected("truncated", &container[..container.len() - 8]));
+    let mut extended = container.clone();
+    extended.extend_from_slice(&[0; 8]);
+    assert_corrupt_container(assert_rejected("extended", &extended));
+}
+
+#[test]
+fn sections_outside_of_the_container_are_rejected() {
+    let mut outside = container();
+    let total_words = word(&outside, TOTAL_WORDS);
+    set_word(&mut outside, POINTER_OFFSET, total_words);
+    assert_corrupt_container(assert_rejected("outside", &outside));
+
+    let mut overflowing = container();
+    set_word(&mut overflowing, POINTER_WORDS, u64::max_value());
+    assert_corrupt_container(assert_rejected("overflowing", &overflowing));
+
+    let mut in_header = container();
+    set_word(&mut in_header, MERGE_TYPE_OFFSET, 0);
+    assert_corrupt_container(assert_rejected("in_header", &in_header));
+}
+
+#[test]
+fn newer_containers_are_rejected() {
+    let mut newer = container();
+    set_word(&mut newer, VERSION, CONTAINER_VERSION + 1);
+    match assert_rejected("newer", &newer).downcast_ref::<DecodeError>() {
+        Some(&DecodeError::UnsupportedVersion(version)) => assert_eq!(version as u64, CONTAINER_VERSION + 1),
+        other => panic!("Error: Expected an unsupported version, got {:?}", other),
+    }
+}
+
+#[test]
+fn corrupt_sections_fail_validation() {
+    //the header is intact, so only the validation finds the damage
+    let mut pointers = container();
+    let offset = word(&pointers, POINTER_OFFSET) as usize;
+    for position in offset..offset + word(&pointers, POINTER_WORDS) as usize {
+        set_word(&mut pointers, position, u64::max_value());
+    }
+    assert_rejected("pointers", &pointers);
+
+    let mut merge_types = container();
+    let offset = word(&merge_types, MERGE_TYPE_OFFSET) as usize;
+    for position in offset..offset + word(&merge_types, MERGE_TYPE_WORDS) as usize {
+        set_word(&mut merge_types, position, u64::max_value());
+    }
+    assert_rejected("merge_types", &merge_types);
+}
+
+#[test]
+fn limits_apply_to_containers() {
+    let mut limits = DecodeLimits::default();
+    limits.max_decompressed_nodes = sample_tree(50).number_of_nodes() - 1;
+    assert!(CompressedTopDag::from_reader(&mut &container()[..], &limits).is_err());
+
+    let path = temp_path("limits");
+    fs::write(&path, container()).unwrap();
+    assert!(CompressedTopDag::open_mmap_with_limits(&path, &limits).is_err());
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn containers_are_little_endian() {
+    let container = container();
+    assert_eq!(&container[..8], b"TTDAG\0\0\0");
+    assert_eq!(container[8 * BYTE_ORDER_MARK..8 * BYTE_ORDER_MARK + 8], [8, 7, 6, 5, 4, 3, 2, 1]);
+}
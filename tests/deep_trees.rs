@@ -0,0 +1,121 @@
+extern crate top_tree_compression;
+
+use std::collections::VecDeque;
+use std::io::Cursor;
+
+use top_tree_compression::TopTreeBuilder;
+use top_tree_compression::arena_tree::ArenaTree;
+use top_tree_compression::io_tree::IO_Tree;
+use top_tree_compression::push_builder::PushBuilder;
+
+
+
+///deeper than the call stack of a test thread allows for any recursion
+const DEPTH: usize = 1_000_000;
+
+fn label(level: usize) -> Vec<u8> {
+    if level % 2 == 0 { b"a".to_vec() } else { b"b".to_vec() }
+}
+
+///a unary chain with DEPTH inner nodes above one leaf, built from the bottom
+fn chain() -> IO_Tree {
+    let mut tree = IO_Tree { label: label(DEPTH), children: VecDeque::new() };
+    for level in (0..DEPTH).rev() {
+        let mut children = VecDeque::with_capacity(1);
+        children.push_back(tree);
+        tree = IO_Tree { label: label(level), children };
+    }
+    tree
+}
+
+fn chain_xml() -> Vec<u8> {
+    let mut xml = Vec::with_capacity(DEPTH * 7);
+    for level in 0..DEPTH + 1 {
+        xml.extend_from_slice(b"<");
+        xml.extend_from_slice(&label(level));
+        xml.extend_from_slice(b">");
+    }
+    for level in (0..DEPTH + 1).rev() {
+        xml.extend_from_slice(b"</");
+        xml.extend_from_slice(&label(level));
+        xml.extend_from_slice(b">");
+    }
+    xml
+}
+
+#[test]
+fn insert_expand_clone_compare_and_drop_a_deep_chain() {
+    let tree = chain();
+    assert_eq!(tree.count(), (DEPTH, 1));
+
+    let copy = tree.clone();
+    assert!(copy == tree);
+
+    let top_dag = TopTreeBuilder::new_from_IO_tree(copy, None).unwrap().build();
+    assert_eq!(top_dag.number_of_nodes(), DEPTH + 1);
+    assert_eq!(top_dag.get_statistic().depth_of_io_tree, DEPTH + 1);
+
+    let expanded = top_dag.get_IO_tree();
+    assert!(expanded == tree);
+    assert!(top_dag.verify_against(&tree).is_ok());
+    drop(expanded);
+}
+
+#[test]
+fn push_builder_builds_a_deep_chain() {
+    let mut push_builder = PushBuilder::new(None);
+    for level in 0..DEPTH {
+        push_builder.open(label(level)).unwrap();
+    }
+    push_builder.leaf(label(DEPTH)).unwrap();
+    for _ in 0..DEPTH {
+        push_builder.close().unwrap();
+    }
+    let top_dag = push_builder.finish().unwrap();
+
+    let expanded = top_dag.get_IO_tree();
+    assert!(expanded == chain());
+    let copy = expanded.clone();
+    drop(expanded);
+    assert_eq!(copy.count(), (DEPTH, 1));
+}
+
+#[test]
+fn arena_tree_reads_and_builds_a_deep_chain() {
+    let arena_tree = ArenaTree::from_xml_reader(Cursor::new(chain_xml())).unwrap();
+    assert_eq!(arena_tree.count(), (DEPTH, 1));
+
+    let top_dag = TopTreeBuilder::new_from_arena_tree(&arena_tree, None).unwrap().build();
+    let tree = chain();
+    assert!(arena_tree.to_IO_tree() == tree);
+    assert!(top_dag.get_IO_tree() == tree);
+    assert!(ArenaTree::from_IO_tree(&tree).to_IO_tree() == tree);
+
+    let mut xml = Vec::new();
+    tree.write_xml(&mut xml).unwrap();
+    assert!(xml == chain_xml());
+    assert!(top_dag.get_shared_tree().verify_against_xml_reader(Cursor::new(xml)).is_ok());
+}
+
+#[test]
+fn debug_prints_a_deep_chain() {
+    let tree = chain();
+    let output = format!("{:?}", tree);
+    assert!(output.starts_with("IO_Tree { label: [97], children: [IO_Tree { label: [98], children: ["));
+    assert_eq!(output.matches("IO_Tree {").count(), DEPTH + 1);
+}
+
+#[test]
+fn debug_matches_the_derived_format() {
+    let leaf = |label: &str| IO_Tree { label: label.to_owned(), children: VecDeque::new() };
+    let mut inner = leaf("b");
+    inner.children.push_back(leaf("c"));
+    let mut root = leaf("a");
+    root.children.push_back(inner);
+    root.children.push_back(leaf("d"));
+
+    assert_eq!(
+        format!("{:?}", root),
+        "IO_Tree { label: \"a\", children: [IO_Tree { label: \"b\", children: [IO_Tree { label: \"c\", children: [] }] }, IO_Tree { label: \"d\", children: [] }] }"
+    );
+}
@@ -0,0 +1,52 @@
+extern crate top_tree_compression;
+
+use std::collections::VecDeque;
+
+use top_tree_compression::TopTreeBuilder;
+use top_tree_compression::io_tree::IO_Tree;
+use top_tree_compression::push_builder::PushBuilder;
+
+
+
+///a label type without Debug, labels only have to be compared, hashed and cloned
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Kind {
+    Block,
+    Call,
+    Literal(u32),
+}
+
+fn node(label: Kind, children: Vec<IO_Tree<Kind>>) -> IO_Tree<Kind> {
+    IO_Tree { label, children: children.into_iter().collect::<VecDeque<_>>() }
+}
+
+fn sample_tree() -> IO_Tree<Kind> {
+    let call = || node(Kind::Call, vec![node(Kind::Literal(1), vec![]), node(Kind::Literal(2), vec![])]);
+    node(Kind::Block, vec![call(), call(), node(Kind::Block, vec![call(), node(Kind::Literal(3), vec![])])])
+}
+
+#[test]
+fn labels_without_debug_round_trip() {
+    let tree = sample_tree();
+    let top_dag = TopTreeBuilder::new_from_IO_tree(tree.clone(), None).unwrap().build();
+
+    assert!(top_dag.get_IO_tree() == tree);
+    assert!(top_dag.verify_against(&tree).is_ok());
+    assert_eq!(top_dag.number_of_labels(), 5);
+}
+
+#[test]
+fn u32_token_ids_as_labels() {
+    let mut push_builder = PushBuilder::new(None);
+    push_builder.open(7u32).unwrap();
+    for token in 0..100u32 {
+        push_builder.leaf(token % 3).unwrap();
+    }
+    push_builder.close().unwrap();
+    let top_dag = push_builder.finish().unwrap();
+
+    let tree = top_dag.get_IO_tree();
+    assert_eq!(tree.label, 7);
+    assert_eq!(tree.children.len(), 100);
+    assert!(tree.children.iter().enumerate().all(|(index, child)| child.label == index as u32 % 3));
+}
@@ -0,0 +1,145 @@
+extern crate top_tree_compression;
+
+mod common;
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::process::Command;
+
+use top_tree_compression::TopTreeBuilder;
+use top_tree_compression::compressed::CompressedTopDag;
+use top_tree_compression::flags_and_statistic::{LabelEncoding, PointerEncoding};
+use top_tree_compression::format::{Meta, SdslSections, Streams, decode_sections, VERSION_1, VERSION_2, VERSION_3, VERSION_4, CURRENT_VERSION};
+use top_tree_compression::io_tree::IO_Tree;
+use top_tree_compression::top_dag::TopDag;
+use top_tree_compression::validation::{DecodeError, DecodeLimits};
+use common::{node, temp_path};
+
+
+
+///every directory in tests/golden was written once by the encoder of its version and is never changed
+///the sections this crate encodes (meta, label.fc, label.plain, pointer.huff) are the bytes the version wrote,
+///a section sdsl stores is a .values file with the values of its vector, so the goldens load without sdsl:
+///the number of values and then every value, each as a little endian u64
+const GOLDEN_ARCHIVES: [(u32, LabelEncoding, PointerEncoding); 4] = [
+    //no meta section, the labels are not escaped
+    (VERSION_1, LabelEncoding::WaveletTree, PointerEncoding::Vlc),
+    (VERSION_2, LabelEncoding::WaveletTree, PointerEncoding::Vlc),
+    (VERSION_3, LabelEncoding::FrontCoding, PointerEncoding::Vlc),
+    (VERSION_4, LabelEncoding::Plain, PointerEncoding::Huffman),
+];
+
+fn item() -> IO_Tree {
+    node("item", vec![node("name", vec![]), node("value", vec![])])
+}
+
+///repeated subtrees and labels, so every golden has merges of both kinds and shared clusters
+fn golden_tree() -> IO_Tree {
+    node("root", vec![
+        item(),
+        node("list", vec![item(), item(), node("value", vec![])]),
+        item(),
+        node("list", vec![item(), item(), node("value", vec![])]),
+    ])
+}
+
+fn golden_path(version: u32) -> String {
+    format!("{}/tests/golden/version_{}", env!("CARGO_MANIFEST_DIR"), version)
+}
+
+fn run_tar(directory: &str, args: &[&str]) {
+    assert!(Command::new("tar").current_dir(directory).args(args).status().unwrap().success());
+}
+
+///None if the version did not store the section with sdsl
+fn read_values(path: &str) -> Option<Vec<u64>> {
+    let bytes = fs::read(path).ok()?;
+    let word = |index: usize| {
+        let mut word = [0u8; 8];
+        word.copy_from_slice(&bytes[index * 8..(index + 1) * 8]);
+        u64::from_le_bytes(word)
+    };
+    assert_eq!(bytes.len() as u64, 8 * (1 + word(0)), "Error: {} is truncated", path);
+    Some((1..bytes.len() / 8).map(word).collect())
+}
+
+fn golden_sections(directory: &str) -> SdslSections {
+    let values = |section: &str| read_values(&format!("{}/{}.values", directory, section));
+    SdslSections {
+        structure: values("structure").unwrap().into_iter().map(|value| value != 0).collect(),
+        merge_type: values("merge_type").unwrap().into_iter().map(|value| value as i32).collect(),
+        pointer: values("pointer").map(|values| values.into_iter().map(|value| value as usize).collect()),
+        label: values("label").map(|values| values.into_iter().map(|value| value as u8).collect()),
+    }
+}
+
+fn assert_golden_archive(version: u32) {
+    let &(_, label_encoding, pointer_encoding) = GOLDEN_ARCHIVES.iter().find(|golden| golden.0 == version).unwrap();
+    let directory = golden_path(version);
+    let meta = Meta::load(&directory).unwrap();
+    assert_eq!(meta, Meta { version, label_encoding, pointer_encoding });
+
+    let limits = DecodeLimits::default();
+    let streams = decode_sections(&directory, &meta, golden_sections(&directory), &limits).unwrap();
+
+    let compressed_top_dag = CompressedTopDag::from_streams(&streams, &limits).unwrap();
+    assert!(compressed_top_dag.get_IO_tree().unwrap() == golden_tree(), "Error: The compressed golden of version {} decodes to a different tree", version);
+
+    let Streams { structure, pointer, merge_type, label } = streams;
+    let top_dag: TopDag = TopDag::from_streams(structure, pointer, merge_type, label.into_vec(), &limits).unwrap();
+    assert!(top_dag.get_IO_tree() == golden_tree(), "Error: The golden of version {} decodes to a different tree", version);
+    assert!(top_dag.verify_against(&golden_tree()).is_ok());
+}
+
+#[test]
+fn version_1_golden_archive() {
+    assert_golden_archive(VERSION_1);
+}
+
+#[test]
+fn version_2_golden_archive() {
+    assert_golden_archive(VERSION_2);
+}
+
+#[test]
+fn version_3_golden_archive() {
+    assert_golden_archive(VERSION_3);
+}
+
+#[test]
+fn version_4_golden_archive() {
+    assert_golden_archive(VERSION_4);
+}
+
+#[test]
+fn every_version_has_a_golden_archive() {
+    assert_eq!(GOLDEN_ARCHIVES.len() as u32, CURRENT_VERSION);
+}
+
+#[test]
+fn future_versions_are_rejected() {
+    let directory = temp_path("future");
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+
+    let top_dag = TopTreeBuilder::new_from_IO_tree(golden_tree(), None).unwrap().build();
+    top_dag.save_to_file_with_encodings(&format!("{}/archive", directory), LabelEncoding::Plain, PointerEncoding::Vlc);
+    run_tar(&directory, &["-xf", "archive.tar"]);
+
+    //an unknown key of the future version is not the reason it is rejected
+    let future_version = CURRENT_VERSION + 1;
+    File::create(format!("{}/archive/meta", directory)).unwrap()
+        .write_all(format!("version={}\nlabel_encoding=plain\npointer_encoding=vlc\nfuture_key=1\n", future_version).as_bytes()).unwrap();
+
+    match Meta::load(&format!("{}/archive", directory)).unwrap_err().downcast_ref::<DecodeError>() {
+        Some(&DecodeError::UnsupportedVersion(version)) => assert_eq!(version, future_version),
+        other => panic!("Error: Expected an unsupported version, got {:?}", other),
+    }
+
+    run_tar(&directory, &["-cf", "archive.tar", "archive"]);
+    let error = TopDag::<Vec<u8>>::new_fom_file(&format!("{}/archive", directory)).err().unwrap();
+    assert_eq!(error.to_string(), format!("Format version {} is newer than the supported version", future_version));
+    assert!(CompressedTopDag::new_fom_file(&format!("{}/archive", directory)).is_err());
+
+    fs::remove_dir_all(&directory).unwrap();
+}
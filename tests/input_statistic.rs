@@ -0,0 +1,111 @@
+extern crate top_tree_compression;
+
+mod common;
+
+use std::io::Cursor;
+
+use top_tree_compression::TopTreeBuilder;
+use top_tree_compression::arena_tree::ArenaTree;
+use top_tree_compression::flags_and_statistic::{Flags, Statistic};
+use top_tree_compression::io_tree::IO_Tree;
+use top_tree_compression::push_builder::PushBuilder;
+use top_tree_compression::top_dag::TopDag;
+use common::node;
+
+
+
+///4 inner nodes, 5 leafs, 8 edges, 5 distinct labels and 4 nodes on the longest path
+fn tree() -> IO_Tree {
+    node("a", vec![
+        node("b", vec![node("c", vec![]), node("c", vec![])]),
+        node("b", vec![node("c", vec![]), node("d", vec![node("e", vec![])])]),
+        node("c", vec![]),
+    ])
+}
+
+fn assert_input_counts(statistic: &Statistic) {
+    assert_eq!(statistic.number_of_nodes_in_io_tree, 4);
+    assert_eq!(statistic.number_of_leafs_in_io_tree, 5);
+    assert_eq!(statistic.number_of_edges_in_io_tree, 8);
+    assert_eq!(statistic.number_of_labels_in_io_tree, 5);
+    assert_eq!(statistic.depth_of_io_tree, 4);
+}
+
+fn assert_top_dag_counts(top_dag: &TopDag) {
+    let statistic = top_dag.get_statistic();
+    assert_eq!(statistic.number_of_leafs_in_top_dag, top_dag.number_of_labels());
+    assert_eq!(statistic.number_of_nodes_in_top_dag, top_dag.number_of_clusters());
+    assert_eq!(statistic.shape.number_of_nodes, top_dag.number_of_nodes());
+
+    let input = statistic.number_of_nodes_in_io_tree + statistic.number_of_leafs_in_io_tree;
+    assert_eq!(statistic.node_compression_ratio(), input as f64 / (top_dag.number_of_labels() + top_dag.number_of_clusters()) as f64);
+    assert_eq!(statistic.edge_compression_ratio(), statistic.number_of_edges_in_io_tree as f64 / (2 * top_dag.number_of_clusters()) as f64);
+}
+
+#[test]
+fn every_input_path_counts_the_same() {
+    let mut xml = Vec::new();
+    tree().write_xml(&mut xml).unwrap();
+
+    let mut push_builder = PushBuilder::new(None);
+    push_builder.open(b"a".to_vec()).unwrap();
+    push_builder.open(b"b".to_vec()).unwrap();
+    push_builder.leaf(b"c".to_vec()).unwrap();
+    push_builder.leaf(b"c".to_vec()).unwrap();
+    push_builder.close().unwrap();
+    push_builder.open(b"b".to_vec()).unwrap();
+    push_builder.leaf(b"c".to_vec()).unwrap();
+    push_builder.open(b"d".to_vec()).unwrap();
+    push_builder.leaf(b"e".to_vec()).unwrap();
+    push_builder.close().unwrap();
+    push_builder.close().unwrap();
+    push_builder.leaf(b"c".to_vec()).unwrap();
+    push_builder.close().unwrap();
+
+    let top_dags = vec![
+        TopTreeBuilder::new_from_IO_tree(tree(), None).unwrap().build(),
+        TopTreeBuilder::new_from_arena_tree(&ArenaTree::from_IO_tree(&tree()), None).unwrap().build(),
+        TopTreeBuilder::from_xml_reader(Cursor::new(xml), None).unwrap().build(),
+        push_builder.finish().unwrap(),
+    ];
+    for top_dag in &top_dags {
+        assert_input_counts(top_dag.get_statistic());
+        assert_top_dag_counts(top_dag);
+    }
+}
+
+#[test]
+fn counts_do_not_depend_on_the_reserved_capacity() {
+    for &capacity_hint in &[Some(1), Some(1_000_000)] {
+        let flags = Flags { capacity_hint, ..Flags::default() };
+        let top_dag = TopTreeBuilder::new_from_IO_tree(tree(), Some(flags)).unwrap().build();
+        assert_input_counts(top_dag.get_statistic());
+        assert_top_dag_counts(&top_dag);
+    }
+}
+
+#[test]
+fn depth_of_a_chain() {
+    let mut chain = node("leaf", vec![]);
+    for _ in 0..999 {
+        chain = node("inner", vec![chain]);
+    }
+    let statistic = TopTreeBuilder::new_from_IO_tree(chain, None).unwrap().build().get_statistic().clone();
+    assert_eq!(statistic.depth_of_io_tree, 1000);
+    assert_eq!(statistic.number_of_nodes_in_io_tree, 999);
+    assert_eq!(statistic.number_of_leafs_in_io_tree, 1);
+    assert_eq!(statistic.number_of_edges_in_io_tree, 999);
+    assert_eq!(statistic.number_of_labels_in_io_tree, 2);
+}
+
+#[test]
+fn a_single_node_has_no_edge() {
+    let statistic = TopTreeBuilder::new_from_IO_tree(node("a", vec![]), None).unwrap().build().get_statistic().clone();
+    assert_eq!(statistic.number_of_nodes_in_io_tree, 0);
+    assert_eq!(statistic.number_of_leafs_in_io_tree, 1);
+    assert_eq!(statistic.number_of_edges_in_io_tree, 0);
+    assert_eq!(statistic.number_of_labels_in_io_tree, 1);
+    assert_eq!(statistic.depth_of_io_tree, 1);
+    assert_eq!(statistic.number_of_nodes_in_top_dag, 0);
+    assert_eq!(statistic.edge_compression_ratio(), 0.0);
+}
@@ -0,0 +1,160 @@
+extern crate top_tree_compression;
+
+mod common;
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use top_tree_compression::{TopTreeBuilder, MergeType};
+use top_tree_compression::compressed::CompressedTopDag;
+use top_tree_compression::io_tree::IO_Tree;
+use top_tree_compression::push_builder::PushBuilder;
+use top_tree_compression::top_dag::TopDag;
+use common::{Random, random_tree};
+
+
+
+fn input_labels(tree: &IO_Tree, labels: &mut BTreeSet<Vec<u8>>) -> usize {
+    labels.insert(tree.label.clone());
+    1 + tree.children.iter().map(|child| input_labels(child, labels)).sum::<usize>()
+}
+
+///a(b(c, d), e)
+fn small_tree() -> TopDag {
+    let mut push_builder = PushBuilder::new(None);
+    push_builder.open(b"a".to_vec()).unwrap();
+    push_builder.open(b"b".to_vec()).unwrap();
+    push_builder.leaf(b"c".to_vec()).unwrap();
+    push_builder.leaf(b"d".to_vec()).unwrap();
+    push_builder.close().unwrap();
+    push_builder.leaf(b"e".to_vec()).unwrap();
+    push_builder.close().unwrap();
+    push_builder.finish().unwrap()
+}
+
+#[test]
+fn ids_are_labels_then_clusters() {
+    let top_dag = small_tree();
+    assert_eq!(top_dag.labels(), &[b"a".to_vec(), b"b".to_vec(), b"c".to_vec(), b"d".to_vec(), b"e".to_vec()][..]);
+    assert_eq!(top_dag.number_of_labels(), 5);
+    assert_eq!(top_dag.root(), top_dag.number_of_labels() + top_dag.number_of_clusters() - 1);
+
+    for id in 0..top_dag.number_of_labels() {
+        assert!(top_dag.is_label(id));
+        assert!(top_dag.cluster(id).is_none());
+        assert_eq!(top_dag.size(id), Some(1));
+    }
+
+    let root = top_dag.cluster(top_dag.root()).unwrap();
+    assert_eq!(root.id, top_dag.root());
+    assert_eq!(root.size, 5);
+    assert!(!top_dag.is_label(top_dag.root()));
+    assert!(top_dag.label(top_dag.root()).is_none());
+
+    //nothing after the root
+    assert!(top_dag.cluster(top_dag.root() + 1).is_none());
+    assert!(top_dag.size(top_dag.root() + 1).is_none());
+    assert!(top_dag.label(top_dag.root() + 1).is_none());
+}
+
+#[test]
+fn clusters_are_consistent() {
+    let mut random = Random(3);
+    for _ in 0..30 {
+        let tree = random_tree(&mut random, 6, 4, 5);
+        let mut labels = BTreeSet::new();
+        let number_of_nodes = input_labels(&tree, &mut labels);
+        let top_dag = TopTreeBuilder::new_from_IO_tree(tree, None).unwrap().build();
+
+        assert_eq!(top_dag.labels().iter().cloned().collect::<BTreeSet<_>>(), labels);
+        assert_eq!(top_dag.labels().len(), labels.len());
+        assert_eq!(top_dag.number_of_nodes(), number_of_nodes);
+        assert_eq!(top_dag.size(top_dag.root()), Some(number_of_nodes));
+
+        let clusters: Vec<_> = top_dag.clusters().collect();
+        assert_eq!(clusters.len(), top_dag.number_of_clusters());
+        for (index, cluster) in clusters.iter().enumerate() {
+            assert_eq!(cluster.id, top_dag.number_of_labels() + index);
+            assert_eq!(top_dag.cluster(cluster.id).as_ref(), Some(cluster));
+
+            //children are decoded before their parents and the sizes add up
+            assert!(cluster.first_child < cluster.id && cluster.second_child < cluster.id);
+            assert_eq!(cluster.size, top_dag.size(cluster.first_child).unwrap() + top_dag.size(cluster.second_child).unwrap());
+            assert_eq!(Some(cluster.size), top_dag.size(cluster.id));
+        }
+
+        //the same pair of children is stored once
+        let distinct: BTreeSet<_> = clusters.iter().map(|cluster| (format!("{:?}", cluster.merge_type), cluster.first_child, cluster.second_child)).collect();
+        assert_eq!(distinct.len(), clusters.len());
+    }
+}
+
+#[test]
+fn shape_matches_the_clusters() {
+    let mut random = Random(5);
+    let tree = IO_Tree { label: b"root".to_vec(), children: (0..8).map(|_| random_tree(&mut random, 5, 4, 5)).collect::<VecDeque<_>>() };
+    let top_dag = TopTreeBuilder::new_from_IO_tree(tree, None).unwrap().build();
+    let shape = top_dag.shape();
+
+    let mut heights = vec![0; top_dag.number_of_labels()];
+    let mut parents = vec![0; top_dag.number_of_labels() + top_dag.number_of_clusters()];
+    for cluster in top_dag.clusters() {
+        heights.push(1 + heights[cluster.first_child].max(heights[cluster.second_child]));
+        parents[cluster.first_child] += 1;
+        parents[cluster.second_child] += 1;
+    }
+    assert_eq!(shape.height, heights[top_dag.root()]);
+    assert_eq!(shape.number_of_nodes, top_dag.number_of_nodes());
+    assert_eq!(shape.sharing_histogram.values().sum::<usize>(), top_dag.number_of_clusters());
+    for (number_of_parents, number_of_clusters) in &shape.sharing_histogram {
+        assert_eq!(parents[top_dag.number_of_labels()..].iter().filter(|parents| *parents == number_of_parents).count(), *number_of_clusters);
+    }
+
+    let below_root = top_dag.clusters().filter(|cluster| cluster.id != top_dag.root()).map(|cluster| cluster.size).max().unwrap();
+    assert_eq!(shape.max_cluster_size, below_root);
+}
+
+#[test]
+fn merge_types_are_public() {
+    let top_dag = small_tree();
+    let vertical = top_dag.clusters().filter(|cluster| cluster.merge_type == MergeType::AB).count();
+    assert!(vertical > 0);
+    assert!(vertical < top_dag.number_of_clusters());
+}
+
+///the size of every id from the children the compressed queries return
+fn compressed_size(compressed: &CompressedTopDag, id: usize, sizes: &mut HashMap<usize, usize>) -> usize {
+    if compressed.is_label(id) {
+        assert_eq!(compressed.children(id).unwrap(), None);
+        return 1;
+    }
+    if let Some(size) = sizes.get(&id) {
+        return *size;
+    }
+    assert!(compressed.merge_type(id).is_some());
+    let (first_child, second_child) = compressed.children(id).unwrap().unwrap();
+    let size = compressed_size(compressed, first_child, sizes) + compressed_size(compressed, second_child, sizes);
+    sizes.insert(id, size);
+    size
+}
+
+#[test]
+fn compressed_queries_match_the_top_dag() {
+    let mut random = Random(5);
+    let mut trees: Vec<IO_Tree> = (0..10).map(|_| random_tree(&mut random, 8, 4, 5)).collect();
+    //distinct labels share nothing, so subtree_end has to skip blocks of the excess tables
+    trees.push(IO_Tree { label: b"list".to_vec(), children: (0..1000).map(|index| IO_Tree { label: format!("item{}", index).into_bytes(), children: VecDeque::new() }).collect() });
+
+    for tree in trees {
+        let top_dag = TopTreeBuilder::new_from_IO_tree(tree, None).unwrap().build();
+        let compressed = top_dag.to_compressed();
+        assert_eq!(compressed.number_of_nodes(), top_dag.number_of_nodes());
+
+        let mut sizes = HashMap::new();
+        assert_eq!(compressed_size(&compressed, compressed.root(), &mut sizes), top_dag.number_of_nodes());
+        //every cluster is reached from the root
+        assert_eq!(sizes.len(), compressed.number_of_clusters());
+        let past_the_end = compressed.number_of_labels() + compressed.number_of_clusters();
+        assert_eq!(compressed.children(past_the_end).unwrap(), None);
+        assert!(compressed.get_IO_tree().unwrap() == top_dag.get_IO_tree());
+    }
+}
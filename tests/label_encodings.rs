@@ -0,0 +1,98 @@
+extern crate top_tree_compression;
+
+mod common;
+
+
+use top_tree_compression::compressed::{CompressedTopDag, CompressedLabels};
+use top_tree_compression::flags_and_statistic::{LabelEncoding, PointerEncoding};
+use top_tree_compression::push_builder::PushBuilder;
+use top_tree_compression::top_dag::TopDag;
+use common::temp_path;
+
+
+
+fn links(number_of_links: usize) -> TopDag {
+    let mut push_builder = PushBuilder::new(None);
+    push_builder.open(b"links".to_vec()).unwrap();
+    for index in 0..number_of_links {
+        push_builder.open(b"a".to_vec()).unwrap();
+        push_builder.leaf(format!("https://example.org/pages/{}", index).into_bytes()).unwrap();
+        push_builder.close().unwrap();
+    }
+    push_builder.close().unwrap();
+    push_builder.finish().unwrap()
+}
+
+#[test]
+fn every_encoding_round_trips() {
+    let original = links(100);
+    let tree = original.get_IO_tree();
+    for &label_encoding in &[LabelEncoding::WaveletTree, LabelEncoding::FrontCoding, LabelEncoding::Plain] {
+        for &pointer_encoding in &[PointerEncoding::Vlc, PointerEncoding::Huffman] {
+            let path = temp_path(&format!("{:?}_{:?}", label_encoding, pointer_encoding));
+            original.save_to_file_with_encodings(&path, label_encoding, pointer_encoding);
+
+            let loaded: TopDag = TopDag::new_fom_file(&path).unwrap();
+            assert!(loaded.verify_against(&tree).is_ok());
+            let compressed = CompressedTopDag::new_fom_file(&path).unwrap();
+            assert!(compressed.verify_against(&tree).is_ok());
+        }
+    }
+}
+
+#[test]
+fn front_coded_labels_stay_compressed() {
+    let top_dag = links(200);
+    let path = temp_path("front_coded");
+    top_dag.save_to_file_with_encodings(&path, LabelEncoding::FrontCoding, PointerEncoding::Vlc);
+
+    let compressed = CompressedTopDag::new_fom_file(&path).unwrap();
+    match compressed.parts().5 {
+        CompressedLabels::FrontCoded(_) => (),
+        CompressedLabels::Store(_) => panic!("the front coded dictionary was decoded"),
+    }
+    for id in 0..compressed.number_of_labels() {
+        assert_eq!(compressed.label(id).unwrap().as_ref(), &top_dag.labels()[id][..]);
+    }
+    //the shared prefixes are only stored once
+    assert!(compressed.size_report().label_bits < top_dag.to_compressed().size_report().label_bits);
+
+    //a container stores the labels one after another
+    let mut container = Vec::new();
+    compressed.write_container(&mut container).unwrap();
+    let loaded = CompressedTopDag::from_reader(&mut &container[..], &Default::default()).unwrap();
+    assert!(loaded.verify_against(&top_dag.get_IO_tree()).is_ok());
+}
+
+
+///more than one chunk of structure words, pointers and merge types
+fn large_tree() -> TopDag {
+    let mut push_builder = PushBuilder::new(None);
+    push_builder.open(b"root".to_vec()).unwrap();
+    for index in 0..150_000 {
+        push_builder.open(format!("{}", index % 3).into_bytes()).unwrap();
+        push_builder.leaf(format!("{}", index).into_bytes()).unwrap();
+        push_builder.close().unwrap();
+    }
+    push_builder.close().unwrap();
+    push_builder.finish().unwrap()
+}
+
+#[test]
+fn large_archives_are_copied_in_chunks() {
+    for &pointer_encoding in &[PointerEncoding::Vlc, PointerEncoding::Huffman] {
+        let top_dag = large_tree();
+        assert!(top_dag.number_of_clusters() > 131_072);
+        let path = temp_path(&format!("large_{:?}", pointer_encoding));
+        top_dag.save_to_file_with_encodings(&path, LabelEncoding::WaveletTree, pointer_encoding);
+
+        let compressed = CompressedTopDag::new_fom_file(&path).unwrap();
+        assert_eq!(compressed.number_of_clusters(), top_dag.number_of_clusters());
+        assert!(compressed.verify_against(&top_dag.get_IO_tree()).is_ok());
+
+        //a pointer needs the bits of the largest id
+        let largest_id = compressed.number_of_labels() + compressed.number_of_clusters() - 1;
+        let (_length, width, _words) = compressed.parts().3.parts();
+        assert_eq!(width, 64 - (largest_id as u64).leading_zeros() as usize);
+    }
+}
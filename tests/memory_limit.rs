@@ -0,0 +1,102 @@
+extern crate top_tree_compression;
+
+use std::collections::VecDeque;
+use std::io::{self, BufReader, Cursor, Read};
+
+use top_tree_compression::TopTreeBuilder;
+use top_tree_compression::flags_and_statistic::Flags;
+use top_tree_compression::io_tree::IO_Tree;
+use top_tree_compression::memory::{MemoryEstimate, MemoryError};
+use top_tree_compression::push_builder::{PushBuilder, BuildError};
+
+
+
+fn wide_tree(number_of_leafs: usize) -> IO_Tree {
+    let leaf = || IO_Tree { label: b"b".to_vec(), children: VecDeque::new() };
+    IO_Tree { label: b"a".to_vec(), children: (0..number_of_leafs).map(|_| leaf()).collect() }
+}
+
+fn flags(memory_limit: usize, capacity_hint: Option<usize>) -> Option<Flags> {
+    Some(Flags { memory_limit: Some(memory_limit), capacity_hint, ..Flags::default() })
+}
+
+///an xml document that never ends: <a><b></b><b></b>...
+struct EndlessXml {
+    position: usize,
+}
+
+impl Read for EndlessXml {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        const START: &[u8] = b"<a>";
+        const CHILD: &[u8] = b"<b></b>";
+        for byte in buffer.iter_mut() {
+            *byte = if self.position < START.len() {
+                START[self.position]
+            } else {
+                CHILD[(self.position - START.len()) % CHILD.len()]
+            };
+            self.position += 1;
+        }
+        Ok(buffer.len())
+    }
+}
+
+#[test]
+fn tree_within_the_limit_is_built() {
+    let needed = MemoryEstimate::new(1, 1000).total();
+    let builder = TopTreeBuilder::new_from_IO_tree(wide_tree(1000), flags(needed, None)).unwrap();
+    assert_eq!(builder.build().number_of_nodes(), 1001);
+}
+
+#[test]
+fn tree_over_the_limit_is_rejected() {
+    let needed = MemoryEstimate::new(1, 1000).total();
+    match TopTreeBuilder::new_from_IO_tree(wide_tree(1000), flags(needed - 1, None)) {
+        Err(MemoryError::BudgetExceeded { needed: reported, limit }) => {
+            assert_eq!(reported, needed);
+            assert_eq!(limit, needed - 1);
+        },
+        _ => panic!("the tree was not rejected"),
+    }
+}
+
+#[test]
+fn small_capacity_hint_does_not_bypass_the_limit() {
+    let needed = MemoryEstimate::new(1, 1000).total();
+    assert!(TopTreeBuilder::new_from_IO_tree(wide_tree(1000), flags(needed - 1, Some(1))).is_err());
+    //the hint only changes the reservation
+    assert!(TopTreeBuilder::new_from_IO_tree(wide_tree(1000), flags(needed, Some(1))).is_ok());
+}
+
+#[test]
+fn xml_parsing_stops_at_the_limit() {
+    let source = BufReader::new(EndlessXml { position: 0 });
+    let error = TopTreeBuilder::from_xml_reader(source, flags(1 << 20, None)).err().expect("the endless document was accepted");
+    match error.downcast_ref::<MemoryError>() {
+        Some(MemoryError::BudgetExceeded { limit, .. }) => assert_eq!(*limit, 1 << 20),
+        _ => panic!("unexpected error {}", error),
+    }
+}
+
+#[test]
+fn xml_within_the_limit_is_parsed() {
+    let xml = b"<a><b></b><b></b><c><b></b></c></a>".to_vec();
+    let builder = TopTreeBuilder::from_xml_reader(Cursor::new(xml), flags(1 << 20, None)).unwrap();
+    assert_eq!(builder.build().number_of_nodes(), 5);
+}
+
+#[test]
+fn push_builder_stops_at_the_limit() {
+    let mut push_builder = PushBuilder::new(flags(MemoryEstimate::new(1, 100).total(), None));
+    push_builder.open(b"a".to_vec()).unwrap();
+    let mut result = Ok(());
+    let mut number_of_leafs = 0;
+    while result.is_ok() && number_of_leafs < 1000 {
+        result = push_builder.leaf(b"b".to_vec());
+        number_of_leafs += 1;
+    }
+    match result {
+        Err(BuildError::Memory(MemoryError::BudgetExceeded { .. })) => assert_eq!(number_of_leafs, 101),
+        _ => panic!("the limit was not enforced"),
+    }
+}
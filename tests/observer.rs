@@ -0,0 +1,118 @@
+extern crate top_tree_compression;
+
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+use top_tree_compression::TopTreeBuilder;
+use top_tree_compression::flags_and_statistic::{Flags, MergeRule};
+use top_tree_compression::progress::{Progress, Phase, Control, Cancelled};
+use top_tree_compression::push_builder::{PushBuilder, BuildError};
+
+
+
+fn xml(children: usize) -> Vec<u8> {
+    let mut xml = String::from("<root>");
+    for index in 0..children {
+        xml.push_str(&format!("<item><name>{}</name><list><a></a><b></b><a></a></list></item>", index % 5));
+    }
+    xml.push_str("</root>");
+    xml.into_bytes()
+}
+
+fn builder(merge_rule: MergeRule) -> TopTreeBuilder {
+    let flags = Flags { merge_rule, ..Flags::default() };
+    TopTreeBuilder::from_xml_reader(Cursor::new(xml(100)), Some(flags)).unwrap()
+}
+
+///records every event and aborts at the first event of abort_round
+fn recorder(events: &Arc<Mutex<Vec<Progress>>>, abort_round: Option<usize>) -> impl FnMut(&Progress) -> Control + Send + 'static {
+    let events = events.clone();
+    move |progress: &Progress| {
+        events.lock().unwrap().push(progress.clone());
+        if Some(progress.round) == abort_round { Control::Abort } else { Control::Continue }
+    }
+}
+
+#[test]
+fn events_follow_the_merge_rounds() {
+    for &merge_rule in &[MergeRule::SimplifiedStandardRules, MergeRule::FastAdvancedRules, MergeRule::SlowAdvancedRules] {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut builder = builder(merge_rule);
+        builder.set_observer(recorder(&events, None));
+        let top_dag = builder.build();
+        let statistic = top_dag.get_statistic();
+        let events = events.lock().unwrap();
+
+        assert_eq!(events[0].round, 1);
+        assert_eq!(events[0].phase, Phase::Horizontal);
+        for pair in events.windows(2) {
+            //a horizontal merge is followed by the vertical merge of the same round
+            match (pair[0].phase, pair[1].phase) {
+                (Phase::Horizontal, Phase::Vertical) => assert_eq!(pair[1].round, pair[0].round),
+                (Phase::Vertical, Phase::Horizontal) => assert_eq!(pair[1].round, pair[0].round + 1),
+                phases => panic!("Error: Unexpected order of phases {:?}", phases),
+            }
+            assert!(pair[1].live_nodes <= pair[0].live_nodes);
+            assert!(pair[1].number_of_clusters >= pair[0].number_of_clusters);
+        }
+
+        let last = events.last().unwrap();
+        assert_eq!(last.round, statistic.number_of_merge_rounds);
+        assert_eq!(last.live_nodes, 1);
+        assert_eq!(last.number_of_clusters, top_dag.number_of_clusters());
+    }
+}
+
+#[test]
+fn abort_stops_the_build_after_the_merge() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let mut builder = builder(MergeRule::SimplifiedStandardRules);
+    builder.set_observer(recorder(&events, Some(2)));
+
+    let cancelled: Cancelled = builder.try_build().err().expect("Error: The build was not cancelled");
+    assert_eq!(cancelled.progress.round, 2);
+    assert_eq!(cancelled.progress.phase, Phase::Horizontal);
+    assert!(cancelled.progress.live_nodes > 1);
+    assert_eq!(cancelled.to_string(), format!("The build was cancelled in round 2 with {} nodes left", cancelled.progress.live_nodes));
+
+    //the observer is not called again after it aborted
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 3);
+    assert_eq!(events.last().unwrap().round, 2);
+}
+
+#[test]
+fn verified_builds_report_the_cancellation() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let tree = builder(MergeRule::SimplifiedStandardRules).build().get_IO_tree();
+    let mut builder = builder(MergeRule::SimplifiedStandardRules);
+    builder.set_observer(recorder(&events, Some(1)));
+
+    let error = builder.build_verified(&tree).err().expect("Error: The build was not cancelled");
+    assert_eq!(error.downcast_ref::<Cancelled>().unwrap().progress.round, 1);
+}
+
+#[test]
+#[should_panic(expected = "cancelled")]
+fn build_panics_when_the_observer_aborts() {
+    let mut builder = builder(MergeRule::SimplifiedStandardRules);
+    builder.set_observer(|_: &Progress| Control::Abort);
+    builder.build();
+}
+
+#[test]
+fn push_builder_reports_the_cancellation() {
+    let mut push_builder = PushBuilder::new(None);
+    push_builder.open(b"a".to_vec()).unwrap();
+    for _ in 0..10 {
+        push_builder.leaf(b"b".to_vec()).unwrap();
+    }
+    push_builder.close().unwrap();
+    push_builder.set_observer(|_: &Progress| Control::Abort);
+
+    match push_builder.finish() {
+        Err(BuildError::Cancelled(cancelled)) => assert_eq!(cancelled.progress.round, 1),
+        Err(error) => panic!("Error: Expected a cancellation, got {:?}", error),
+        Ok(_) => panic!("Error: Expected a cancellation, got a TopDAG"),
+    }
+}
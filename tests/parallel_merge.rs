@@ -0,0 +1,77 @@
+extern crate top_tree_compression;
+
+mod common;
+
+use std::collections::VecDeque;
+
+use top_tree_compression::TopTreeBuilder;
+use top_tree_compression::flags_and_statistic::{Flags, MergeRule};
+use top_tree_compression::io_tree::IO_Tree;
+use top_tree_compression::top_dag::{TopDag, ClusterInfo};
+use common::{Random, random_tree};
+
+
+
+const RULES: [MergeRule; 3] = [MergeRule::SimplifiedStandardRules, MergeRule::FastAdvancedRules, MergeRule::SlowAdvancedRules];
+
+///a chain with a repeating pattern of wide nodes on it
+fn comb(length: usize) -> IO_Tree {
+    let mut tree = IO_Tree { label: b"end".to_vec(), children: VecDeque::new() };
+    for index in 0..length {
+        let mut children: VecDeque<IO_Tree> = vec![tree].into_iter().collect();
+        if index % 3 == 0 {
+            children.push_back(IO_Tree { label: b"leaf".to_vec(), children: VecDeque::new() });
+            children.push_back(IO_Tree { label: b"leaf".to_vec(), children: VecDeque::new() });
+        }
+        tree = IO_Tree { label: format!("{}", index % 4).into_bytes(), children };
+    }
+    tree
+}
+
+fn trees() -> Vec<IO_Tree> {
+    let mut random = Random(7);
+    let mut trees: Vec<IO_Tree> = (0..20).map(|_| random_tree(&mut random, 6, 3, 5)).collect();
+    trees.push(comb(2000));
+    trees.push(IO_Tree { label: b"single".to_vec(), children: VecDeque::new() });
+    trees
+}
+
+fn build(tree: &IO_Tree, merge_rule: MergeRule, threads: usize) -> TopDag {
+    let flags = Flags { merge_rule, threads, ..Flags::default() };
+    TopTreeBuilder::new_from_IO_tree(tree.clone(), Some(flags)).unwrap().build()
+}
+
+fn clusters(top_dag: &TopDag) -> Vec<ClusterInfo> {
+    top_dag.clusters().collect()
+}
+
+#[test]
+fn simplified_rules_build_the_same_top_dag() {
+    for tree in &trees() {
+        let sequential = build(tree, MergeRule::SimplifiedStandardRules, 1);
+        for &threads in &[2, 3, 8] {
+            let parallel = build(tree, MergeRule::SimplifiedStandardRules, threads);
+            assert_eq!(parallel.labels(), sequential.labels());
+            assert_eq!(clusters(&parallel), clusters(&sequential));
+            assert_eq!(parallel.get_statistic().number_of_merge_rounds, sequential.get_statistic().number_of_merge_rounds);
+        }
+    }
+}
+
+///in parallel the advanced rules only see the clusters from before the phase, so they can choose other merges,
+///but the TopDAG has to describe the same tree
+#[test]
+fn every_rule_builds_an_equivalent_top_dag() {
+    for tree in &trees() {
+        for &merge_rule in &RULES {
+            let sequential = build(tree, merge_rule, 1);
+            assert!(sequential.verify_against(tree).is_ok());
+            for &threads in &[2, 3, 8] {
+                let parallel = build(tree, merge_rule, threads);
+                assert!(parallel.verify_against(tree).is_ok(), "{:?} with {} threads", merge_rule, threads);
+                assert!(parallel.get_IO_tree() == sequential.get_IO_tree());
+                assert_eq!(parallel.number_of_nodes(), sequential.number_of_nodes());
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+extern crate top_tree_compression;
+
+mod common;
+
+use std::collections::VecDeque;
+
+use top_tree_compression::TopTreeBuilder;
+use top_tree_compression::flags_and_statistic::{Flags, MergeRule};
+use top_tree_compression::io_tree::IO_Tree;
+use top_tree_compression::push_builder::{PushBuilder, BuildError};
+use top_tree_compression::top_dag::{TopDag, ClusterInfo};
+use common::{Random, random_tree};
+
+
+
+const RULES: [MergeRule; 3] = [MergeRule::SimplifiedStandardRules, MergeRule::FastAdvancedRules, MergeRule::SlowAdvancedRules];
+
+fn wide_tree(number_of_children: usize) -> IO_Tree {
+    let child = |index: usize| IO_Tree { label: format!("{}", index % 5).into_bytes(), children: VecDeque::new() };
+    IO_Tree { label: b"root".to_vec(), children: (0..number_of_children).map(child).collect() }
+}
+
+fn deep_tree(depth: usize) -> IO_Tree {
+    let mut tree = IO_Tree { label: b"leaf".to_vec(), children: VecDeque::new() };
+    for index in 0..depth {
+        tree = IO_Tree { label: format!("{}", index % 3).into_bytes(), children: vec![tree].into_iter().collect() };
+    }
+    tree
+}
+
+fn trees() -> Vec<IO_Tree> {
+    let mut random = Random(11);
+    let mut trees: Vec<IO_Tree> = (0..20).map(|_| random_tree(&mut random, 6, 3, 6)).collect();
+    trees.push(wide_tree(3000));
+    trees.push(deep_tree(3000));
+    trees.push(IO_Tree { label: b"single".to_vec(), children: VecDeque::new() });
+    trees
+}
+
+///feeds the tree in preorder, the number of children is never told to the builder
+fn push(push_builder: &mut PushBuilder, tree: &IO_Tree) {
+    if tree.children.is_empty() {
+        push_builder.leaf(tree.label.clone()).unwrap();
+        return;
+    }
+
+    //(node, number of pushed children)
+    let mut stack = vec![(tree, 0)];
+    push_builder.open(tree.label.clone()).unwrap();
+    while let Some((node, next_child)) = stack.pop() {
+        match node.children.get(next_child) {
+            Some(child) => {
+                stack.push((node, next_child + 1));
+                if child.children.is_empty() {
+                    push_builder.leaf(child.label.clone()).unwrap();
+                } else {
+                    push_builder.open(child.label.clone()).unwrap();
+                    stack.push((child, 0));
+                }
+            },
+            None => push_builder.close().unwrap(),
+        }
+    }
+}
+
+fn assert_same_top_dag(pushed: &TopDag, built: &TopDag) {
+    assert_eq!(pushed.labels(), built.labels());
+    assert_eq!(pushed.clusters().collect::<Vec<ClusterInfo>>(), built.clusters().collect::<Vec<ClusterInfo>>());
+    assert_eq!(pushed.root(), built.root());
+}
+
+#[test]
+fn pushed_trees_match_new_from_io_tree() {
+    for tree in trees() {
+        for &merge_rule in RULES.iter() {
+            for &capacity_hint in &[None, Some(1), Some(100_000)] {
+                let flags = || Some(Flags { merge_rule, capacity_hint, ..Flags::default() });
+
+                let mut push_builder = PushBuilder::new(flags());
+                push(&mut push_builder, &tree);
+                let pushed = push_builder.finish().unwrap();
+
+                let built = TopTreeBuilder::new_from_IO_tree(tree.clone(), flags()).unwrap().build();
+                assert_same_top_dag(&pushed, &built);
+                assert!(pushed.verify_against(&tree).is_ok());
+            }
+        }
+    }
+}
+
+#[test]
+fn pushed_statistic_counts_the_input() {
+    let tree = trees().remove(0);
+    let mut push_builder = PushBuilder::new(None);
+    push(&mut push_builder, &tree);
+    let pushed = push_builder.finish().unwrap().get_statistic().clone();
+    let built = TopTreeBuilder::new_from_IO_tree(tree.clone(), None).unwrap().build().get_statistic().clone();
+
+    assert_eq!(pushed.number_of_nodes_in_io_tree, built.number_of_nodes_in_io_tree);
+    assert_eq!(pushed.number_of_leafs_in_io_tree, built.number_of_leafs_in_io_tree);
+    assert_eq!(pushed.number_of_edges_in_io_tree, built.number_of_edges_in_io_tree);
+    assert_eq!(pushed.number_of_labels_in_io_tree, built.number_of_labels_in_io_tree);
+    assert_eq!(pushed.depth_of_io_tree, built.depth_of_io_tree);
+}
+
+#[test]
+fn misuse_is_reported() {
+    let mut push_builder: PushBuilder = PushBuilder::new(None);
+    match push_builder.close() {
+        Err(BuildError::NoOpenNode) => (),
+        other => panic!("Error: Expected NoOpenNode, got {:?}", other),
+    }
+
+    push_builder.leaf(b"root".to_vec()).unwrap();
+    match push_builder.leaf(b"second".to_vec()) {
+        Err(BuildError::SecondRoot) => (),
+        other => panic!("Error: Expected SecondRoot, got {:?}", other),
+    }
+    match push_builder.open(b"second".to_vec()) {
+        Err(BuildError::SecondRoot) => (),
+        other => panic!("Error: Expected SecondRoot, got {:?}", other),
+    }
+
+    let mut push_builder: PushBuilder = PushBuilder::new(None);
+    push_builder.open(b"a".to_vec()).unwrap();
+    push_builder.open(b"b".to_vec()).unwrap();
+    match push_builder.finish() {
+        Err(BuildError::UnclosedNodes(2)) => (),
+        Err(error) => panic!("Error: Expected UnclosedNodes(2), got {:?}", error),
+        Ok(_) => panic!("Error: Expected UnclosedNodes(2), got a TopDAG"),
+    }
+
+    let push_builder: PushBuilder = PushBuilder::new(None);
+    match push_builder.finish() {
+        Err(BuildError::Empty) => (),
+        Err(error) => panic!("Error: Expected Empty, got {:?}", error),
+        Ok(_) => panic!("Error: Expected Empty, got a TopDAG"),
+    }
+}
@@ -0,0 +1,74 @@
+extern crate top_tree_compression;
+
+mod common;
+
+use std::io::Cursor;
+
+use top_tree_compression::TopTreeBuilder;
+use top_tree_compression::flags_and_statistic::{Flags, MergeRule};
+use top_tree_compression::io_tree::IO_Tree;
+use common::node;
+
+
+
+fn xml(children: usize) -> Vec<u8> {
+    let mut xml = String::from("<root>");
+    for index in 0..children {
+        xml.push_str(&format!("<item><name>{}</name><list><a/><b/><a/></list></item>", index % 5));
+    }
+    xml.push_str("</root>");
+    xml.into_bytes()
+}
+
+///a leaf below `length` unary nodes
+fn chain(length: usize) -> IO_Tree {
+    let mut tree = node("c", vec![]);
+    for _ in 0..length {
+        tree = node("a", vec![tree]);
+    }
+    tree
+}
+
+#[test]
+fn live_counters_match_the_input_and_the_merges() {
+    for &merge_rule in &[MergeRule::SimplifiedStandardRules, MergeRule::FastAdvancedRules, MergeRule::SlowAdvancedRules] {
+        for &threads in &[1, 4] {
+            let flags = Flags { merge_rule, threads, ..Flags::default() };
+            let top_dag = TopTreeBuilder::from_xml_reader(Cursor::new(xml(200)), Some(flags)).unwrap().build();
+            let statistic = top_dag.get_statistic();
+            let rounds = &statistic.rounds;
+
+            assert_eq!(rounds.len(), statistic.number_of_merge_rounds);
+            assert_eq!(rounds[0].live_nodes_before, statistic.number_of_nodes_in_io_tree + statistic.number_of_leafs_in_io_tree);
+            assert_eq!(rounds[0].live_edges_before, statistic.number_of_edges_in_io_tree);
+            for round in rounds {
+                //every merge removes one node and one edge
+                assert_eq!(round.live_nodes_before - round.live_nodes_after, round.merges());
+                assert_eq!(round.live_edges_before - round.live_edges_after, round.merges());
+            }
+            for pair in rounds.windows(2) {
+                assert_eq!(pair[0].live_nodes_after, pair[1].live_nodes_before);
+                assert_eq!(pair[0].live_edges_after, pair[1].live_edges_before);
+            }
+            //only the root cluster is left
+            assert_eq!(rounds.last().unwrap().live_nodes_after, 1);
+            assert_eq!(rounds.last().unwrap().live_edges_after, 0);
+        }
+    }
+}
+
+///the chain from the root ends at the branching node b, the chains below b start again from b
+#[test]
+fn simplified_vertical_merges_stop_at_branching_nodes() {
+    let tree = node("a", vec![node("b", vec![chain(4), chain(4)])]);
+    let flags = Flags { merge_rule: MergeRule::SimplifiedStandardRules, ..Flags::default() };
+    let top_dag = TopTreeBuilder::new_from_IO_tree(tree.clone(), Some(flags)).unwrap().build();
+    let statistic = top_dag.get_statistic();
+
+    //one merge above b and two in each chain of five edges below it
+    assert_eq!(statistic.rounds[0].ab_merges, 5);
+    assert_eq!(statistic.rounds[0].live_nodes_after, 7);
+    assert_eq!(statistic.number_of_merge_rounds, 4);
+    assert_eq!(top_dag.number_of_clusters(), 6);
+    assert!(top_dag.get_IO_tree() == tree);
+}
@@ -0,0 +1,68 @@
+extern crate top_tree_compression;
+
+mod common;
+
+use std::sync::Arc;
+use std::thread;
+
+use top_tree_compression::flags_and_statistic::SizeKind;
+use top_tree_compression::format::size_report;
+use top_tree_compression::top_dag::TopDag;
+use common::{sample_tree, temp_path};
+
+
+
+#[test]
+fn saving_leaves_the_statistic_unchanged() {
+    let top_dag = sample_tree(100);
+    let size_report = top_dag.save_to_file(&temp_path("statistic"));
+    assert!(top_dag.get_statistic().size_report.is_none());
+    assert_eq!(size_report.number_of_nodes, 1 + 2 * 100);
+    assert_eq!(size_report.number_of_clusters, top_dag.number_of_clusters());
+
+    //the caller adds the report to its copy of the statistic
+    let mut statistic = top_dag.get_statistic().clone();
+    statistic.size_report = Some(size_report);
+    assert!(format!("{}", statistic).contains("Bits per input node"));
+}
+
+#[test]
+fn size_reports_say_what_they_measure() {
+    let top_dag = sample_tree(100);
+    let path = temp_path("kind");
+
+    let saved = top_dag.save_to_file(&path);
+    assert_eq!(saved.kind, SizeKind::EncodedFile);
+
+    let loaded = size_report(&path).unwrap();
+    assert_eq!(loaded.kind, SizeKind::EncodedFile);
+    assert_eq!(loaded.total_bits(), saved.total_bits());
+    assert_eq!(loaded.number_of_nodes, saved.number_of_nodes);
+
+    let in_memory = top_dag.to_compressed().size_report();
+    assert_eq!(in_memory.kind, SizeKind::InMemory);
+    assert_eq!(in_memory.number_of_nodes, saved.number_of_nodes);
+    assert!(format!("{}", in_memory).starts_with("Size in memory:"));
+    assert!(format!("{}", saved).starts_with("Size of the archive files:"));
+}
+
+#[test]
+fn shared_top_dags_are_saved_from_several_threads() {
+    let top_dags = (0..8).map(|index| Arc::new(sample_tree(10 + index * 10))).collect::<Vec<_>>();
+
+    let handles = top_dags.iter().enumerate().map(|(index, top_dag)| {
+        let top_dag = top_dag.clone();
+        thread::spawn(move || {
+            let path = temp_path(&format!("thread_{}", index));
+            for _ in 0..5 {
+                top_dag.save_to_file(&path);
+                let loaded: TopDag = TopDag::new_fom_file(&path).unwrap();
+                assert!(loaded.verify_against(&top_dag.get_IO_tree()).is_ok());
+            }
+        })
+    }).collect::<Vec<_>>();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
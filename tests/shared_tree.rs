@@ -0,0 +1,79 @@
+extern crate top_tree_compression;
+
+mod common;
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use top_tree_compression::TopTreeBuilder;
+use top_tree_compression::flags_and_statistic::{Flags, MergeRule};
+use top_tree_compression::io_tree::IO_Tree;
+use top_tree_compression::shared_tree::SharedTree;
+use common::{Random, random_tree};
+
+
+
+const RULES: [MergeRule; 3] = [MergeRule::SimplifiedStandardRules, MergeRule::FastAdvancedRules, MergeRule::SlowAdvancedRules];
+
+fn full_binary_tree(depth: usize) -> IO_Tree {
+    let children = if depth == 0 { VecDeque::new() } else { vec![full_binary_tree(depth - 1), full_binary_tree(depth - 1)].into_iter().collect() };
+    IO_Tree { label: b"n".to_vec(), children }
+}
+
+///numbers every distinct subtree of the input, returns the id of the tree
+fn distinct_subtrees(tree: &IO_Tree, ids: &mut HashMap<(Vec<u8>, Vec<usize>), usize>) -> usize {
+    let children: Vec<usize> = tree.children.iter().map(|child| distinct_subtrees(child, ids)).collect();
+    let next_id = ids.len();
+    *ids.entry((tree.label.clone(), children)).or_insert(next_id)
+}
+
+fn assert_minimal(shared_tree: &SharedTree<Vec<u8>>) {
+    assert_eq!(shared_tree.root(), shared_tree.number_of_nodes() - 1);
+
+    let mut nodes = BTreeSet::new();
+    for node in 0..shared_tree.number_of_nodes() {
+        assert!(shared_tree.children(node).iter().all(|child| *child < node));
+        //an equal subtree would have the same label and the same children
+        assert!(nodes.insert((shared_tree.label(node).clone(), shared_tree.children(node).to_vec())));
+    }
+}
+
+#[test]
+fn shared_trees_expand_to_the_input() {
+    let mut random = Random(41);
+    for _ in 0..20 {
+        let tree = random_tree(&mut random, 6, 2, 4);
+        let mut ids = HashMap::new();
+        distinct_subtrees(&tree, &mut ids);
+
+        for &merge_rule in RULES.iter() {
+            let flags = Flags { merge_rule, ..Flags::default() };
+            let top_dag = TopTreeBuilder::new_from_IO_tree(tree.clone(), Some(flags)).unwrap().build();
+
+            let shared_tree = top_dag.get_shared_tree();
+            assert!(shared_tree.to_IO_tree() == tree);
+            assert_eq!(shared_tree.number_of_nodes(), ids.len());
+            assert_eq!(shared_tree.expanded_size(shared_tree.root()), top_dag.number_of_nodes());
+            assert_minimal(&shared_tree);
+
+            let compressed = top_dag.to_compressed().get_shared_tree().unwrap();
+            assert!(compressed.to_IO_tree() == tree);
+            assert_eq!(compressed.number_of_nodes(), ids.len());
+            assert_minimal(&compressed);
+        }
+    }
+}
+
+#[test]
+fn memory_depends_on_the_distinct_subtrees() {
+    let depth = 16;
+    let top_dag = TopTreeBuilder::new_from_IO_tree(full_binary_tree(depth), None).unwrap().build();
+    let shared_tree = top_dag.get_shared_tree();
+
+    //one node for every level
+    assert_eq!(shared_tree.number_of_nodes(), depth + 1);
+    assert_eq!(shared_tree.expanded_size(shared_tree.root()), (1 << (depth + 1)) - 1);
+    for node in 1..shared_tree.number_of_nodes() {
+        assert_eq!(shared_tree.children(node), &[node - 1, node - 1][..]);
+        assert_eq!(shared_tree.expanded_size(node), (1 << (node + 1)) - 1);
+    }
+}
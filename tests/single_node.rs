@@ -0,0 +1,87 @@
+extern crate top_tree_compression;
+
+mod common;
+
+use std::io::Cursor;
+
+use top_tree_compression::TopTreeBuilder;
+use top_tree_compression::compressed::CompressedTopDag;
+use top_tree_compression::push_builder::PushBuilder;
+use top_tree_compression::top_dag::TopDag;
+use top_tree_compression::validation::{DecodeLimits, DecodeError};
+use common::temp_path;
+
+
+
+fn single_node() -> TopDag {
+    let mut push_builder = PushBuilder::new(None);
+    push_builder.leaf(b"a".to_vec()).unwrap();
+    push_builder.finish().unwrap()
+}
+
+fn assert_single_node(top_dag: &TopDag) {
+    assert_eq!(top_dag.number_of_clusters(), 0);
+    assert_eq!(top_dag.number_of_labels(), 1);
+    assert_eq!(top_dag.root(), 0);
+    assert_eq!(top_dag.number_of_nodes(), 1);
+    assert_eq!(top_dag.shape().height, 0);
+
+    let tree = top_dag.get_IO_tree();
+    assert_eq!(tree.label, b"a".to_vec());
+    assert!(tree.children.is_empty());
+    assert!(top_dag.verify_against(&tree).is_ok());
+    assert_eq!(top_dag.get_shared_tree().root(), 0);
+}
+
+#[test]
+fn push_builder_with_a_single_leaf() {
+    let top_dag = single_node();
+    assert_single_node(&top_dag);
+    assert_eq!(top_dag.get_statistic().number_of_merge_rounds, 0);
+    assert_eq!(top_dag.get_statistic().number_of_leafs_in_io_tree, 1);
+}
+
+#[test]
+fn xml_with_a_single_element() {
+    let top_dag = TopTreeBuilder::from_xml_reader(Cursor::new(b"<a></a>".to_vec()), None).unwrap().build();
+    assert_single_node(&top_dag);
+}
+
+#[test]
+fn archive_of_a_single_node_round_trips() {
+    let path = temp_path("archive");
+    let size_report = single_node().save_to_file(&path);
+    assert_eq!(size_report.number_of_nodes, 1);
+    assert_eq!(size_report.number_of_clusters, 0);
+
+    let top_dag: TopDag = TopDag::new_fom_file(&path).unwrap();
+    assert_single_node(&top_dag);
+}
+
+#[test]
+fn container_of_a_single_node_round_trips() {
+    let compressed = single_node().to_compressed();
+    assert_eq!(compressed.root(), 0);
+    assert_eq!(compressed.number_of_nodes(), 1);
+
+    let mut bytes = Vec::new();
+    compressed.write_container(&mut bytes).unwrap();
+    let loaded = CompressedTopDag::from_reader(&mut Cursor::new(bytes), &DecodeLimits::default()).unwrap();
+    assert_eq!(loaded.get_IO_tree().unwrap().label, b"a".to_vec());
+    assert!(loaded.verify_against(&single_node().get_IO_tree()).is_ok());
+}
+
+#[test]
+fn forged_single_node_streams_are_rejected() {
+    let limits = DecodeLimits::default();
+    let load = |structure: Vec<bool>, pointer: Vec<usize>, label: Vec<Vec<u8>>| {
+        TopDag::<Vec<u8>>::from_streams(structure, pointer, Vec::new(), label, &limits)
+            .err().expect("forged streams were accepted")
+    };
+    let is = |error: Box<std::error::Error>, expected: &str| format!("{:?}", error.downcast_ref::<DecodeError>().unwrap()) == expected;
+
+    assert!(is(load(Vec::new(), vec![0], Vec::new()), "Empty"));
+    assert!(is(load(Vec::new(), Vec::new(), vec![b"a".to_vec()]), "LengthMismatch"));
+    assert!(is(load(vec![false, false], vec![0], vec![b"a".to_vec()]), "LengthMismatch"));
+    assert!(is(load(Vec::new(), vec![1], vec![b"a".to_vec(), b"b".to_vec()]), "InvalidRoot"));
+}
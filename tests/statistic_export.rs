@@ -0,0 +1,314 @@
+extern crate top_tree_compression;
+
+use std::io::Cursor;
+use std::time::Duration;
+
+use top_tree_compression::TopTreeBuilder;
+use top_tree_compression::flags_and_statistic::{Statistic, SizeReport, SizeKind, STATISTIC_SCHEMA_VERSION};
+
+
+
+///the columns scripts depend on, a change here needs a new STATISTIC_SCHEMA_VERSION
+const CSV_HEADER: &str = "schema_version,xml_parsing_ns,io_tree_parsing_ns,horizontal_merges_ns,vertical_merges_ns,traverse_ns,detraverse_ns,\
+decompression_ns,number_of_merge_rounds,number_of_nodes_in_io_tree,number_of_leafs_in_io_tree,number_of_edges_in_io_tree,number_of_leafs_in_top_dag,\
+number_of_nodes_in_top_dag,height,max_cluster_size,number_of_nodes,number_of_labels_in_io_tree,depth_of_io_tree,\
+node_compression_ratio,edge_compression_ratio,size_kind,structure_bits,pointer_bits,merge_type_bits,label_bits,\
+merge_timestamps_ns,rounds,sharing_histogram";
+
+///the columns after them are empty or lists
+const NUMBER_COLUMNS: usize = 21;
+
+const JSON_KEYS: [&str; 22] = [
+    "schema_version", "xml_parsing_ns", "io_tree_parsing_ns", "horizontal_merges_ns", "vertical_merges_ns", "traverse_ns", "detraverse_ns",
+    "decompression_ns", "number_of_merge_rounds", "number_of_nodes_in_io_tree", "number_of_leafs_in_io_tree", "number_of_edges_in_io_tree",
+    "number_of_labels_in_io_tree", "depth_of_io_tree", "number_of_leafs_in_top_dag", "number_of_nodes_in_top_dag",
+    "node_compression_ratio", "edge_compression_ratio", "merge_timestamps_ns", "rounds", "shape", "size_report",
+];
+
+const ROUND_KEYS: [&str; 11] = [
+    "round", "live_nodes_before", "live_nodes_after", "live_edges_before", "live_edges_after",
+    "ab_merges", "ce_merges", "de_merges", "slowed_down_merges", "new_clusters", "reused_clusters",
+];
+
+const SHAPE_KEYS: [&str; 4] = ["height", "max_cluster_size", "number_of_nodes", "sharing_histogram"];
+
+const SIZE_REPORT_KEYS: [&str; 7] = ["kind", "structure_bits", "pointer_bits", "merge_type_bits", "label_bits", "number_of_nodes", "number_of_clusters"];
+
+///the subset of JSON the export uses
+#[derive(Debug)]
+enum Json {
+    Null,
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn number(&self) -> f64 {
+        match self {
+            Json::Number(number) => *number,
+            other => panic!("Error: Expected a number, got {:?}", other),
+        }
+    }
+
+    fn string(&self) -> &str {
+        match self {
+            Json::String(string) => string,
+            other => panic!("Error: Expected a string, got {:?}", other),
+        }
+    }
+
+    fn array(&self) -> &[Json] {
+        match self {
+            Json::Array(elements) => elements,
+            other => panic!("Error: Expected an array, got {:?}", other),
+        }
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        match self {
+            Json::Object(members) => members.iter().map(|(key, _)| key.as_str()).collect(),
+            other => panic!("Error: Expected an object, got {:?}", other),
+        }
+    }
+
+    fn get(&self, key: &str) -> &Json {
+        match self {
+            Json::Object(members) => &members.iter().find(|(name, _)| name == key).expect("Error: Missing key").1,
+            other => panic!("Error: Expected an object, got {:?}", other),
+        }
+    }
+}
+
+fn parse_json(input: &str) -> Json {
+    let bytes = input.as_bytes();
+    let mut position = 0;
+    let value = parse_value(bytes, &mut position);
+    assert_eq!(position, bytes.len(), "Error: Trailing characters in {}", input);
+    value
+}
+
+fn parse_value(bytes: &[u8], position: &mut usize) -> Json {
+    match bytes[*position] {
+        b'{' => {
+            *position += 1;
+            let mut members = Vec::new();
+            while bytes[*position] != b'}' {
+                if !members.is_empty() { expect(bytes, position, b','); }
+                let key = parse_string(bytes, position);
+                expect(bytes, position, b':');
+                members.push((key, parse_value(bytes, position)));
+            }
+            *position += 1;
+            Json::Object(members)
+        },
+
+        b'[' => {
+            *position += 1;
+            let mut elements = Vec::new();
+            while bytes[*position] != b']' {
+                if !elements.is_empty() { expect(bytes, position, b','); }
+                elements.push(parse_value(bytes, position));
+            }
+            *position += 1;
+            Json::Array(elements)
+        },
+
+        b'"' => Json::String(parse_string(bytes, position)),
+
+        b'n' => {
+            assert_eq!(&bytes[*position..*position + 4], b"null");
+            *position += 4;
+            Json::Null
+        },
+
+        _ => {
+            let start = *position;
+            while *position < bytes.len() && (bytes[*position] == b'-' || bytes[*position] == b'.' || bytes[*position] == b'e' || bytes[*position].is_ascii_digit()) {
+                *position += 1;
+            }
+            let number = std::str::from_utf8(&bytes[start..*position]).unwrap();
+            Json::Number(number.parse().unwrap_or_else(|_| panic!("Error: {:?} is not a JSON number", number)))
+        },
+    }
+}
+
+fn parse_string(bytes: &[u8], position: &mut usize) -> String {
+    expect(bytes, position, b'"');
+    let start = *position;
+    while bytes[*position] != b'"' {
+        assert!(bytes[*position] != b'\\', "Error: The strings need no escaping");
+        *position += 1;
+    }
+    *position += 1;
+    String::from_utf8(bytes[start..*position - 1].to_vec()).unwrap()
+}
+
+fn expect(bytes: &[u8], position: &mut usize, byte: u8) {
+    assert_eq!(bytes[*position] as char, byte as char);
+    *position += 1;
+}
+
+fn built_statistic() -> Statistic {
+    let mut xml = String::from("<root>");
+    for index in 0..50 {
+        xml.push_str(&format!("<item><name>{}</name><value></value></item>", index % 4));
+    }
+    xml.push_str("</root>");
+    TopTreeBuilder::from_xml_reader(Cursor::new(xml.into_bytes()), None).unwrap().build().get_statistic().clone()
+}
+
+#[test]
+fn csv_header_is_stable() {
+    assert_eq!(STATISTIC_SCHEMA_VERSION, 2);
+    assert_eq!(Statistic::csv_header(), CSV_HEADER);
+}
+
+#[test]
+fn csv_row_matches_the_header() {
+    let statistic = built_statistic();
+    let header = Statistic::csv_header();
+    let row = statistic.to_csv_row();
+    let columns: Vec<&str> = header.split(',').collect();
+    let values: Vec<&str> = row.split(',').collect();
+    assert_eq!(values.len(), columns.len());
+
+    let value = |column: &str| -> f64 {
+        values[columns.iter().position(|name| *name == column).unwrap()].parse().unwrap()
+    };
+    assert_eq!(value("schema_version"), STATISTIC_SCHEMA_VERSION as f64);
+    assert_eq!(value("number_of_merge_rounds"), statistic.number_of_merge_rounds as f64);
+    assert_eq!(value("number_of_nodes_in_io_tree"), statistic.number_of_nodes_in_io_tree as f64);
+    assert_eq!(value("number_of_leafs_in_io_tree"), statistic.number_of_leafs_in_io_tree as f64);
+    assert_eq!(value("number_of_edges_in_io_tree"), statistic.number_of_edges_in_io_tree as f64);
+    assert_eq!(value("number_of_nodes_in_top_dag"), statistic.number_of_nodes_in_top_dag as f64);
+    assert_eq!(value("height"), statistic.shape.height as f64);
+    assert_eq!(value("node_compression_ratio"), statistic.node_compression_ratio());
+
+    let list = |column: &str| -> Vec<Vec<f64>> {
+        let records = values[columns.iter().position(|name| *name == column).unwrap()];
+        records.split(';').filter(|record| !record.is_empty()).map(|record| record.split(':').map(|field| field.parse().unwrap()).collect()).collect()
+    };
+    let timestamps = list("merge_timestamps_ns");
+    assert_eq!(timestamps.len(), statistic.timestamps_vector.len());
+    assert!(timestamps.iter().all(|record| record.len() == 2));
+    let rounds = list("rounds");
+    assert_eq!(rounds.len(), statistic.rounds.len());
+    for (round, expected) in rounds.iter().zip(&statistic.rounds) {
+        assert_eq!(round.len(), ROUND_KEYS.len());
+        assert_eq!(round[0], expected.round as f64);
+        assert_eq!(round[5], expected.ab_merges as f64);
+        assert_eq!(round[10], expected.reused_clusters as f64);
+    }
+    let histogram = list("sharing_histogram");
+    assert_eq!(histogram, statistic.shape.sharing_histogram.iter().map(|(parents, clusters)| vec![*parents as f64, *clusters as f64]).collect::<Vec<_>>());
+}
+
+#[test]
+fn json_has_the_documented_keys() {
+    let statistic = built_statistic();
+    let json = parse_json(&statistic.to_json());
+    assert_eq!(json.keys(), JSON_KEYS.to_vec());
+
+    assert_eq!(json.get("schema_version").number(), STATISTIC_SCHEMA_VERSION as f64);
+    assert_eq!(json.get("number_of_nodes_in_io_tree").number(), statistic.number_of_nodes_in_io_tree as f64);
+    assert_eq!(json.get("depth_of_io_tree").number(), statistic.depth_of_io_tree as f64);
+    assert_eq!(json.get("edge_compression_ratio").number(), statistic.edge_compression_ratio());
+
+    let timestamps = json.get("merge_timestamps_ns").array();
+    assert_eq!(timestamps.len(), statistic.timestamps_vector.len());
+    assert!(timestamps.iter().all(|pair| pair.array().len() == 2));
+
+    let rounds = json.get("rounds").array();
+    assert_eq!(rounds.len(), statistic.rounds.len());
+    for (round, expected) in rounds.iter().zip(&statistic.rounds) {
+        assert_eq!(round.keys(), ROUND_KEYS.to_vec());
+        assert_eq!(round.get("round").number(), expected.round as f64);
+        assert_eq!(round.get("new_clusters").number(), expected.new_clusters as f64);
+    }
+
+    let shape = json.get("shape");
+    assert_eq!(shape.keys(), SHAPE_KEYS.to_vec());
+    let histogram = shape.get("sharing_histogram").array();
+    assert_eq!(histogram.len(), statistic.shape.sharing_histogram.len());
+    for (pair, (parents, clusters)) in histogram.iter().zip(&statistic.shape.sharing_histogram) {
+        assert_eq!(pair.array()[0].number(), *parents as f64);
+        assert_eq!(pair.array()[1].number(), *clusters as f64);
+    }
+
+    //nothing was saved yet
+    match json.get("size_report") {
+        Json::Null => (),
+        other => panic!("Error: Expected null, got {:?}", other),
+    }
+}
+
+#[test]
+fn size_report_is_exported() {
+    let mut statistic = built_statistic();
+    statistic.size_report = Some(SizeReport {
+        kind: SizeKind::InMemory,
+        structure_bits: 11,
+        pointer_bits: 12,
+        merge_type_bits: 13,
+        label_bits: 14,
+        number_of_nodes: 150,
+        number_of_clusters: 16,
+    });
+
+    let json = parse_json(&statistic.to_json());
+    let size_report = json.get("size_report");
+    assert_eq!(size_report.keys(), SIZE_REPORT_KEYS.to_vec());
+    assert_eq!(size_report.get("kind").string(), "in_memory");
+    let numbers: Vec<f64> = SIZE_REPORT_KEYS[1..].iter().map(|key| size_report.get(key).number()).collect();
+    assert_eq!(numbers, vec![11.0, 12.0, 13.0, 14.0, 150.0, 16.0]);
+
+    let columns = CSV_HEADER.split(',').collect::<Vec<_>>();
+    let row = statistic.to_csv_row();
+    let values = row.split(',').collect::<Vec<_>>();
+    let size_kind = columns.iter().position(|name| *name == "size_kind").unwrap();
+    assert_eq!(values[size_kind..size_kind + 5].to_vec(), vec!["in_memory", "11", "12", "13", "14"]);
+
+    statistic.size_report.as_mut().unwrap().kind = SizeKind::EncodedFile;
+    assert_eq!(parse_json(&statistic.to_json()).get("size_report").get("kind").string(), "encoded_file");
+}
+
+#[test]
+fn durations_are_exported_in_nanoseconds() {
+    let mut statistic = Statistic::new();
+    statistic.time_for_xml_parsing = Duration::new(2, 5);
+    statistic.time_for_traverse = Duration::from_millis(3);
+    statistic.time_for_decompression = Duration::new(0, 42);
+    statistic.timestamps_vector = vec![(Duration::new(1, 1), Duration::new(0, 7)), (Duration::new(0, 9), Duration::new(1, 0))];
+
+    let json = parse_json(&statistic.to_json());
+    assert_eq!(json.get("xml_parsing_ns").number(), 2_000_000_005.0);
+    assert_eq!(json.get("traverse_ns").number(), 3_000_000.0);
+    assert_eq!(json.get("horizontal_merges_ns").number(), 1_000_000_010.0);
+    assert_eq!(json.get("vertical_merges_ns").number(), 1_000_000_007.0);
+    assert_eq!(json.get("decompression_ns").number(), 42.0);
+    assert_eq!(json.get("merge_timestamps_ns").array()[1].array()[0].number(), 9.0);
+
+    let row = statistic.to_csv_row();
+    let values: Vec<&str> = row.split(',').collect();
+    assert_eq!(values[1], "2000000005");
+    assert_eq!(values[3], "1000000010");
+    assert_eq!(values[7], "42");
+    assert_eq!(values[values.len() - 3], "1000000001:7;9:1000000000");
+}
+
+#[test]
+fn an_empty_statistic_is_valid_json() {
+    //the ratios of an empty statistic must not be NaN, that is no JSON number
+    let statistic = Statistic::new();
+    let json = parse_json(&statistic.to_json());
+    assert!(json.get("node_compression_ratio").number().is_finite());
+    assert!(json.get("edge_compression_ratio").number().is_finite());
+    assert!(json.get("rounds").array().is_empty());
+
+    let row = statistic.to_csv_row();
+    let values: Vec<&str> = row.split(',').collect();
+    assert!(values[..NUMBER_COLUMNS].iter().all(|value| value.parse::<f64>().map(|value| value.is_finite()).unwrap_or(false)));
+    assert!(values[NUMBER_COLUMNS..].iter().all(|value| value.is_empty()));
+}
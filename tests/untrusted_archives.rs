@@ -0,0 +1,71 @@
+extern crate top_tree_compression;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::os::unix::fs::symlink;
+use std::process::Command;
+
+use top_tree_compression::push_builder::PushBuilder;
+use top_tree_compression::top_dag::TopDag;
+use top_tree_compression::compressed::CompressedTopDag;
+use top_tree_compression::validation::DecodeLimits;
+
+
+
+fn directory(name: &str) -> String {
+    let directory = env::temp_dir().join(format!("top_tree_untrusted_{}", name));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory.to_str().unwrap().to_owned()
+}
+
+fn small_tree() -> TopDag {
+    let mut push_builder = PushBuilder::new(None);
+    push_builder.open(b"a".to_vec()).unwrap();
+    push_builder.leaf(b"b".to_vec()).unwrap();
+    push_builder.leaf(b"c".to_vec()).unwrap();
+    push_builder.close().unwrap();
+    push_builder.finish().unwrap()
+}
+
+#[test]
+fn loading_keeps_the_archive_unchanged() {
+    let path = format!("{}/archive", directory("unchanged"));
+    small_tree().save_to_file(&path);
+    let bytes = fs::read(format!("{}.tar", path)).unwrap();
+
+    let top_dag: TopDag = TopDag::new_fom_file(&path).unwrap();
+    assert!(top_dag.verify_against(&small_tree().get_IO_tree()).is_ok());
+    assert!(CompressedTopDag::new_fom_file(&format!("{}.tar", path)).is_ok());
+
+    assert_eq!(fs::read(format!("{}.tar", path)).unwrap(), bytes);
+    assert!(fs::metadata(&path).is_err());
+}
+
+#[test]
+fn garbage_is_rejected() {
+    let path = format!("{}/archive", directory("garbage"));
+    File::create(format!("{}.tar", path)).unwrap().write_all(b"no tar at all").unwrap();
+
+    assert!(TopDag::<Vec<u8>>::new_fom_file_with_limits(&path, &DecodeLimits::default()).is_err());
+    assert!(CompressedTopDag::new_fom_file_with_limits(&path, &DecodeLimits::default()).is_err());
+}
+
+#[test]
+fn links_in_the_archive_are_rejected() {
+    let directory = directory("links");
+    let path = format!("{}/archive", directory);
+    small_tree().save_to_file(&path);
+
+    //replace one section by a link to a file outside of the archive
+    let status = Command::new("tar").args(&["-xf", "archive.tar"]).current_dir(&directory).status().unwrap();
+    assert!(status.success());
+    fs::remove_file(format!("{}/structure.sdsl", path)).unwrap();
+    symlink("/etc/hostname", format!("{}/structure.sdsl", path)).unwrap();
+    let status = Command::new("tar").args(&["-cf", "archive.tar", "archive"]).current_dir(&directory).status().unwrap();
+    assert!(status.success());
+    fs::remove_dir_all(&path).unwrap();
+
+    assert!(TopDag::<Vec<u8>>::new_fom_file(&path).is_err());
+}
@@ -0,0 +1,152 @@
+extern crate top_tree_compression;
+
+mod common;
+
+use std::fs::{self, File};
+use std::io::Write;
+
+use top_tree_compression::TopTreeBuilder;
+use top_tree_compression::io_tree::{IO_Tree, GenError};
+use top_tree_compression::top_dag::TopDag;
+use top_tree_compression::verify::{Mismatch, MismatchKind};
+use common::{node, temp_path};
+
+
+
+///a(b(c, d), e, b(c, d)), the second b is shared with the first one
+fn input() -> IO_Tree {
+    node("a", vec![
+        node("b", vec![node("c", vec![]), node("d", vec![])]),
+        node("e", vec![]),
+        node("b", vec![node("c", vec![]), node("d", vec![])]),
+    ])
+}
+
+const INPUT_XML: &str = "<a><b><c></c><d></d></b><e></e><b><c></c><d></d></b></a>";
+
+fn top_dag() -> TopDag {
+    TopTreeBuilder::new_from_IO_tree(input(), None).unwrap().build()
+}
+
+fn write_xml(name: &str, xml: &str) -> String {
+    let path = temp_path(name);
+    File::create(&path).unwrap().write_all(xml.as_bytes()).unwrap();
+    path
+}
+
+fn assert_mismatch(error: GenError, path: Vec<usize>, kind: MismatchKind) {
+    let mismatch = error.downcast_ref::<Mismatch>().expect("Error: expected a mismatch");
+    assert_eq!(mismatch.path, path);
+    assert_eq!(mismatch.kind, kind);
+}
+
+#[test]
+fn equal_trees_verify() {
+    let top_dag = top_dag();
+    assert!(top_dag.verify_against(&input()).is_ok());
+    assert!(top_dag.to_compressed().verify_against(&input()).is_ok());
+
+    let builder = TopTreeBuilder::new_from_IO_tree(input(), None).unwrap();
+    assert!(builder.build_verified(&input()).is_ok());
+}
+
+#[test]
+fn a_changed_label_is_reported_at_its_node() {
+    let top_dag = top_dag();
+
+    let mut tree = input();
+    tree.children[2].children[1].label = b"x".to_vec();
+    let mismatch = top_dag.verify_against(&tree).unwrap_err();
+    assert_eq!(mismatch.path, vec![2, 1]);
+    assert_eq!(mismatch.kind, MismatchKind::Label);
+
+    let mut tree = input();
+    tree.label = b"x".to_vec();
+    let mismatch = top_dag.verify_against(&tree).unwrap_err();
+    assert_eq!(mismatch.path, Vec::<usize>::new());
+    assert_eq!(mismatch.kind, MismatchKind::Label);
+}
+
+#[test]
+fn a_removed_child_is_a_missing_node() {
+    let top_dag = top_dag();
+
+    let mut tree = input();
+    tree.children[0].children.pop_back();
+    let mismatch = top_dag.verify_against(&tree).unwrap_err();
+    assert_eq!(mismatch.path, vec![0, 1]);
+    assert_eq!(mismatch.kind, MismatchKind::MissingNode);
+
+    let mut tree = input();
+    tree.children.pop_back();
+    let error = top_dag.to_compressed().verify_against(&tree).unwrap_err();
+    let mismatch = error.downcast_ref::<Mismatch>().unwrap();
+    assert_eq!(mismatch.path, vec![2]);
+    assert_eq!(mismatch.kind, MismatchKind::MissingNode);
+}
+
+#[test]
+fn an_added_child_is_an_extra_node() {
+    let top_dag = top_dag();
+
+    let mut tree = input();
+    tree.children[1].children.push_back(node("f", vec![]));
+    let mismatch = top_dag.verify_against(&tree).unwrap_err();
+    assert_eq!(mismatch.path, vec![1, 0]);
+    assert_eq!(mismatch.kind, MismatchKind::ExtraNode);
+
+    let mut tree = input();
+    tree.children.push_back(node("f", vec![]));
+    let error = top_dag.to_compressed().verify_against(&tree).unwrap_err();
+    let mismatch = error.downcast_ref::<Mismatch>().unwrap();
+    assert_eq!(mismatch.path, vec![3]);
+    assert_eq!(mismatch.kind, MismatchKind::ExtraNode);
+}
+
+#[test]
+fn build_verified_reports_the_mismatch() {
+    let mut tree = input();
+    tree.children[0].children[0].label = b"x".to_vec();
+
+    let builder = TopTreeBuilder::new_from_IO_tree(input(), None).unwrap();
+    assert_mismatch(builder.build_verified(&tree).unwrap_err(), vec![0, 0], MismatchKind::Label);
+}
+
+#[test]
+fn streamed_xml_reports_the_same_paths() {
+    let top_dag = top_dag();
+
+    let path = write_xml("equal.xml", INPUT_XML);
+    assert!(top_dag.verify_against_xml(&path).is_ok());
+    assert!(TopTreeBuilder::new_from_xml(&path, None).unwrap().build_verified_xml(&path).is_ok());
+
+    let cases = [
+        ("label.xml", "<a><b><c></c><x></x></b><e></e><b><c></c><d></d></b></a>", vec![0, 1], MismatchKind::Label),
+        ("missing.xml", "<a><b><c></c><d></d></b><e></e></a>", vec![2], MismatchKind::MissingNode),
+        ("extra.xml", "<a><b><c></c><d></d></b><e><f></f></e><b><c></c><d></d></b></a>", vec![1, 0], MismatchKind::ExtraNode),
+    ];
+    for &(name, xml, ref expected_path, kind) in cases.iter() {
+        let path = write_xml(name, xml);
+        assert_mismatch(top_dag.verify_against_xml(&path).unwrap_err(), expected_path.clone(), kind);
+        assert_mismatch(top_dag.to_compressed().verify_against_xml(&path).unwrap_err(), expected_path.clone(), kind);
+        fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn loaded_archives_are_verified() {
+    let archive = temp_path("archive");
+    top_dag().save_to_file(&archive);
+
+    assert!(TopDag::new_fom_file_verified(&archive, &input()).is_ok());
+
+    let mut tree = input();
+    tree.children.pop_front();
+    assert_mismatch(TopDag::new_fom_file_verified(&archive, &tree).err().unwrap(), vec![0], MismatchKind::Label);
+
+    let path = write_xml("archive.xml", "<a><b><c></c><d></d></b><e></e><b><c></c><d></d></b><f></f></a>");
+    assert_mismatch(TopDag::new_fom_file_verified_xml(&archive, &path).err().unwrap(), vec![3], MismatchKind::ExtraNode);
+    assert!(TopDag::new_fom_file_verified_xml(&archive, &write_xml("archive_equal.xml", INPUT_XML)).is_ok());
+
+    fs::remove_file(format!("{}.tar", archive)).unwrap();
+}